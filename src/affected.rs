@@ -1,19 +1,28 @@
 //! Affected tests detection
 //!
 //! Maps source files to their associated tests for automatic re-running
-//! when files change.
+//! when files change. Tests recorded by `runx run --coverage-map` are
+//! selected by the files they executed; the others by their module path.
 
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
+use crate::coverage::TestCoverage;
 use crate::test_model::TestNode;
 
+/// A test: its target label ("" when unknown) and its name
+type TestKey = (String, String);
+
 /// Maps source files to affected tests
 pub struct AffectedTestsMap {
-    /// Map from source file path to test names that depend on it
-    file_to_tests: HashMap<String, Vec<String>>,
+    /// Map from source file path to tests that depend on it
+    file_to_tests: HashMap<String, Vec<TestKey>>,
     /// Set of all test file paths
     test_files: HashSet<String>,
+    /// Map from source file path to the mapped tests that executed it
+    covered_files: HashMap<String, HashSet<TestKey>>,
+    /// Tests with a coverage map, which heuristics no longer select
+    mapped_tests: HashSet<TestKey>,
 }
 
 impl AffectedTestsMap {
@@ -21,11 +30,14 @@ impl AffectedTestsMap {
         Self {
             file_to_tests: HashMap::new(),
             test_files: HashSet::new(),
+            covered_files: HashMap::new(),
+            mapped_tests: HashSet::new(),
         }
     }
 
     /// Build the affected tests map from a test tree
-    /// This is a heuristic based approach:
+    /// Tests are affected by changes to the file they are defined in, plus
+    /// a heuristic based on their module path:
     /// - Tests in src/foo.rs are affected by changes to src/foo.rs
     /// - Tests in tests/foo_test.rs are affected by changes to src/foo.rs
     /// - Module tests (mod tests) are affected by changes to the parent module
@@ -33,6 +45,11 @@ impl AffectedTestsMap {
         let mut map = Self::new();
 
         for test in tree.all_tests() {
+            let key = (
+                test.target.as_ref().map(|t| t.qualified_label()).unwrap_or_default(),
+                test.full_name.clone(),
+            );
+
             // Parse test module path to infer source file
             let source_paths = infer_source_paths(&test.module_path, project_dir);
 
@@ -40,39 +57,76 @@ impl AffectedTestsMap {
                 map.file_to_tests
                     .entry(path.clone())
                     .or_default()
-                    .push(test.full_name.clone());
+                    .push(key.clone());
             }
 
             // Also track test files themselves
             if let Some(ref test_file) = test.source_file {
-                map.test_files.insert(test_file.clone());
+                let test_file = normalize_path(test_file);
+                map.file_to_tests
+                    .entry(test_file.clone())
+                    .or_default()
+                    .push(key);
+                map.test_files.insert(test_file);
             }
         }
 
         map
     }
 
+    /// Select the tests of a per-test coverage map by the files they executed
+    pub fn with_coverage(mut self, coverage: &[TestCoverage]) -> Self {
+        for test in coverage {
+            let key = (test.target.clone().unwrap_or_default(), test.test_name.clone());
+            for file in &test.files {
+                self.covered_files
+                    .entry(normalize_path(&file.path))
+                    .or_default()
+                    .insert(key.clone());
+            }
+            self.mapped_tests.insert(key);
+        }
+        self
+    }
+
     /// Find tests affected by a file change
     pub fn find_affected_tests(&self, changed_file: &str) -> Vec<String> {
         let normalized = normalize_path(changed_file);
 
+        // Heuristics only select the tests whose target was not mapped
+        let mut affected: Vec<String> = self.infer_affected_tests(&normalized)
+            .into_iter()
+            .filter(|key| !self.mapped_tests.contains(key))
+            .map(|(_, name)| name)
+            .collect();
+        if let Some(tests) = self.covered_files.get(&normalized) {
+            affected.extend(tests.iter().map(|(_, name)| name.clone()));
+        }
+
+        affected.sort();
+        affected.dedup();
+        affected
+    }
+
+    /// Tests guessed from their module path and source location
+    fn infer_affected_tests(&self, normalized: &str) -> Vec<TestKey> {
         // Direct match
-        if let Some(tests) = self.file_to_tests.get(&normalized) {
+        if let Some(tests) = self.file_to_tests.get(normalized) {
             return tests.clone();
         }
 
         // Try partial matches (e.g., the file is a parent module)
         let mut affected = Vec::new();
         for (path, tests) in &self.file_to_tests {
-            if path.starts_with(&normalized) || normalized.starts_with(path) {
+            if path.starts_with(normalized) || normalized.starts_with(path.as_str()) {
                 affected.extend(tests.clone());
             }
         }
 
         // If it's a test file, rerun its tests
-        if is_test_file(&normalized) {
+        if is_test_file(normalized) {
             // Extract module name from test file path
-            if let Some(module) = extract_module_from_test_file(&normalized) {
+            if let Some(module) = extract_module_from_test_file(normalized) {
                 for (path, tests) in &self.file_to_tests {
                     if path.contains(&module) {
                         affected.extend(tests.clone());
@@ -190,13 +244,15 @@ fn extract_module_from_test_file(path: &str) -> Option<String> {
     Some(module.to_string())
 }
 
-/// Find all tests that might be affected by changes to a set of files
+/// Find all tests that might be affected by changes to a set of files,
+/// using the per-test coverage map when one was recorded
 pub fn find_affected_from_files(
     changed_files: &[String],
     test_tree: &TestNode,
     project_dir: &Path,
+    coverage: &[TestCoverage],
 ) -> Vec<String> {
-    let map = AffectedTestsMap::from_test_tree(test_tree, project_dir);
+    let map = AffectedTestsMap::from_test_tree(test_tree, project_dir).with_coverage(coverage);
 
     let mut affected = Vec::new();
     for file in changed_files {
@@ -230,6 +286,45 @@ mod tests {
         assert!(paths.contains(&"src/config/mod.rs".to_string()));
     }
 
+    #[test]
+    fn test_affected_by_source_location() {
+        let mut tree = TestNode::new_module("tests");
+        let mut test = crate::test_model::Test::from_name("api::it_works");
+        test.source_file = Some("core/tests/api.rs".to_string());
+        tree.add_test(test);
+        tree.add_test(crate::test_model::Test::from_name("parser::tests::test_parse"));
+
+        let map = AffectedTestsMap::from_test_tree(&tree, Path::new("/project"));
+        assert_eq!(map.find_affected_tests("./core/tests/api.rs"), vec!["api::it_works"]);
+        assert!(map.test_files.contains("core/tests/api.rs"));
+    }
+
+    #[test]
+    fn test_affected_by_coverage_map() {
+        use crate::coverage::CoveredLines;
+        use crate::test_model::{TargetKind, Test, TestTarget};
+
+        let lib = |package: &str| TestTarget { package: package.to_string(), name: package.to_string(), kind: TargetKind::Lib };
+        let mut tree = TestNode::new_module("tests");
+        tree.add_test(Test::from_name("parser::tests::test_parse").with_target(lib("core")));
+        tree.add_test(Test::from_name("lexer::tests::test_lex").with_target(lib("core")));
+        // Same name in another crate, never mapped
+        tree.add_test(Test::from_name("parser::tests::test_parse").with_target(lib("app")));
+        let coverage = vec![TestCoverage {
+            test_name: "parser::tests::test_parse".to_string(),
+            target: Some("core (lib)".to_string()),
+            files: vec![CoveredLines { path: "src/lexer.rs".to_string(), ranges: vec![(5, 9)] }],
+        }];
+
+        let changed = |file: &str| find_affected_from_files(&[file.to_string()], &tree, Path::new("/project"), &coverage);
+        // The mapped test is selected by the files it executed, the other one by its module
+        assert_eq!(changed("src/lexer.rs"), vec!["lexer::tests::test_lex", "parser::tests::test_parse"]);
+        // The mapped test did not execute parser.rs, but its namesake in "app" is still guessed
+        assert_eq!(changed("src/parser.rs"), vec!["parser::tests::test_parse"]);
+        // A file no test is known to depend on still re-runs everything
+        assert_eq!(changed("src/unknown.rs").len(), 3);
+    }
+
     #[test]
     fn test_normalize_path() {
         assert_eq!(normalize_path("./src/foo.rs"), "src/foo.rs");
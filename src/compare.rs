@@ -0,0 +1,298 @@
+//! Run comparison
+//!
+//! Compares the task results of two runs: tests that started or stopped
+//! failing, appeared or disappeared, or became markedly slower or faster.
+//! A test is identified by its target and its name.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::db::{RunSummary, TaskResult};
+
+/// Duration ratio from which a test counts as slower (or, inverted, faster)
+pub const DURATION_RATIO: f64 = 1.5;
+
+/// Smallest duration change that counts, so that 2ms → 5ms is not reported
+pub const MIN_DURATION_DELTA_MS: i64 = 100;
+
+/// A test present in only one of the two runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComparedTest {
+    pub name: String,
+    /// Target label, e.g. "core (lib)"
+    pub target: Option<String>,
+}
+
+impl ComparedTest {
+    /// Name followed by the target, e.g. "it_works [core (lib)]"
+    pub fn label(&self) -> String {
+        test_label(&self.name, self.target.as_deref())
+    }
+}
+
+/// A test whose outcome changed between the two runs
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusChange {
+    pub name: String,
+    pub target: Option<String>,
+    pub before: String,
+    pub after: String,
+    /// Panic message of the failing side
+    pub error_message: Option<String>,
+}
+
+/// A passing test whose duration changed markedly
+#[derive(Debug, Clone, Serialize)]
+pub struct DurationChange {
+    pub name: String,
+    pub target: Option<String>,
+    pub before_ms: i64,
+    pub after_ms: i64,
+}
+
+impl StatusChange {
+    pub fn label(&self) -> String {
+        test_label(&self.name, self.target.as_deref())
+    }
+}
+
+impl DurationChange {
+    pub fn label(&self) -> String {
+        test_label(&self.name, self.target.as_deref())
+    }
+
+    /// Relative change, in percent
+    pub fn change_pct(&self) -> f64 {
+        (self.after_ms - self.before_ms) as f64 * 100.0 / self.before_ms.max(1) as f64
+    }
+}
+
+/// Differences from a base run to a head run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunComparison {
+    pub base: String,
+    pub head: String,
+    pub newly_failing: Vec<StatusChange>,
+    pub newly_passing: Vec<StatusChange>,
+    /// Tests only in the head run
+    pub added: Vec<ComparedTest>,
+    /// Tests only in the base run
+    pub removed: Vec<ComparedTest>,
+    /// Slowest first
+    pub slower: Vec<DurationChange>,
+    /// Most sped up first
+    pub faster: Vec<DurationChange>,
+}
+
+impl RunComparison {
+    /// Compare the tasks of two runs
+    pub fn new(base: &RunSummary, head: &RunSummary) -> Self {
+        let before = outcomes(&base.tasks);
+        let after = outcomes(&head.tasks);
+        let mut comparison = Self {
+            base: base.run.id.clone(),
+            head: head.run.id.clone(),
+            ..Default::default()
+        };
+
+        let test = |(target, name): &(Option<&str>, &str)| ComparedTest {
+            name: name.to_string(),
+            target: target.map(String::from),
+        };
+        for (key, new) in &after {
+            let Some(old) = before.get(key) else {
+                comparison.added.push(test(key));
+                continue;
+            };
+            let change = || StatusChange {
+                name: key.1.to_string(),
+                target: key.0.map(String::from),
+                before: old.status.to_string(),
+                after: new.status.to_string(),
+                error_message: new.error_message.or(old.error_message).map(String::from),
+            };
+            if new.failing() && !old.failing() {
+                comparison.newly_failing.push(change());
+            } else if old.failing() && new.status == "passed" {
+                comparison.newly_passing.push(change());
+            } else if old.status == "passed" && new.status == "passed" {
+                let duration = DurationChange {
+                    name: key.1.to_string(),
+                    target: key.0.map(String::from),
+                    before_ms: old.duration_ms,
+                    after_ms: new.duration_ms,
+                };
+                let delta = new.duration_ms - old.duration_ms;
+                if delta >= MIN_DURATION_DELTA_MS && new.duration_ms as f64 >= old.duration_ms as f64 * DURATION_RATIO {
+                    comparison.slower.push(duration);
+                } else if -delta >= MIN_DURATION_DELTA_MS && old.duration_ms as f64 >= new.duration_ms as f64 * DURATION_RATIO {
+                    comparison.faster.push(duration);
+                }
+            }
+        }
+        comparison.removed = before.keys()
+            .filter(|key| !after.contains_key(*key))
+            .map(test)
+            .collect();
+
+        comparison.slower.sort_by_key(|d| d.before_ms - d.after_ms);
+        comparison.faster.sort_by_key(|d| d.after_ms - d.before_ms);
+        comparison
+    }
+
+    /// Whether nothing changed between the runs
+    pub fn is_empty(&self) -> bool {
+        self.newly_failing.is_empty()
+            && self.newly_passing.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.slower.is_empty()
+            && self.faster.is_empty()
+    }
+}
+
+/// Outcome of a test in one run
+struct Outcome<'a> {
+    status: &'a str,
+    duration_ms: i64,
+    error_message: Option<&'a str>,
+}
+
+impl Outcome<'_> {
+    fn failing(&self) -> bool {
+        self.status == "failed" || self.status == "timed_out"
+    }
+}
+
+/// Outcome of each test of a run, by target and name. Tests recorded twice
+/// (e.g. without a target) are merged: failing if any failed, with the
+/// longest duration.
+fn outcomes(tasks: &[TaskResult]) -> BTreeMap<(Option<&str>, &str), Outcome<'_>> {
+    let mut outcomes: BTreeMap<(Option<&str>, &str), Outcome> = BTreeMap::new();
+    for task in tasks {
+        let outcome = Outcome {
+            status: &task.status,
+            duration_ms: task.duration_ms,
+            error_message: task.error_message.as_deref(),
+        };
+        let key = (task.target.as_deref(), task.task_name.as_str());
+        match outcomes.get_mut(&key) {
+            Some(existing) => {
+                existing.duration_ms = existing.duration_ms.max(outcome.duration_ms);
+                if outcome.failing() && !existing.failing() {
+                    existing.status = outcome.status;
+                    existing.error_message = outcome.error_message;
+                }
+            }
+            None => {
+                outcomes.insert(key, outcome);
+            }
+        }
+    }
+    outcomes
+}
+
+/// A test's name, followed by its target when known
+fn test_label(name: &str, target: Option<&str>) -> String {
+    match target {
+        Some(target) => format!("{} [{}]", name, target),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Run;
+    use chrono::Utc;
+
+    fn summary(id: &str, tasks: &[(&str, &str, i64)]) -> RunSummary {
+        let tasks: Vec<_> = tasks.iter().map(|&(name, status, duration_ms)| (None, name, status, duration_ms)).collect();
+        summary_in_targets(id, &tasks)
+    }
+
+    fn summary_in_targets(id: &str, tasks: &[(Option<&str>, &str, &str, i64)]) -> RunSummary {
+        RunSummary {
+            run: Run {
+                id: id.to_string(),
+                started_at: Utc::now(),
+                finished_at: None,
+                status: "passed".to_string(),
+                total_tasks: tasks.len() as i32,
+                passed: 0,
+                failed: 0,
+                source: None,
+            },
+            tasks: tasks.iter().map(|(target, name, status, duration_ms)| TaskResult {
+                id: format!("{}-{}", id, name),
+                run_id: id.to_string(),
+                task_name: name.to_string(),
+                category: Some("test".to_string()),
+                status: status.to_string(),
+                duration_ms: *duration_ms,
+                started_at: Utc::now(),
+                output: None,
+                ignore_reason: None,
+                error_message: (*status == "failed").then(|| "boom".to_string()),
+                error_type: None,
+                retries: 0,
+                target: target.map(String::from),
+                classname: None,
+            }).collect(),
+        }
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let base = summary("a", &[
+            ("broken", "passed", 10),
+            ("fixed", "failed", 10),
+            ("slow", "passed", 100),
+            ("fast", "passed", 900),
+            ("noise", "passed", 2),
+            ("gone", "passed", 1),
+        ]);
+        let head = summary("b", &[
+            ("broken", "failed", 10),
+            ("fixed", "passed", 10),
+            ("slow", "passed", 400),
+            ("fast", "passed", 300),
+            ("noise", "passed", 9),
+            ("new", "passed", 1),
+        ]);
+
+        let comparison = RunComparison::new(&base, &head);
+        assert_eq!(comparison.newly_failing[0].name, "broken");
+        assert_eq!(comparison.newly_failing[0].error_message.as_deref(), Some("boom"));
+        assert_eq!(comparison.newly_passing[0].name, "fixed");
+        assert_eq!(comparison.added, vec![ComparedTest { name: "new".to_string(), target: None }]);
+        assert_eq!(comparison.removed[0].label(), "gone");
+        assert_eq!(comparison.slower.len(), 1);
+        assert_eq!(comparison.slower[0].change_pct(), 300.0);
+        assert_eq!(comparison.faster[0].name, "fast");
+        assert!(RunComparison::new(&head, &head).is_empty());
+    }
+
+    #[test]
+    fn test_same_name_in_several_targets() {
+        let lib = Some("core (lib)");
+        let api = Some("core (integration api)");
+        let base = summary_in_targets("a", &[
+            (lib, "it_works", "passed", 1),
+            (api, "it_works", "passed", 1),
+            (lib, "moved", "passed", 1),
+        ]);
+        let head = summary_in_targets("b", &[
+            (lib, "it_works", "passed", 1),
+            (api, "it_works", "failed", 1),
+            (api, "moved", "passed", 1),
+        ]);
+
+        let comparison = RunComparison::new(&base, &head);
+        assert_eq!(comparison.newly_failing.len(), 1);
+        assert_eq!(comparison.newly_failing[0].label(), "it_works [core (integration api)]");
+        // A test that left one target is reported, even though its name remains
+        assert_eq!(comparison.removed[0].label(), "moved [core (lib)]");
+        assert_eq!(comparison.added[0].label(), "moved [core (integration api)]");
+    }
+}
@@ -0,0 +1,231 @@
+//! Project configuration
+//!
+//! Loads the optional `runx.toml` file at the project root.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::test_runner::TestBackend;
+
+/// Name of the project configuration file
+pub const CONFIG_FILE: &str = "runx.toml";
+
+/// Top-level `runx.toml` contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunxConfig {
+    /// `[project]` section
+    #[serde(default)]
+    pub project: ProjectConfig,
+    /// `[test]` section
+    #[serde(default)]
+    pub test: TestConfig,
+    /// `[editor]` section
+    #[serde(default)]
+    pub editor: EditorConfig,
+    /// `[quarantine]` section
+    #[serde(default)]
+    pub quarantine: QuarantineConfig,
+    /// `[retry]` section
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// `[coverage]` section
+    #[serde(default)]
+    pub coverage: CoverageConfig,
+    /// `[tasks.<name>]` entries
+    #[serde(default)]
+    pub tasks: BTreeMap<String, TaskConfig>,
+}
+
+/// `[project]` section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    /// Display name (overrides the Cargo package name)
+    pub name: Option<String>,
+}
+
+/// `[test]` section: how runx discovers and runs tests
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestConfig {
+    /// Runner backend (`"cargo"` or `"nextest"`)
+    #[serde(default)]
+    pub backend: TestBackend,
+    /// Number of tests to run concurrently (default: 1)
+    pub jobs: Option<usize>,
+    /// Per-test timeout in seconds; a test running longer is killed
+    pub timeout: Option<u64>,
+    /// Seconds after which a still-running test is reported as slow (default: 60)
+    pub slow_timeout: Option<u64>,
+    /// Timeout in seconds for the whole test run
+    pub run_timeout: Option<u64>,
+}
+
+/// `[editor]` section: where "open in editor" actions go
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EditorConfig {
+    /// Editor command used by the TUI (default: `$VISUAL`, then `$EDITOR`)
+    pub command: Option<String>,
+    /// Link template used by the dashboard, with `{file}`, `{line}` and
+    /// `{col}` placeholders (e.g. `"vscode://file/{file}:{line}:{col}"`)
+    pub url: Option<String>,
+}
+
+/// `[quarantine]` section: automatic quarantine of flaky tests
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuarantineConfig {
+    /// Quarantine flaky tests automatically after each run (default: false)
+    #[serde(default)]
+    pub auto: bool,
+    /// Flaky score from which a test is quarantined (default: 30)
+    pub min_score: Option<f64>,
+    /// Recorded outcomes needed before a test can be quarantined (default: 5)
+    pub min_runs: Option<i32>,
+}
+
+/// `[retry]` section: which failed tests `runx run` runs again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Retries per failed test (default: 0, overridden by `--retry`)
+    pub count: Option<u32>,
+    /// Only retry failures whose output contains one of these texts
+    /// (case-insensitive, e.g. `"connection refused"`)
+    #[serde(default)]
+    pub on: Vec<String>,
+    /// Retries of the tests whose name contains the key, overriding `count`
+    #[serde(default)]
+    pub tests: BTreeMap<String, u32>,
+}
+
+/// `[coverage]` section: what `runx run --coverage` requires
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoverageConfig {
+    /// Minimum line coverage in percent; a run below it fails
+    /// (overridden by `--coverage-threshold`)
+    pub threshold: Option<f64>,
+}
+
+/// A single `[tasks.<name>]` entry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskConfig {
+    /// Shell command to execute
+    pub cmd: String,
+    /// Category used for grouping in reports (e.g. "build", "lint", "unit")
+    pub category: Option<String>,
+    /// Glob patterns of files that affect this task
+    #[serde(default)]
+    pub watch: Vec<String>,
+    /// Tasks that must succeed before this one starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+impl RunxConfig {
+    /// Load `runx.toml` from the project directory.
+    ///
+    /// Returns the default (empty) configuration if the file does not exist.
+    pub fn load(project_dir: &Path) -> Result<Self> {
+        let path = project_dir.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// Parse configuration from TOML text
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tasks() {
+        let config = RunxConfig::parse(r#"
+[project]
+name = "demo"
+
+[tasks.build]
+cmd = "cargo build"
+category = "build"
+watch = ["src/**/*.rs"]
+
+[tasks.test]
+cmd = "cargo test"
+depends_on = ["build"]
+"#).unwrap();
+
+        assert_eq!(config.project.name.as_deref(), Some("demo"));
+        assert_eq!(config.test.backend, TestBackend::Cargo);
+        assert_eq!(config.tasks.len(), 2);
+        assert_eq!(config.tasks["build"].category.as_deref(), Some("build"));
+        assert_eq!(config.tasks["test"].depends_on, vec!["build"]);
+        assert!(config.tasks["test"].watch.is_empty());
+    }
+
+    #[test]
+    fn test_parse_backend() {
+        let config = RunxConfig::parse("[test]\nbackend = \"nextest\"\n").unwrap();
+        assert_eq!(config.test.backend, TestBackend::Nextest);
+    }
+
+    #[test]
+    fn test_parse_timeouts() {
+        let config = RunxConfig::parse("[test]\ntimeout = 30\nrun_timeout = 600\n").unwrap();
+        assert_eq!(config.test.timeout, Some(30));
+        assert_eq!(config.test.slow_timeout, None);
+        assert_eq!(config.test.run_timeout, Some(600));
+    }
+
+    #[test]
+    fn test_parse_editor() {
+        let config = RunxConfig::parse("[editor]\ncommand = \"code --wait\"\nurl = \"vscode://file/{file}:{line}\"\n").unwrap();
+        assert_eq!(config.editor.command.as_deref(), Some("code --wait"));
+        assert_eq!(config.editor.url.as_deref(), Some("vscode://file/{file}:{line}"));
+    }
+
+    #[test]
+    fn test_parse_quarantine() {
+        let config = RunxConfig::parse("[quarantine]\nauto = true\nmin_score = 50\n").unwrap();
+        assert!(config.quarantine.auto);
+        assert_eq!(config.quarantine.min_score, Some(50.0));
+        assert_eq!(config.quarantine.min_runs, None);
+        assert!(!RunxConfig::default().quarantine.auto);
+    }
+
+    #[test]
+    fn test_parse_retry() {
+        let config = RunxConfig::parse(r#"
+[retry]
+count = 1
+on = ["connection refused"]
+
+[retry.tests]
+"net::fetch" = 3
+"#).unwrap();
+        assert_eq!(config.retry.count, Some(1));
+        assert_eq!(config.retry.on, vec!["connection refused"]);
+        assert_eq!(config.retry.tests["net::fetch"], 3);
+    }
+
+    #[test]
+    fn test_parse_coverage() {
+        let config = RunxConfig::parse("[coverage]\nthreshold = 80\n").unwrap();
+        assert_eq!(config.coverage.threshold, Some(80.0));
+        assert_eq!(RunxConfig::default().coverage.threshold, None);
+    }
+
+    #[test]
+    fn test_missing_file_is_default() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = RunxConfig::load(dir.path()).unwrap();
+        assert!(config.tasks.is_empty());
+    }
+}
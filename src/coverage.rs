@@ -0,0 +1,517 @@
+//! Code coverage
+//!
+//! `runx run --coverage` measures LLVM source-based coverage. With
+//! cargo-llvm-cov installed, `cargo llvm-cov show-env` instruments the build
+//! and `cargo llvm-cov report --json` summarizes it. Otherwise the tests are
+//! built with `-C instrument-coverage` in a separate target directory, and the
+//! `.profraw` profiles they write are merged with `llvm-profdata` and
+//! summarized with `llvm-cov export` from the `llvm-tools` rustup component.
+//!
+//! The coverage environment is set on runx's own process, so that the build,
+//! the test processes and `cargo nextest` all inherit it.
+//!
+//! `runx run --coverage-map` then runs each test again on its own, with its
+//! own profile, to record the lines it executes. The watcher re-runs the tests
+//! that executed a changed file.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::test_model::TestStatus;
+use crate::test_runner::{TestRunResult, TestRunner};
+
+/// Profiles written by the test processes (`%p`: process id, `%m`: binary signature)
+const PROFILE_PATTERN: &str = "runx-%p-%m.profraw";
+
+/// Covered and coverable lines and branches
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageSummary {
+    pub lines_covered: u64,
+    pub lines_total: u64,
+    pub branches_covered: u64,
+    /// Zero unless branch coverage was enabled (nightly only)
+    pub branches_total: u64,
+}
+
+impl CoverageSummary {
+    /// Percentage of lines covered
+    pub fn line_coverage(&self) -> f64 {
+        percent(self.lines_covered, self.lines_total).unwrap_or(0.0)
+    }
+
+    /// Percentage of branches covered, when branches were measured
+    pub fn branch_coverage(&self) -> Option<f64> {
+        percent(self.branches_covered, self.branches_total)
+    }
+
+    /// Whether the line coverage reaches a threshold (in percent)
+    pub fn meets(&self, threshold: f64) -> bool {
+        self.line_coverage() >= threshold
+    }
+}
+
+fn percent(covered: u64, total: u64) -> Option<f64> {
+    (total > 0).then(|| covered as f64 / total as f64 * 100.0)
+}
+
+/// Coverage of a source file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileCoverage {
+    /// Path relative to the project directory
+    pub path: String,
+    #[serde(flatten)]
+    pub summary: CoverageSummary,
+}
+
+/// Coverage of a run: every project source file and the total
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub total: CoverageSummary,
+    /// Sorted by path
+    pub files: Vec<FileCoverage>,
+}
+
+/// Lines of a source file executed by a test
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoveredLines {
+    /// Path relative to the project directory
+    pub path: String,
+    /// Inclusive ranges of executed lines, in order
+    pub ranges: Vec<(u32, u32)>,
+}
+
+impl CoveredLines {
+    /// Ranges of the executed lines, given in any order
+    pub fn from_lines(path: &str, mut lines: Vec<u32>) -> Self {
+        lines.sort_unstable();
+        lines.dedup();
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        for line in lines {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == line => *end = line,
+                _ => ranges.push((line, line)),
+            }
+        }
+        Self { path: path.to_string(), ranges }
+    }
+
+    /// Ranges written as "3-7,12"
+    pub fn ranges_text(&self) -> String {
+        self.ranges.iter()
+            .map(|&(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Parse ranges written by `ranges_text`
+    pub fn parse_ranges(path: &str, text: &str) -> Self {
+        let ranges = text.split(',')
+            .filter_map(|range| {
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                Some((start.trim().parse().ok()?, end.trim().parse().ok()?))
+            })
+            .collect();
+        Self { path: path.to_string(), ranges }
+    }
+}
+
+/// Project lines executed by a test, recorded with `runx run --coverage-map`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestCoverage {
+    pub test_name: String,
+    /// Target label, e.g. "core (lib)"
+    pub target: Option<String>,
+    /// Sorted by path
+    pub files: Vec<CoveredLines>,
+}
+
+/// Executed lines of the project files in `llvm-cov export -format=lcov` output
+fn parse_lcov(lcov: &str, project_dir: &Path) -> Vec<CoveredLines> {
+    let project_dir = project_dir.canonicalize().unwrap_or_else(|_| project_dir.to_path_buf());
+
+    let mut files = Vec::new();
+    let mut current: Option<(String, Vec<u32>)> = None;
+    for line in lcov.lines() {
+        if let Some(path) = line.strip_prefix("SF:") {
+            current = Path::new(path).strip_prefix(&project_dir).ok()
+                .map(|rel| (rel.to_string_lossy().replace('\\', "/"), Vec::new()));
+        } else if let Some(data) = line.strip_prefix("DA:") {
+            // DA:<line>,<execution count>[,<checksum>]
+            let mut fields = data.split(',');
+            let (Some(number), Some(count)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            if let (Some((_, lines)), Ok(number)) = (current.as_mut(), number.parse()) {
+                if count.parse::<u64>().is_ok_and(|count| count > 0) {
+                    lines.push(number);
+                }
+            }
+        } else if line == "end_of_record" {
+            if let Some((path, lines)) = current.take() {
+                if !lines.is_empty() {
+                    files.push(CoveredLines::from_lines(&path, lines));
+                }
+            }
+        }
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// `llvm-cov export -summary-only` output (only the fields we use)
+#[derive(Deserialize)]
+struct LlvmExport {
+    data: Vec<LlvmExportData>,
+}
+
+#[derive(Deserialize)]
+struct LlvmExportData {
+    files: Vec<LlvmFile>,
+}
+
+#[derive(Deserialize)]
+struct LlvmFile {
+    filename: PathBuf,
+    summary: LlvmSummary,
+}
+
+#[derive(Deserialize)]
+struct LlvmSummary {
+    lines: LlvmCounts,
+    #[serde(default)]
+    branches: LlvmCounts,
+}
+
+#[derive(Default, Deserialize)]
+struct LlvmCounts {
+    count: u64,
+    covered: u64,
+}
+
+impl CoverageReport {
+    /// Parse `llvm-cov export` (or `cargo llvm-cov --json`) output. Files
+    /// outside the project (the standard library, dependencies) are left out.
+    pub fn from_llvm_export(json: &str, project_dir: &Path) -> Result<Self> {
+        let export: LlvmExport = serde_json::from_str(json).context("Invalid llvm-cov export")?;
+        let project_dir = project_dir.canonicalize().unwrap_or_else(|_| project_dir.to_path_buf());
+
+        let mut report = CoverageReport::default();
+        for file in export.data.into_iter().flat_map(|d| d.files) {
+            let Ok(path) = file.filename.strip_prefix(&project_dir) else {
+                continue;
+            };
+            let summary = CoverageSummary {
+                lines_covered: file.summary.lines.covered,
+                lines_total: file.summary.lines.count,
+                branches_covered: file.summary.branches.covered,
+                branches_total: file.summary.branches.count,
+            };
+            report.total.lines_covered += summary.lines_covered;
+            report.total.lines_total += summary.lines_total;
+            report.total.branches_covered += summary.branches_covered;
+            report.total.branches_total += summary.branches_total;
+            report.files.push(FileCoverage {
+                path: path.to_string_lossy().replace('\\', "/"),
+                summary,
+            });
+        }
+
+        report.files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(report)
+    }
+}
+
+/// Tool that instruments the build and summarizes the profiles
+#[derive(Debug, Clone)]
+enum CoverageTool {
+    /// cargo-llvm-cov
+    CargoLlvmCov,
+    /// `llvm-profdata` and `llvm-cov` from the `llvm-tools` rustup component
+    LlvmTools { profdata: PathBuf, cov: PathBuf },
+}
+
+impl CoverageTool {
+    fn detect(project_dir: &Path) -> Result<Self> {
+        let cargo_llvm_cov = Command::new("cargo")
+            .args(["llvm-cov", "--version"])
+            .current_dir(project_dir)
+            .output()
+            .is_ok_and(|out| out.status.success());
+        if cargo_llvm_cov {
+            return Ok(CoverageTool::CargoLlvmCov);
+        }
+
+        let (profdata, cov) = llvm_tools(project_dir).context("Coverage needs cargo-llvm-cov or llvm-tools")?;
+        Ok(CoverageTool::LlvmTools { profdata, cov })
+    }
+}
+
+/// `llvm-profdata` and `llvm-cov` from the `llvm-tools` rustup component
+fn llvm_tools(project_dir: &Path) -> Result<(PathBuf, PathBuf)> {
+    let bin = llvm_tools_dir(project_dir)
+        .context("Could not locate the Rust toolchain's sysroot")?;
+    let (profdata, cov) = (bin.join(exe("llvm-profdata")), bin.join(exe("llvm-cov")));
+    if !profdata.exists() || !cov.exists() {
+        anyhow::bail!("The llvm-tools component is not installed (rustup component add llvm-tools)");
+    }
+    Ok((profdata, cov))
+}
+
+/// `<sysroot>/lib/rustlib/<host>/bin`, where rustup installs llvm-tools
+fn llvm_tools_dir(project_dir: &Path) -> Option<PathBuf> {
+    let rustc = |args: &[&str]| {
+        Command::new("rustc")
+            .args(args)
+            .current_dir(project_dir)
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .map(|out| String::from_utf8_lossy(&out.stdout).to_string())
+    };
+    let sysroot = rustc(&["--print", "sysroot"])?;
+    let version = rustc(&["-vV"])?;
+    let host = version.lines().find_map(|line| line.strip_prefix("host: "))?;
+    Some(Path::new(sysroot.trim()).join("lib").join("rustlib").join(host.trim()).join("bin"))
+}
+
+fn exe(name: &str) -> String {
+    format!("{}{}", name, std::env::consts::EXE_SUFFIX)
+}
+
+/// A coverage measurement around a test run
+#[derive(Debug)]
+pub struct CoverageSession {
+    project_dir: PathBuf,
+    tool: CoverageTool,
+    /// Where the test processes write their profiles (llvm-tools only)
+    profile_dir: PathBuf,
+}
+
+impl CoverageSession {
+    /// Set up the instrumented build, discarding the profiles of previous
+    /// measurements
+    pub fn start(project_dir: &Path) -> Result<Self> {
+        let tool = CoverageTool::detect(project_dir)?;
+        let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| project_dir.join("target"))
+            .join("runx-coverage");
+        let session = Self {
+            project_dir: project_dir.to_path_buf(),
+            tool,
+            profile_dir: target_dir.join("profiles"),
+        };
+
+        match session.tool {
+            CoverageTool::CargoLlvmCov => {
+                cargo_llvm_cov(project_dir, &["clean", "--workspace"])?;
+                let env = cargo_llvm_cov(project_dir, &["show-env"])?;
+                for (key, value) in parse_env(&env) {
+                    std::env::set_var(key, value);
+                }
+            }
+            CoverageTool::LlvmTools { .. } => {
+                let _ = std::fs::remove_dir_all(&session.profile_dir);
+                std::fs::create_dir_all(&session.profile_dir)?;
+                // A separate target directory keeps the regular build's cache
+                let rustflags = match std::env::var("RUSTFLAGS") {
+                    Ok(flags) if !flags.trim().is_empty() => format!("{} -C instrument-coverage", flags),
+                    _ => "-C instrument-coverage".to_string(),
+                };
+                std::env::set_var("CARGO_TARGET_DIR", &target_dir);
+                std::env::set_var("RUSTFLAGS", rustflags);
+                std::env::set_var("LLVM_PROFILE_FILE", session.profile_dir.join(PROFILE_PATTERN));
+            }
+        }
+        Ok(session)
+    }
+
+    /// Name of the tool, shown in messages
+    pub fn tool_name(&self) -> &'static str {
+        match self.tool {
+            CoverageTool::CargoLlvmCov => "cargo-llvm-cov",
+            CoverageTool::LlvmTools { .. } => "llvm-tools",
+        }
+    }
+
+    /// Summarize the profiles written by the test processes
+    pub fn report(&self) -> Result<CoverageReport> {
+        let json = match self.tool {
+            CoverageTool::CargoLlvmCov => cargo_llvm_cov(&self.project_dir, &["report", "--json", "--summary-only"])?,
+            CoverageTool::LlvmTools { ref profdata, ref cov } => self.export(profdata, cov)?,
+        };
+        CoverageReport::from_llvm_export(&json, &self.project_dir)
+    }
+
+    /// Run each test that passed or failed again on its own, writing its own
+    /// profile, and record the project lines it executed. Doc tests, which
+    /// have no binary of their own, are left out, as are the runs that timed
+    /// out or were cancelled: `runner` enforces the usual time limits.
+    pub fn map_tests(&self, result: &TestRunResult, runner: &TestRunner) -> Result<Vec<TestCoverage>> {
+        let (profdata, cov) = match self.tool {
+            CoverageTool::LlvmTools { ref profdata, ref cov } => (profdata.clone(), cov.clone()),
+            CoverageTool::CargoLlvmCov => llvm_tools(&self.project_dir)
+                .context("Mapping coverage per test needs llvm-tools")?,
+        };
+        let dir = self.profile_dir.join("tests");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+
+        let build = crate::discovery::build_tests(&self.project_dir)?;
+        let deadline = runner.deadline();
+        let mut map = Vec::new();
+        for (i, test) in result.test_results.iter().enumerate() {
+            if !matches!(test.status, TestStatus::Passed | TestStatus::Failed) {
+                continue;
+            }
+            let Some(binary) = build.binaries.iter().find(|b| test.target.as_ref() == Some(&b.target)) else {
+                continue;
+            };
+
+            let profile = dir.join(format!("{}.profraw", i));
+            let rerun = runner.run_alone(binary, &test.name, &[("LLVM_PROFILE_FILE", &profile)], deadline)?;
+            if rerun.cancelled || rerun.timed_out {
+                break;
+            }
+            let finished = rerun.test_results.iter()
+                .any(|t| matches!(t.status, TestStatus::Passed | TestStatus::Failed));
+            if !finished || !profile.exists() {
+                continue;
+            }
+
+            let merged = dir.join(format!("{}.profdata", i));
+            run_tool(Command::new(&profdata)
+                .args(["merge", "-sparse", "-o"])
+                .arg(&merged)
+                .arg(&profile))?;
+            let lcov = run_tool(Command::new(&cov)
+                .args(["export", "-format=lcov"])
+                .arg(format!("-instr-profile={}", merged.display()))
+                .arg(&binary.executable))?;
+
+            map.push(TestCoverage {
+                test_name: test.name.clone(),
+                target: Some(binary.target.qualified_label()),
+                files: parse_lcov(&lcov, &self.project_dir),
+            });
+        }
+        Ok(map)
+    }
+
+    /// Merge the profiles and export their summary for the test binaries
+    fn export(&self, profdata: &Path, cov: &Path) -> Result<String> {
+        let profiles: Vec<PathBuf> = std::fs::read_dir(&self.profile_dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "profraw"))
+            .collect();
+        if profiles.is_empty() {
+            anyhow::bail!("No coverage profile was written by the tests");
+        }
+
+        let merged = self.profile_dir.join("runx.profdata");
+        run_tool(Command::new(profdata)
+            .args(["merge", "-sparse", "-o"])
+            .arg(&merged)
+            .args(&profiles))?;
+
+        // The instrumented binaries are up to date: listing them only
+        // reuses the build of this run
+        let binaries = crate::discovery::build_tests(&self.project_dir)?.binaries;
+        let Some((first, others)) = binaries.split_first() else {
+            anyhow::bail!("No test binary to read coverage mappings from");
+        };
+        let mut export = Command::new(cov);
+        export.args(["export", "-summary-only"])
+            .arg(format!("-instr-profile={}", merged.display()))
+            .arg(&first.executable);
+        for binary in others {
+            export.arg("-object").arg(&binary.executable);
+        }
+        run_tool(&mut export)
+    }
+}
+
+fn cargo_llvm_cov(project_dir: &Path, args: &[&str]) -> Result<String> {
+    run_tool(Command::new("cargo").arg("llvm-cov").args(args).current_dir(project_dir))
+}
+
+/// Standard output of a coverage tool, which must succeed
+fn run_tool(cmd: &mut Command) -> Result<String> {
+    let output = cmd.output()
+        .with_context(|| format!("Failed to execute {:?}", cmd.get_program()))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} failed: {}",
+            Path::new(cmd.get_program()).file_name().unwrap_or_default().to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `KEY=value` lines of `cargo llvm-cov show-env`, whose values may be quoted
+fn parse_env(output: &str) -> Vec<(String, String)> {
+    output.lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches("export ");
+            let (key, value) = line.split_once('=')?;
+            let value = value
+                .strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_llvm_export() {
+        let json = r#"{"data":[{"files":[
+            {"filename":"/ws/core/src/lib.rs","summary":{
+                "lines":{"count":8,"covered":6,"percent":75},
+                "branches":{"count":0,"covered":0,"notcovered":0,"percent":0}}},
+            {"filename":"/ws/cli/src/main.rs","summary":{"lines":{"count":2,"covered":0,"percent":0}}},
+            {"filename":"/rustc/abc/library/core/src/fmt/mod.rs","summary":{"lines":{"count":50,"covered":1,"percent":2}}}
+        ],"totals":{}}],"type":"llvm.coverage.json.export","version":"2.0.1"}"#;
+
+        let report = CoverageReport::from_llvm_export(json, Path::new("/ws")).unwrap();
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.files[0].path, "cli/src/main.rs");
+        assert_eq!(report.total.lines_covered, 6);
+        assert_eq!(report.total.lines_total, 10);
+        assert_eq!(report.total.line_coverage(), 60.0);
+        assert_eq!(report.total.branch_coverage(), None);
+        assert!(report.total.meets(60.0));
+        assert!(!report.total.meets(80.0));
+    }
+
+    #[test]
+    fn test_parse_lcov() {
+        let lcov = "SF:/ws/core/src/lib.rs\nFN:1,add\nDA:1,2\nDA:2,2\nDA:3,2\nDA:5,0\nDA:7,1\nend_of_record\n\
+                    SF:/ws/core/src/unused.rs\nDA:1,0\nend_of_record\n\
+                    SF:/rustc/abc/library/core/src/fmt/mod.rs\nDA:10,4\nend_of_record\n";
+
+        let files = parse_lcov(lcov, Path::new("/ws"));
+        assert_eq!(files, vec![CoveredLines { path: "core/src/lib.rs".to_string(), ranges: vec![(1, 3), (7, 7)] }]);
+        assert_eq!(files[0].ranges_text(), "1-3,7");
+        assert_eq!(CoveredLines::parse_ranges("core/src/lib.rs", "1-3,7"), files[0]);
+    }
+
+    #[test]
+    fn test_parse_env() {
+        let env = "RUSTFLAGS=\"-C instrument-coverage --cfg=coverage\"\nexport LLVM_PROFILE_FILE='/t/%p.profraw'\nCARGO_LLVM_COV=1\n";
+        assert_eq!(parse_env(env), vec![
+            ("RUSTFLAGS".to_string(), "-C instrument-coverage --cfg=coverage".to_string()),
+            ("LLVM_PROFILE_FILE".to_string(), "/t/%p.profraw".to_string()),
+            ("CARGO_LLVM_COV".to_string(), "1".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,221 @@
+//! Duration baselines and regressions
+//!
+//! A test's baseline is computed from its most recent passing executions in
+//! `test_history`: the median duration, the median absolute deviation (MAD)
+//! around it and the 95th percentile. An execution is a regression when it is
+//! well outside the spread of the baseline and materially slower than its
+//! median.
+
+use anyhow::Result;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use super::Database;
+
+/// Number of recent passing executions a baseline is computed over
+const BASELINE_WINDOW: i64 = 20;
+
+/// Passing executions needed before a test has a baseline
+const MIN_SAMPLES: usize = 5;
+
+/// Robust z-score (in scaled MADs from the median) from which an execution
+/// is a regression
+const MAD_THRESHOLD: f64 = 3.0;
+
+/// Scales the MAD to a standard deviation for normally distributed durations
+const MAD_SCALE: f64 = 1.4826;
+
+/// Slowdown, relative to the median, needed for a regression
+const MIN_SLOWDOWN: f64 = 1.25;
+
+/// Slowdown, in milliseconds, needed for a regression
+const MIN_DELTA_MS: f64 = 50.0;
+
+/// Duration statistics of a test's recent passing executions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DurationBaseline {
+    pub test_name: String,
+    /// Target label (empty when unknown)
+    pub classname: String,
+    pub median_ms: f64,
+    /// Median absolute deviation from the median
+    pub mad_ms: f64,
+    pub p95_ms: f64,
+    /// Number of executions the baseline is computed over
+    pub samples: usize,
+}
+
+impl DurationBaseline {
+    /// Baseline of a set of durations, if there are enough of them
+    pub fn from_durations(test_name: &str, classname: &str, durations: &[i64]) -> Option<Self> {
+        if durations.len() < MIN_SAMPLES {
+            return None;
+        }
+        let mut sorted: Vec<f64> = durations.iter().map(|&d| d as f64).collect();
+        sorted.sort_by(f64::total_cmp);
+        let median_ms = median(&sorted);
+        let mut deviations: Vec<f64> = sorted.iter().map(|d| (d - median_ms).abs()).collect();
+        deviations.sort_by(f64::total_cmp);
+        let p95 = sorted[((sorted.len() as f64 * 0.95).ceil() as usize).clamp(1, sorted.len()) - 1];
+
+        Some(Self {
+            test_name: test_name.to_string(),
+            classname: classname.to_string(),
+            median_ms,
+            mad_ms: median(&deviations),
+            p95_ms: p95,
+            samples: sorted.len(),
+        })
+    }
+
+    /// Whether a duration is a regression from this baseline
+    pub fn is_regression(&self, duration_ms: i64) -> bool {
+        let duration = duration_ms as f64;
+        duration > self.median_ms + MAD_THRESHOLD * MAD_SCALE * self.mad_ms
+            && duration >= self.median_ms * MIN_SLOWDOWN
+            && duration - self.median_ms >= MIN_DELTA_MS
+    }
+}
+
+/// Median of sorted values
+fn median(sorted: &[f64]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// A test that ran materially slower than its baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DurationRegression {
+    pub run_id: String,
+    pub duration_ms: i64,
+    pub baseline: DurationBaseline,
+}
+
+impl DurationRegression {
+    /// Duration relative to the baseline median (e.g. 2.5 for 2.5× slower)
+    pub fn slowdown(&self) -> f64 {
+        self.duration_ms as f64 / self.baseline.median_ms.max(1.0)
+    }
+}
+
+impl Database {
+    /// Passing tests of a run that were materially slower than their
+    /// baseline from the executions before it, most slowed down first
+    pub fn find_regressions(&self, run_id: &str) -> Result<Vec<DurationRegression>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, test_name, COALESCE(classname, ''), COALESCE(duration_ms, 0)
+             FROM test_history WHERE run_id = ?1 AND status = 'passed'"
+        )?;
+        let executions: Vec<(i64, String, String, i64)> = stmt
+            .query_map(params![run_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+
+        let mut earlier = self.conn.prepare(
+            "SELECT COALESCE(duration_ms, 0) FROM test_history
+             WHERE test_name = ?1 AND COALESCE(classname, '') = ?2 AND status = 'passed' AND id < ?3
+             ORDER BY id DESC
+             LIMIT ?4"
+        )?;
+        let mut regressions = Vec::new();
+        for (id, test_name, classname, duration_ms) in executions {
+            let durations: Vec<i64> = earlier
+                .query_map(params![test_name, classname, id, BASELINE_WINDOW], |row| row.get(0))?
+                .collect::<std::result::Result<_, _>>()?;
+            let Some(baseline) = DurationBaseline::from_durations(&test_name, &classname, &durations) else {
+                continue;
+            };
+            if baseline.is_regression(duration_ms) {
+                regressions.push(DurationRegression {
+                    run_id: run_id.to_string(),
+                    duration_ms,
+                    baseline,
+                });
+            }
+        }
+
+        regressions.sort_by(|a, b| b.slowdown().total_cmp(&a.slowdown()));
+        Ok(regressions)
+    }
+
+    /// Baselines of the tests with the longest median duration
+    pub fn get_duration_baselines(&self, limit: usize) -> Result<Vec<DurationBaseline>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT test_name, classname, duration_ms FROM (
+                SELECT test_name, COALESCE(classname, '') AS classname, COALESCE(duration_ms, 0) AS duration_ms,
+                       ROW_NUMBER() OVER (PARTITION BY test_name, COALESCE(classname, '') ORDER BY id DESC) AS rank
+                FROM test_history WHERE status = 'passed'
+             )
+             WHERE rank <= ?1
+             ORDER BY test_name, classname"
+        )?;
+        let rows: Vec<(String, String, i64)> = stmt
+            .query_map(params![BASELINE_WINDOW], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+
+        let mut baselines = Vec::new();
+        for group in rows.chunk_by(|a, b| a.0 == b.0 && a.1 == b.1) {
+            let durations: Vec<i64> = group.iter().map(|(_, _, d)| *d).collect();
+            baselines.extend(DurationBaseline::from_durations(&group[0].0, &group[0].1, &durations));
+        }
+
+        baselines.sort_by(|a, b| b.median_ms.total_cmp(&a.median_ms));
+        baselines.truncate(limit);
+        Ok(baselines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::HistoryEntry;
+
+    #[test]
+    fn test_baseline() {
+        assert!(DurationBaseline::from_durations("a", "", &[10, 10, 10]).is_none());
+
+        let baseline = DurationBaseline::from_durations("a", "", &[100, 110, 90, 105, 95, 400]).unwrap();
+        assert_eq!(baseline.median_ms, 102.5);
+        assert_eq!(baseline.mad_ms, 7.5);
+        assert_eq!(baseline.p95_ms, 400.0);
+
+        // Within the spread, or not slower by enough
+        assert!(!baseline.is_regression(125));
+        assert!(baseline.is_regression(200));
+        let fast = DurationBaseline::from_durations("a", "", &[2, 2, 2, 2, 2]).unwrap();
+        assert!(!fast.is_regression(20));
+        assert!(fast.is_regression(60));
+    }
+
+    #[test]
+    fn test_find_regressions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("runx.db")).unwrap();
+        let entry = |name: &str, duration_ms| HistoryEntry {
+            test_name: name.to_string(),
+            classname: Some("core (lib)".to_string()),
+            status: "passed".to_string(),
+            duration_ms,
+        };
+        for run in 0..6 {
+            db.record_test_history(&format!("run-{}", run), &[
+                entry("a::steady", 100 + run),
+                entry("a::slowed", 100 + run),
+            ]).unwrap();
+        }
+        db.record_test_history("run-6", &[entry("a::steady", 104), entry("a::slowed", 450)]).unwrap();
+
+        let regressions = db.find_regressions("run-6").unwrap();
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].baseline.test_name, "a::slowed");
+        assert_eq!(regressions[0].baseline.samples, 6);
+        // Earlier runs are judged against their own past
+        assert!(db.find_regressions("run-5").unwrap().is_empty());
+
+        let baselines = db.get_duration_baselines(1).unwrap();
+        assert_eq!(baselines[0].test_name, "a::slowed");
+    }
+}
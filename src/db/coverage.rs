@@ -0,0 +1,204 @@
+//! Code coverage of runs
+//!
+//! `coverage_results` holds one row per source file of a run measured with
+//! `runx run --coverage`, and one row without a file for the run's total.
+//! `test_coverage` maps each test to the lines it executed, one row per file,
+//! as last recorded by `runx run --coverage-map`.
+
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use super::Database;
+use crate::coverage::{CoverageReport, CoverageSummary, CoveredLines, FileCoverage, TestCoverage};
+
+/// Recorded coverage of a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunCoverage {
+    pub run_id: String,
+    #[serde(flatten)]
+    pub report: CoverageReport,
+    /// Whether the total line coverage reached the threshold in force
+    /// (`None` without threshold)
+    pub threshold_passed: Option<bool>,
+}
+
+fn summary_from_row(row: &Row, offset: usize) -> rusqlite::Result<CoverageSummary> {
+    Ok(CoverageSummary {
+        lines_covered: row.get::<_, i64>(offset)? as u64,
+        lines_total: row.get::<_, i64>(offset + 1)? as u64,
+        branches_covered: row.get::<_, i64>(offset + 2)? as u64,
+        branches_total: row.get::<_, i64>(offset + 3)? as u64,
+    })
+}
+
+impl Database {
+    /// Record the coverage of a run, judged against an optional threshold
+    pub fn save_coverage(&self, run_id: &str, report: &CoverageReport, threshold: Option<f64>) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO coverage_results
+                (run_id, file_path, line_coverage, branch_coverage, lines_covered, lines_total,
+                 branches_covered, branches_total, threshold_passed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+        )?;
+        let rows = std::iter::once((None, &report.total))
+            .chain(report.files.iter().map(|f| (Some(f.path.as_str()), &f.summary)));
+        for (path, summary) in rows {
+            stmt.execute(params![
+                run_id,
+                path,
+                summary.line_coverage(),
+                summary.branch_coverage(),
+                summary.lines_covered as i64,
+                summary.lines_total as i64,
+                summary.branches_covered as i64,
+                summary.branches_total as i64,
+                threshold.map(|t| summary.meets(t)),
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Coverage recorded for a run, if it was measured
+    pub fn get_coverage(&self, run_id: &str) -> Result<Option<RunCoverage>> {
+        let total = self.conn.query_row(
+            "SELECT lines_covered, lines_total, branches_covered, branches_total, threshold_passed
+             FROM coverage_results WHERE run_id = ?1 AND file_path IS NULL",
+            params![run_id],
+            |row| Ok((summary_from_row(row, 0)?, row.get::<_, Option<bool>>(4)?)),
+        ).optional()?;
+        let Some((total, threshold_passed)) = total else {
+            return Ok(None);
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path, lines_covered, lines_total, branches_covered, branches_total
+             FROM coverage_results WHERE run_id = ?1 AND file_path IS NOT NULL
+             ORDER BY file_path"
+        )?;
+        let files = stmt
+            .query_map(params![run_id], |row| Ok(FileCoverage {
+                path: row.get(0)?,
+                summary: summary_from_row(row, 1)?,
+            }))?
+            .collect::<std::result::Result<_, _>>()?;
+
+        Ok(Some(RunCoverage {
+            run_id: run_id.to_string(),
+            report: CoverageReport { total, files },
+            threshold_passed,
+        }))
+    }
+
+    /// Total line coverage of the most recent run measured
+    pub fn latest_line_coverage(&self) -> Result<Option<f64>> {
+        Ok(self.conn.query_row(
+            "SELECT line_coverage FROM coverage_results WHERE file_path IS NULL ORDER BY id DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        ).optional()?)
+    }
+
+    /// Record the lines executed by tests, replacing what was recorded for
+    /// them before
+    pub fn save_test_coverage(&self, run_id: &str, tests: &[TestCoverage]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for test in tests {
+            let classname = test.target.as_deref().unwrap_or_default();
+            tx.execute(
+                "DELETE FROM test_coverage WHERE test_name = ?1 AND classname = ?2",
+                params![test.test_name, classname],
+            )?;
+            for file in &test.files {
+                tx.execute(
+                    "INSERT INTO test_coverage (test_name, classname, file_path, line_ranges, run_id)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![test.test_name, classname, file.path, file.ranges_text(), run_id],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Lines executed by every mapped test
+    pub fn get_test_coverage(&self) -> Result<Vec<TestCoverage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT test_name, classname, file_path, line_ranges FROM test_coverage
+             ORDER BY test_name, classname, file_path"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+        )))?;
+
+        let mut tests: Vec<TestCoverage> = Vec::new();
+        for row in rows {
+            let (test_name, classname, path, ranges) = row?;
+            let target = (!classname.is_empty()).then_some(classname);
+            let file = CoveredLines::parse_ranges(&path, &ranges);
+            match tests.last_mut() {
+                Some(test) if test.test_name == test_name && test.target == target => test.files.push(file),
+                _ => tests.push(TestCoverage { test_name, target, files: vec![file] }),
+            }
+        }
+        Ok(tests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_coverage() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("runx.db")).unwrap();
+        let summary = |covered, total| CoverageSummary {
+            lines_covered: covered,
+            lines_total: total,
+            ..Default::default()
+        };
+        let report = CoverageReport {
+            total: summary(9, 12),
+            files: vec![
+                FileCoverage { path: "src/lib.rs".to_string(), summary: summary(8, 8) },
+                FileCoverage { path: "src/main.rs".to_string(), summary: summary(1, 4) },
+            ],
+        };
+
+        db.create_run("run-1", 0).unwrap();
+        assert!(db.get_coverage("run-1").unwrap().is_none());
+        db.save_coverage("run-1", &report, Some(80.0)).unwrap();
+
+        let coverage = db.get_coverage("run-1").unwrap().unwrap();
+        assert_eq!(coverage.report, report);
+        assert_eq!(coverage.threshold_passed, Some(false));
+        assert_eq!(db.latest_line_coverage().unwrap(), Some(75.0));
+    }
+
+    #[test]
+    fn test_save_test_coverage() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("runx.db")).unwrap();
+        let test = |files: Vec<CoveredLines>| TestCoverage {
+            test_name: "tests::it_works".to_string(),
+            target: Some("core (lib)".to_string()),
+            files,
+        };
+
+        db.create_run("run-1", 0).unwrap();
+        db.save_test_coverage("run-1", &[test(vec![
+            CoveredLines { path: "core/src/lib.rs".to_string(), ranges: vec![(1, 3)] },
+            CoveredLines { path: "core/src/parser.rs".to_string(), ranges: vec![(10, 12), (20, 20)] },
+        ])]).unwrap();
+
+        // A new mapping replaces the files the test executed before
+        let mapped = test(vec![CoveredLines { path: "core/src/lib.rs".to_string(), ranges: vec![(1, 5)] }]);
+        db.create_run("run-2", 0).unwrap();
+        db.save_test_coverage("run-2", std::slice::from_ref(&mapped)).unwrap();
+        assert_eq!(db.get_test_coverage().unwrap(), vec![mapped]);
+    }
+}
@@ -1,44 +1,265 @@
 //! Flaky test detection
+//!
+//! Every test outcome is appended to `test_history`; after each run the
+//! `flaky_tests` entry of the tests that ran is recomputed from their most
+//! recent outcomes.
 
 use anyhow::Result;
+use chrono::Utc;
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
 
 use super::Database;
 
+/// Status recorded in `test_history` for a test that failed, then passed
+/// when retried within the same run
+pub const PASSED_ON_RETRY: &str = "passed_on_retry";
+
+/// Number of recent outcomes a flaky score is computed over
+const FLAKY_WINDOW: i64 = 20;
+
+/// Score from which a test is reported as flaky
+const FLAKY_THRESHOLD: f64 = 10.0;
+
+/// Outcomes needed before flips alone make a test flaky
+const MIN_RUNS: i32 = 3;
+
 /// A flaky test entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlakyTest {
     pub test_name: String,
+    /// Target the test belongs to (empty when unknown)
+    pub classname: String,
     pub task_name: String,
     pub flaky_score: f64,
     pub total_runs: i32,
     pub pass_count: i32,
     pub fail_count: i32,
+    /// Runs in which the test only passed after being retried
+    pub retry_passes: i32,
+}
+
+/// A test outcome to append to `test_history`
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub test_name: String,
+    /// Target label, telling apart tests with the same name
+    pub classname: Option<String>,
+    /// Task result status, or [`PASSED_ON_RETRY`]
+    pub status: String,
+    pub duration_ms: i64,
 }
 
 impl Database {
     /// Get flaky tests (tests with inconsistent pass/fail patterns)
     pub fn get_flaky_tests(&self, limit: i32) -> Result<Vec<FlakyTest>> {
         let mut stmt = self.conn.prepare(
-            "SELECT test_name, task_name, flaky_score, total_runs, pass_count, fail_count
+            "SELECT test_name, classname, task_name, flaky_score, total_runs, pass_count, fail_count, retry_passes
              FROM flaky_tests
-             WHERE total_runs >= 3 AND flaky_score > 20 AND flaky_score < 80
-             ORDER BY ABS(flaky_score - 50) ASC
-             LIMIT ?1"
+             WHERE flaky_score >= ?1 AND (total_runs >= ?2 OR retry_passes > 0)
+             ORDER BY flaky_score DESC, test_name
+             LIMIT ?3"
         )?;
 
-        let rows = stmt.query_map(params![limit], |row| {
+        let rows = stmt.query_map(params![FLAKY_THRESHOLD, MIN_RUNS, limit], |row| {
             Ok(FlakyTest {
                 test_name: row.get(0)?,
-                task_name: row.get(1)?,
-                flaky_score: row.get(2)?,
-                total_runs: row.get(3)?,
-                pass_count: row.get(4)?,
-                fail_count: row.get(5)?,
+                classname: row.get(1)?,
+                task_name: row.get(2)?,
+                flaky_score: row.get(3)?,
+                total_runs: row.get(4)?,
+                pass_count: row.get(5)?,
+                fail_count: row.get(6)?,
+                retry_passes: row.get(7)?,
             })
         })?;
 
         rows.collect::<std::result::Result<Vec<_>, _>>().map_err(Into::into)
     }
+
+    /// Append a run's test outcomes to `test_history` and recompute the
+    /// flaky score of each test
+    pub fn record_test_history(&self, run_id: &str, entries: &[HistoryEntry]) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let tx = self.conn.unchecked_transaction()?;
+        for entry in entries {
+            tx.execute(
+                "INSERT INTO test_history (test_name, classname, task_name, status, duration_ms, run_id, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    entry.test_name,
+                    entry.classname,
+                    entry.test_name,
+                    entry.status,
+                    entry.duration_ms,
+                    run_id,
+                    now,
+                ],
+            )?;
+        }
+        for entry in entries {
+            self.update_flaky_score(&entry.test_name, entry.classname.as_deref())?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Recompute a test's `flaky_tests` entry from its recent outcomes
+    fn update_flaky_score(&self, test_name: &str, classname: Option<&str>) -> Result<()> {
+        let classname = classname.unwrap_or_default();
+        let mut stmt = self.conn.prepare(
+            "SELECT status FROM test_history
+             WHERE test_name = ?1 AND COALESCE(classname, '') = ?2
+                AND status IN ('passed', 'failed', 'timed_out', ?3)
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?4"
+        )?;
+        let mut outcomes: Vec<String> = stmt
+            .query_map(params![test_name, classname, PASSED_ON_RETRY, FLAKY_WINDOW], |row| row.get(0))?
+            .collect::<std::result::Result<_, _>>()?;
+        if outcomes.is_empty() {
+            return Ok(());
+        }
+        outcomes.reverse();
+
+        let statuses: Vec<&str> = outcomes.iter().map(String::as_str).collect();
+        let fail_count = statuses.iter().filter(|s| !passed(s)).count();
+        let retry_passes = statuses.iter().filter(|s| **s == PASSED_ON_RETRY).count();
+
+        self.conn.execute(
+            "INSERT INTO flaky_tests (test_name, classname, task_name, flaky_score, total_runs, pass_count, fail_count, retry_passes, updated_at)
+             VALUES (?1, ?2, ?1, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(test_name, classname) DO UPDATE SET
+                flaky_score = excluded.flaky_score,
+                total_runs = excluded.total_runs,
+                pass_count = excluded.pass_count,
+                fail_count = excluded.fail_count,
+                retry_passes = excluded.retry_passes,
+                updated_at = excluded.updated_at",
+            params![
+                test_name,
+                classname,
+                flaky_score(&statuses),
+                statuses.len() as i32,
+                (statuses.len() - fail_count) as i32,
+                fail_count as i32,
+                retry_passes as i32,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+fn passed(status: &str) -> bool {
+    status == "passed" || status == PASSED_ON_RETRY
+}
+
+/// Flip rate of a test's outcomes (oldest first), from 0 to 100: the share
+/// of consecutive runs whose outcome changed, a pass after retry counting as
+/// a flip of its own. Only flips into or out of an isolated outcome count
+/// (pass→fail→pass over one run), so a regression that failed several runs
+/// in a row before being fixed is not flakiness.
+pub fn flaky_score(outcomes: &[&str]) -> f64 {
+    let isolated = |i: usize| {
+        i > 0 && i + 1 < outcomes.len()
+            && passed(outcomes[i]) != passed(outcomes[i - 1])
+            && passed(outcomes[i]) != passed(outcomes[i + 1])
+    };
+    let flips = (0..outcomes.len().saturating_sub(1))
+        .filter(|&i| isolated(i) || isolated(i + 1))
+        .count();
+    let retry_passes = outcomes.iter().filter(|s| **s == PASSED_ON_RETRY).count();
+    let transitions = outcomes.len().saturating_sub(1).max(1);
+    ((flips + retry_passes) as f64 * 100.0 / transitions as f64).min(100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, status: &str) -> HistoryEntry {
+        HistoryEntry {
+            test_name: name.to_string(),
+            classname: Some("core (lib)".to_string()),
+            status: status.to_string(),
+            duration_ms: 5,
+        }
+    }
+
+    #[test]
+    fn test_flaky_score() {
+        assert_eq!(flaky_score(&["passed", "passed", "passed"]), 0.0);
+        // Broken for a few runs and fixed: a regression, not flakiness
+        assert_eq!(flaky_score(&["passed", "failed", "failed", "failed", "passed"]), 0.0);
+        assert_eq!(flaky_score(&["passed", "failed", "passed", "passed", "passed"]), 50.0);
+        assert_eq!(flaky_score(&["failed", "passed", "failed"]), 100.0);
+        assert!(flaky_score(&["passed", "passed", "failed"]) < FLAKY_THRESHOLD);
+        assert_eq!(flaky_score(&["passed", PASSED_ON_RETRY, "passed"]), 50.0);
+        assert_eq!(flaky_score(&["failed"]), 0.0);
+    }
+
+    #[test]
+    fn test_record_history_feeds_flaky_tests() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("runx.db")).unwrap();
+
+        for (run, status) in ["passed", "failed", "passed", "passed"].iter().enumerate() {
+            db.record_test_history(&format!("run-{}", run), &[
+                entry("a::flaky", status),
+                entry("a::stable", "passed"),
+                entry("a::broken", "failed"),
+            ]).unwrap();
+        }
+        db.record_test_history("run-4", &[entry("a::retried", PASSED_ON_RETRY)]).unwrap();
+
+        // The same name in another target has its own history
+        let mut other = entry("a::broken", "passed");
+        other.classname = Some("core (test api)".to_string());
+        db.record_test_history("run-5", &[other]).unwrap();
+
+        let flaky = db.get_flaky_tests(10).unwrap();
+        let names: Vec<&str> = flaky.iter().map(|t| t.test_name.as_str()).collect();
+        assert_eq!(names, vec!["a::retried", "a::flaky"]);
+        assert_eq!((flaky[1].total_runs, flaky[1].fail_count), (4, 1));
+        assert_eq!(flaky[0].retry_passes, 1);
+    }
+
+    #[test]
+    fn test_save_test_results() {
+        use crate::test_model::{TestStatus, TestTarget};
+        use crate::test_runner::{SingleTestResult, TestAttempt, TestRunResult};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("runx.db")).unwrap();
+        db.create_run("run-1", 1).unwrap();
+        let result = TestRunResult {
+            passed: 1,
+            test_results: vec![SingleTestResult {
+                name: "a::retried".to_string(),
+                status: TestStatus::Passed,
+                duration_ms: Some(4),
+                output: Vec::new(),
+                ignore_reason: None,
+                failure: None,
+                target: Some(TestTarget::from_binary_id("core")),
+                attempts: vec![TestAttempt {
+                    status: TestStatus::Failed,
+                    duration_ms: Some(3),
+                    output: vec!["boom".to_string()],
+                    failure: None,
+                }],
+            }],
+            ..Default::default()
+        };
+
+        let tasks = db.save_test_results("run-1", &result).unwrap();
+        assert_eq!((tasks[0].status.as_str(), tasks[0].retries), ("passed", 1));
+        assert_eq!(tasks[0].target.as_deref(), Some("core (lib)"));
+        let flaky = db.get_flaky_tests(10).unwrap();
+        assert_eq!((flaky[0].classname.as_str(), flaky[0].retry_passes), ("core (lib)", 1));
+        let attempts = db.get_task_attempts_for_run("run-1").unwrap();
+        let statuses: Vec<&str> = attempts[&tasks[0].id].iter().map(|a| a.status.as_str()).collect();
+        assert_eq!(statuses, vec!["failed", "passed"]);
+    }
 }
@@ -0,0 +1,185 @@
+//! Per-test history
+//!
+//! Reads back the outcomes appended to `test_history`, one test at a time.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::{Database, PASSED_ON_RETRY};
+
+/// One recorded execution of a test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestExecution {
+    pub run_id: String,
+    /// Target label (empty when unknown)
+    pub classname: String,
+    pub status: String,
+    pub duration_ms: i64,
+    pub created_at: DateTime<Utc>,
+    /// First line of the panic message of a failed execution
+    pub failure: Option<String>,
+}
+
+impl TestExecution {
+    /// Whether the test passed, possibly after a retry
+    pub fn passed(&self) -> bool {
+        self.status == "passed" || self.status == PASSED_ON_RETRY
+    }
+
+    /// Whether the test failed or timed out
+    pub fn failed(&self) -> bool {
+        self.status == "failed" || self.status == "timed_out"
+    }
+}
+
+impl Database {
+    /// Names of the recorded tests equal to `pattern`, or else containing it
+    pub fn find_test_names(&self, pattern: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT test_name FROM test_history
+             WHERE test_name = ?1 OR instr(test_name, ?1) > 0
+             ORDER BY test_name != ?1, test_name"
+        )?;
+        let names: Vec<String> = stmt
+            .query_map(params![pattern], |row| row.get(0))?
+            .collect::<std::result::Result<_, _>>()?;
+
+        if names.first().map(String::as_str) == Some(pattern) {
+            return Ok(vec![pattern.to_string()]);
+        }
+        Ok(names)
+    }
+
+    /// The `limit` most recent executions of a test, oldest first
+    pub fn get_test_history(&self, test_name: &str, limit: i32) -> Result<Vec<TestExecution>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT h.run_id, COALESCE(h.classname, ''), h.status, COALESCE(h.duration_ms, 0), h.created_at,
+                    CASE WHEN h.status IN ('failed', 'timed_out') THEN
+                        (SELECT tc.error_message FROM task_results tr
+                         JOIN test_cases tc ON tc.task_result_id = tr.id
+                         WHERE tr.run_id = h.run_id AND tr.task_name = h.test_name
+                            AND COALESCE(tr.target, '') = COALESCE(h.classname, '')
+                            AND tr.status = h.status
+                         LIMIT 1)
+                    END
+             FROM test_history h
+             WHERE h.test_name = ?1
+             ORDER BY h.created_at DESC, h.id DESC
+             LIMIT ?2"
+        )?;
+
+        let rows = stmt.query_map(params![test_name, limit], |row| {
+            let created_at: String = row.get(4)?;
+            let message: Option<String> = row.get(5)?;
+            Ok(TestExecution {
+                run_id: row.get(0)?,
+                classname: row.get(1)?,
+                status: row.get(2)?,
+                duration_ms: row.get(3)?,
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .map(|d| d.with_timezone(&Utc))
+                    .unwrap_or_default(),
+                failure: message.and_then(|m| m.lines().next().map(String::from)),
+            })
+        })?;
+
+        let mut executions = rows.collect::<std::result::Result<Vec<_>, _>>()?;
+        executions.reverse();
+        Ok(executions)
+    }
+}
+
+/// Executions split by target, since tests of the same name in different
+/// targets have unrelated histories
+pub fn by_target(executions: Vec<TestExecution>) -> BTreeMap<String, Vec<TestExecution>> {
+    let mut targets: BTreeMap<String, Vec<TestExecution>> = BTreeMap::new();
+    for execution in executions {
+        targets.entry(execution.classname.clone()).or_default().push(execution);
+    }
+    targets
+}
+
+/// The execution from which a test has been failing, when its latest
+/// execution failed
+pub fn failing_since(executions: &[TestExecution]) -> Option<&TestExecution> {
+    let passing = executions.iter().rposition(|e| !e.failed());
+    let start = passing.map_or(0, |i| i + 1);
+    executions.get(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::HistoryEntry;
+
+    #[test]
+    fn test_get_test_history() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("runx.db")).unwrap();
+        for (run, status) in ["passed", "failed", "passed", "failed", "timed_out"].iter().enumerate() {
+            db.record_test_history(&format!("run-{}", run), &[
+                HistoryEntry {
+                    test_name: "net::fetch".to_string(),
+                    classname: Some("net (lib)".to_string()),
+                    status: status.to_string(),
+                    duration_ms: run as i64,
+                },
+                HistoryEntry {
+                    test_name: "net::fetch".to_string(),
+                    classname: Some("net (integration http)".to_string()),
+                    status: "passed".to_string(),
+                    duration_ms: 1,
+                },
+            ]).unwrap();
+        }
+
+        assert_eq!(db.find_test_names("fetch").unwrap(), vec!["net::fetch"]);
+        assert!(db.find_test_names("parse").unwrap().is_empty());
+
+        let targets = by_target(db.get_test_history("net::fetch", 8).unwrap());
+        assert_eq!(targets.len(), 2);
+        let history = &targets["net (lib)"];
+        let runs: Vec<&str> = history.iter().map(|e| e.run_id.as_str()).collect();
+        assert_eq!(runs, vec!["run-1", "run-2", "run-3", "run-4"]);
+        assert_eq!(failing_since(history).unwrap().run_id, "run-3");
+        assert!(failing_since(&history[..2]).is_none());
+        assert!(failing_since(&targets["net (integration http)"]).is_none());
+    }
+
+    #[test]
+    fn test_failure_message_of_each_target() {
+        use crate::failure::TestFailure;
+        use crate::test_model::{TestStatus, TestTarget};
+        use crate::test_runner::{SingleTestResult, TestRunResult};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("runx.db")).unwrap();
+        db.create_run("run-1", 2).unwrap();
+        let failed = |binary_id: &str, message: &str| SingleTestResult {
+            name: "net::fetch".to_string(),
+            status: TestStatus::Failed,
+            duration_ms: Some(1),
+            output: Vec::new(),
+            ignore_reason: None,
+            failure: TestFailure::parse(&[
+                "thread 'net::fetch' panicked at src/lib.rs:3:5:".to_string(),
+                message.to_string(),
+            ]),
+            target: Some(TestTarget::from_binary_id(binary_id)),
+            attempts: Vec::new(),
+        };
+        let result = TestRunResult {
+            failed: 2,
+            test_results: vec![failed("net", "in lib"), failed("net::http", "in http")],
+            ..Default::default()
+        };
+        db.save_test_results("run-1", &result).unwrap();
+
+        let targets = by_target(db.get_test_history("net::fetch", 8).unwrap());
+        assert_eq!(targets["net (lib)"][0].failure.as_deref(), Some("in lib"));
+        assert_eq!(targets["net (integration http)"][0].failure.as_deref(), Some("in http"));
+    }
+}
@@ -2,15 +2,28 @@
 //!
 //! Provides SQLite storage for run history and task results.
 
+mod baseline;
+mod coverage;
 mod flaky;
+mod history;
+mod quarantine;
 mod schema;
 
+pub use baseline::{DurationBaseline, DurationRegression};
+pub use coverage::RunCoverage;
+pub use flaky::{FlakyTest, HistoryEntry, PASSED_ON_RETRY};
+pub use history::{by_target, failing_since, TestExecution};
+pub use quarantine::{Quarantine, QuarantinePolicy};
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
+use uuid::Uuid;
+
+use crate::test_runner::{SingleTestResult, TestRunResult};
 
 pub struct Database {
     conn: Connection,
@@ -40,12 +53,25 @@ impl Database {
             total_tasks,
             passed: 0,
             failed: 0,
+            source: None,
         })
     }
 
     pub fn finish_run(&self, id: &str, passed: i32, failed: i32) -> Result<()> {
-        let now = Utc::now();
         let status = if failed > 0 { "failed" } else { "passed" };
+        self.finish_run_with_status(id, status, passed, failed)
+    }
+
+    /// Finish a run where `quarantined` of the failures come from quarantined
+    /// tests, which do not make the run fail
+    pub fn finish_run_quarantined(&self, id: &str, passed: i32, failed: i32, quarantined: i32) -> Result<()> {
+        let status = if failed > quarantined { "failed" } else { "passed" };
+        self.finish_run_with_status(id, status, passed, failed)
+    }
+
+    /// Finish a run with an explicit final status (e.g. "cancelled")
+    pub fn finish_run_with_status(&self, id: &str, status: &str, passed: i32, failed: i32) -> Result<()> {
+        let now = Utc::now();
         self.conn.execute(
             "UPDATE runs SET finished_at = ?1, status = ?2, passed = ?3, failed = ?4 WHERE id = ?5",
             params![now.to_rfc3339(), status, passed, failed, id],
@@ -55,7 +81,7 @@ impl Database {
 
     pub fn get_run(&self, id: &str) -> Result<Option<Run>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, started_at, finished_at, status, total_tasks, passed, failed FROM runs WHERE id = ?1"
+            "SELECT id, started_at, finished_at, status, total_tasks, passed, failed, source FROM runs WHERE id = ?1"
         )?;
 
         let mut rows = stmt.query(params![id])?;
@@ -70,15 +96,53 @@ impl Database {
                 total_tasks: row.get(4)?,
                 passed: row.get(5)?,
                 failed: row.get(6)?,
+                source: row.get(7)?,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Tag a run as imported from `source`
+    pub fn set_run_source(&self, id: &str, source: &str) -> Result<()> {
+        self.conn.execute("UPDATE runs SET source = ?1 WHERE id = ?2", params![source, id])?;
+        Ok(())
+    }
+
+    /// Full id of the run whose id is, or starts with, `id`
+    pub fn resolve_run_id(&self, id: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM runs WHERE substr(id, 1, length(?1)) = ?1 ORDER BY id != ?1 LIMIT 2"
+        )?;
+        let ids: Vec<String> = stmt
+            .query_map(params![id], |row| row.get(0))?
+            .collect::<std::result::Result<_, _>>()?;
+
+        match ids.as_slice() {
+            [] => Ok(None),
+            [first, ..] if first == id => Ok(Some(first.clone())),
+            [only] => Ok(Some(only.clone())),
+            _ => anyhow::bail!("Run id '{}' matches several runs", id),
+        }
+    }
+
+    /// Id of the run started just before `id`
+    pub fn previous_run_id(&self, id: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM runs
+             WHERE started_at < (SELECT started_at FROM runs WHERE id = ?1)
+             ORDER BY started_at DESC LIMIT 1"
+        )?;
+        let mut rows = stmt.query(params![id])?;
+        Ok(match rows.next()? {
+            Some(row) => Some(row.get(0)?),
+            None => None,
+        })
+    }
+
     pub fn get_recent_runs(&self, limit: i32) -> Result<Vec<Run>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, started_at, finished_at, status, total_tasks, passed, failed
+            "SELECT id, started_at, finished_at, status, total_tasks, passed, failed, source
              FROM runs ORDER BY started_at DESC LIMIT ?1"
         )?;
 
@@ -93,6 +157,7 @@ impl Database {
                 total_tasks: row.get(4)?,
                 passed: row.get(5)?,
                 failed: row.get(6)?,
+                source: row.get(7)?,
             })
         })?;
 
@@ -103,8 +168,8 @@ impl Database {
 
     pub fn insert_task_result(&self, result: &TaskResult) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO task_results (id, run_id, task_name, category, status, duration_ms, started_at, output)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT INTO task_results (id, run_id, task_name, category, status, duration_ms, started_at, output, ignore_reason, retries, target)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 result.id,
                 result.run_id,
@@ -114,15 +179,38 @@ impl Database {
                 result.duration_ms,
                 result.started_at.to_rfc3339(),
                 result.output,
+                result.ignore_reason,
+                result.retries,
+                result.target,
             ],
         )?;
+
+        if result.error_message.is_some() || result.error_type.is_some() {
+            self.conn.execute(
+                "INSERT INTO test_cases (task_result_id, name, classname, status, duration_ms, error_message, error_type)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    result.id,
+                    result.task_name,
+                    crate::junit::classname(&result.task_name, result.target.as_deref()),
+                    result.status,
+                    result.duration_ms,
+                    result.error_message,
+                    result.error_type,
+                ],
+            )?;
+        }
         Ok(())
     }
 
     pub fn get_task_results_for_run(&self, run_id: &str) -> Result<Vec<TaskResult>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, run_id, task_name, category, status, duration_ms, started_at, output
-             FROM task_results WHERE run_id = ?1 ORDER BY started_at"
+            "SELECT tr.id, tr.run_id, tr.task_name, tr.category, tr.status, tr.duration_ms, tr.started_at,
+                    tr.output, tr.ignore_reason, tc.error_message, tc.error_type, tr.retries, tr.target,
+                    tc.classname
+             FROM task_results tr
+             LEFT JOIN test_cases tc ON tc.task_result_id = tr.id
+             WHERE tr.run_id = ?1 ORDER BY tr.started_at"
         )?;
 
         let rows = stmt.query_map(params![run_id], |row| {
@@ -135,12 +223,110 @@ impl Database {
                 duration_ms: row.get(5)?,
                 started_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?).unwrap().with_timezone(&Utc),
                 output: row.get(7)?,
+                ignore_reason: row.get(8)?,
+                error_message: row.get(9)?,
+                error_type: row.get(10)?,
+                retries: row.get(11)?,
+                target: row.get(12)?,
+                classname: row.get(13)?,
             })
         })?;
 
         rows.collect::<std::result::Result<Vec<_>, _>>().map_err(Into::into)
     }
 
+    /// Record every attempt of a retried test
+    pub fn insert_task_attempts(&self, attempts: &[TaskAttempt]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for attempt in attempts {
+            tx.execute(
+                "INSERT INTO task_attempts (task_result_id, attempt, status, duration_ms, output, error_message)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    attempt.task_result_id,
+                    attempt.attempt,
+                    attempt.status,
+                    attempt.duration_ms,
+                    attempt.output,
+                    attempt.error_message,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record the results of a test run, with the attempts of retried tests,
+    /// and append their outcomes to the test history. Returns the recorded
+    /// results.
+    pub fn save_test_results(&self, run_id: &str, result: &TestRunResult) -> Result<Vec<TaskResult>> {
+        let started_at = Utc::now();
+        let mut tasks = Vec::new();
+        let mut history = Vec::new();
+        for test in &result.test_results {
+            let task_result = TaskResult {
+                id: Uuid::new_v4().to_string(),
+                run_id: run_id.to_string(),
+                task_name: test.name.clone(),
+                category: Some("test".to_string()),
+                status: test.status.as_db_str().to_string(),
+                duration_ms: test.duration_ms.unwrap_or(0) as i64,
+                started_at,
+                output: if test.output.is_empty() { None } else { Some(test.output.join("\n")) },
+                ignore_reason: test.ignore_reason.clone(),
+                error_message: test.failure.as_ref().map(|f| f.message.clone()),
+                error_type: test.failure.as_ref().map(|f| f.kind.label().to_string()),
+                retries: test.attempts.len() as i32,
+                target: test.target.as_ref().map(|t| t.qualified_label()),
+                classname: None,
+            };
+            self.insert_task_result(&task_result)?;
+            if !test.attempts.is_empty() {
+                self.insert_task_attempts(&task_attempts(&task_result, test))?;
+            }
+            history.push(HistoryEntry {
+                test_name: test.name.clone(),
+                classname: task_result.target.clone(),
+                status: if test.passed_on_retry() {
+                    PASSED_ON_RETRY.to_string()
+                } else {
+                    task_result.status.clone()
+                },
+                duration_ms: task_result.duration_ms,
+            });
+            tasks.push(task_result);
+        }
+        self.record_test_history(run_id, &history)?;
+        Ok(tasks)
+    }
+
+    /// Attempts of the tests retried in a run, by task result id
+    pub fn get_task_attempts_for_run(&self, run_id: &str) -> Result<HashMap<String, Vec<TaskAttempt>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ta.task_result_id, ta.attempt, ta.status, ta.duration_ms, ta.output, ta.error_message
+             FROM task_attempts ta
+             JOIN task_results tr ON tr.id = ta.task_result_id
+             WHERE tr.run_id = ?1 ORDER BY ta.task_result_id, ta.attempt"
+        )?;
+
+        let mut attempts: HashMap<String, Vec<TaskAttempt>> = HashMap::new();
+        let rows = stmt.query_map(params![run_id], |row| {
+            Ok(TaskAttempt {
+                task_result_id: row.get(0)?,
+                attempt: row.get(1)?,
+                status: row.get(2)?,
+                duration_ms: row.get(3)?,
+                output: row.get(4)?,
+                error_message: row.get(5)?,
+            })
+        })?;
+        for attempt in rows {
+            let attempt = attempt?;
+            attempts.entry(attempt.task_result_id.clone()).or_default().push(attempt);
+        }
+        Ok(attempts)
+    }
+
     // === Stats ===
 
     pub fn get_dashboard_stats(&self) -> Result<DashboardStats> {
@@ -193,6 +379,7 @@ impl Database {
             avg_duration_ms,
             recent_runs,
             pass_rate_history,
+            line_coverage: self.latest_line_coverage()?,
         })
     }
 
@@ -209,7 +396,9 @@ impl Database {
         let mut total = 0;
         total += self.conn.execute("DELETE FROM artifacts", [])?;
         total += self.conn.execute("DELETE FROM coverage_results", [])?;
+        total += self.conn.execute("DELETE FROM test_coverage", [])?;
         total += self.conn.execute("DELETE FROM test_cases", [])?;
+        total += self.conn.execute("DELETE FROM task_attempts", [])?;
         total += self.conn.execute("DELETE FROM test_history", [])?;
         total += self.conn.execute("DELETE FROM task_results", [])?;
         total += self.conn.execute("DELETE FROM runs", [])?;
@@ -220,7 +409,7 @@ impl Database {
         let mut stmt = self.conn.prepare(
             "SELECT tr.task_name FROM task_results tr
              JOIN runs r ON tr.run_id = r.id
-             WHERE tr.status = 'failed'
+             WHERE tr.status IN ('failed', 'timed_out')
              AND r.id = (SELECT id FROM runs ORDER BY started_at DESC LIMIT 1)"
         )?;
 
@@ -240,6 +429,32 @@ pub struct Run {
     pub total_tasks: i32,
     pub passed: i32,
     pub failed: i32,
+    /// Where an imported run comes from (`runx import --source`)
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Every attempt of a retried test, the last one being its final result
+fn task_attempts(task_result: &TaskResult, test: &SingleTestResult) -> Vec<TaskAttempt> {
+    let last = TaskAttempt {
+        task_result_id: task_result.id.clone(),
+        attempt: test.attempts.len() as i32 + 1,
+        status: task_result.status.clone(),
+        duration_ms: task_result.duration_ms,
+        output: task_result.output.clone(),
+        error_message: task_result.error_message.clone(),
+    };
+    test.attempts.iter().enumerate()
+        .map(|(i, attempt)| TaskAttempt {
+            task_result_id: task_result.id.clone(),
+            attempt: i as i32 + 1,
+            status: attempt.status.as_db_str().to_string(),
+            duration_ms: attempt.duration_ms.unwrap_or(0) as i64,
+            output: if attempt.output.is_empty() { None } else { Some(attempt.output.join("\n")) },
+            error_message: attempt.failure.as_ref().map(|f| f.message.clone()),
+        })
+        .chain(std::iter::once(last))
+        .collect()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -252,6 +467,44 @@ pub struct TaskResult {
     pub duration_ms: i64,
     pub started_at: DateTime<Utc>,
     pub output: Option<String>,
+    /// Reason given with `#[ignore = "..."]`
+    #[serde(default)]
+    pub ignore_reason: Option<String>,
+    /// Panic message of a failed test (stored in `test_cases`)
+    #[serde(default)]
+    pub error_message: Option<String>,
+    /// Failure kind: "assert_eq", "assert_ne", "assert" or "panic"
+    #[serde(default)]
+    pub error_type: Option<String>,
+    /// Times the test was run again after failing
+    #[serde(default)]
+    pub retries: i32,
+    /// Target the test was compiled into, e.g. "core (lib)"
+    #[serde(default)]
+    pub target: Option<String>,
+    /// JUnit class of a failed test (stored in `test_cases`, derived from
+    /// the name and target when inserted)
+    #[serde(default)]
+    pub classname: Option<String>,
+}
+
+impl TaskResult {
+    /// Failed at first, then passed when retried
+    pub fn passed_on_retry(&self) -> bool {
+        self.status == "passed" && self.retries > 0
+    }
+}
+
+/// One attempt of a retried test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskAttempt {
+    pub task_result_id: String,
+    /// 1 for the first run of the test
+    pub attempt: i32,
+    pub status: String,
+    pub duration_ms: i64,
+    pub output: Option<String>,
+    pub error_message: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -268,6 +521,9 @@ pub struct DashboardStats {
     pub avg_duration_ms: i64,
     pub recent_runs: Vec<Run>,
     pub pass_rate_history: Vec<PassRatePoint>,
+    /// Line coverage of the most recent run measured with `--coverage`
+    #[serde(default)]
+    pub line_coverage: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
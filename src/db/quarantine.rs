@@ -0,0 +1,287 @@
+//! Quarantined tests
+//!
+//! Quarantined tests still run and are recorded, but their failures do not
+//! fail the run. Tests are quarantined by target and name, using the
+//! `flaky_tests.quarantined` column; a test quarantined by hand before it
+//! ever ran has no target and is quarantined in every target.
+
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::{Database, PASSED_ON_RETRY};
+use crate::config::QuarantineConfig;
+
+/// `quarantined` value of a test quarantined with `runx quarantine add`
+const MANUAL: i32 = 1;
+/// `quarantined` value of a test quarantined by the automatic policy
+const AUTO: i32 = 2;
+/// `quarantined` value of a test removed from quarantine by hand, which the
+/// automatic policy leaves alone
+const RELEASED: i32 = -1;
+
+/// Latest outcomes that, all passed, keep a test out of automatic quarantine
+const STEADY_PASSES: usize = 3;
+
+/// A quarantined test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedTest {
+    pub test_name: String,
+    /// Target label, `None` for every target
+    pub target: Option<String>,
+    /// Quarantined by the automatic policy rather than by hand
+    pub auto: bool,
+    pub flaky_score: f64,
+    pub total_runs: i32,
+}
+
+impl QuarantinedTest {
+    /// Name and target, as shown in messages
+    pub fn label(&self) -> String {
+        match self.target {
+            Some(ref target) => format!("{} ({})", self.test_name, target),
+            None => self.test_name.clone(),
+        }
+    }
+}
+
+/// The tests in quarantine, by target and name
+#[derive(Debug, Clone, Default)]
+pub struct Quarantine {
+    /// (target label, test name); an empty label stands for every target
+    tests: HashSet<(String, String)>,
+}
+
+impl Quarantine {
+    pub fn insert(&mut self, target: Option<&str>, test_name: &str) {
+        self.tests.insert((target.unwrap_or_default().to_string(), test_name.to_string()));
+    }
+
+    /// Whether a test of a target is in quarantine
+    pub fn contains(&self, target: Option<&str>, test_name: &str) -> bool {
+        let key = |target: &str| (target.to_string(), test_name.to_string());
+        self.tests.contains(&key("")) || target.is_some_and(|t| self.tests.contains(&key(t)))
+    }
+}
+
+/// When to quarantine a test automatically, from `[quarantine]` in runx.toml
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuarantinePolicy {
+    /// Flaky score from which a test is quarantined
+    pub min_score: f64,
+    /// Recorded outcomes needed before a test can be quarantined
+    pub min_runs: i32,
+}
+
+impl QuarantinePolicy {
+    /// The configured policy, or `None` when automatic quarantine is off
+    pub fn from_config(config: &QuarantineConfig) -> Option<Self> {
+        config.auto.then(|| Self {
+            min_score: config.min_score.unwrap_or(30.0),
+            min_runs: config.min_runs.unwrap_or(5),
+        })
+    }
+}
+
+impl Database {
+    /// The quarantined tests
+    pub fn quarantined_tests(&self) -> Result<Quarantine> {
+        let mut stmt = self.conn.prepare(
+            "SELECT classname, test_name FROM flaky_tests WHERE quarantined > 0"
+        )?;
+        let tests = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Quarantine { tests })
+    }
+
+    /// Quarantined tests, most flaky first
+    pub fn get_quarantined_tests(&self) -> Result<Vec<QuarantinedTest>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT test_name, classname, quarantined, flaky_score, total_runs
+             FROM flaky_tests
+             WHERE quarantined > 0
+             ORDER BY flaky_score DESC, test_name, classname"
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(QuarantinedTest {
+                test_name: row.get(0)?,
+                target: Some(row.get::<_, String>(1)?).filter(|t| !t.is_empty()),
+                auto: row.get::<_, i32>(2)? == AUTO,
+                flaky_score: row.get(3)?,
+                total_runs: row.get(4)?,
+            })
+        })?;
+
+        rows.collect::<std::result::Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Quarantine a test by hand, in one target or in every target it ran
+    /// in. Returns false if it already was.
+    pub fn quarantine_test(&self, test_name: &str, target: Option<&str>) -> Result<bool> {
+        let known: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM flaky_tests WHERE test_name = ?1 AND (?2 IS NULL OR classname = ?2)",
+            params![test_name, target],
+            |row| row.get(0),
+        )?;
+        if known == 0 {
+            // Not run yet: track it under its target, or for every target
+            self.conn.execute(
+                "INSERT INTO flaky_tests (test_name, classname, task_name, quarantined, updated_at)
+                 VALUES (?1, ?2, ?1, ?3, ?4)",
+                params![test_name, target.unwrap_or_default(), MANUAL, Utc::now().to_rfc3339()],
+            )?;
+            return Ok(true);
+        }
+
+        let updated = self.conn.execute(
+            "UPDATE flaky_tests SET quarantined = ?1
+             WHERE test_name = ?2 AND (?3 IS NULL OR classname = ?3) AND quarantined <= 0",
+            params![MANUAL, test_name, target],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Take a test out of quarantine, in one target or in every target; the
+    /// automatic policy will not put it back. Returns false if it was not
+    /// quarantined.
+    pub fn release_test(&self, test_name: &str, target: Option<&str>) -> Result<bool> {
+        let quarantined: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM flaky_tests
+             WHERE test_name = ?1 AND (?2 IS NULL OR classname = ?2) AND quarantined > 0",
+            params![test_name, target],
+            |row| row.get(0),
+        )?;
+        self.conn.execute(
+            "UPDATE flaky_tests SET quarantined = ?1 WHERE test_name = ?2 AND (?3 IS NULL OR classname = ?3)",
+            params![RELEASED, test_name, target],
+        )?;
+        Ok(quarantined > 0)
+    }
+
+    /// Quarantine the tests the policy selects and return them. A test whose
+    /// latest outcomes all passed is left alone: it no longer fails.
+    pub fn apply_quarantine_policy(&self, policy: &QuarantinePolicy) -> Result<Vec<QuarantinedTest>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT test_name, classname, flaky_score, total_runs FROM flaky_tests
+             WHERE quarantined = 0 AND flaky_score >= ?1 AND total_runs >= ?2
+             ORDER BY test_name, classname"
+        )?;
+        let candidates: Vec<(String, String, f64, i32)> = stmt
+            .query_map(params![policy.min_score, policy.min_runs], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<std::result::Result<_, _>>()?;
+
+        let mut quarantined = Vec::new();
+        for (test_name, classname, flaky_score, total_runs) in candidates {
+            if self.passes_steadily(&test_name, &classname)? {
+                continue;
+            }
+            self.conn.execute(
+                "UPDATE flaky_tests SET quarantined = ?1 WHERE test_name = ?2 AND classname = ?3",
+                params![AUTO, test_name, classname],
+            )?;
+            quarantined.push(QuarantinedTest {
+                test_name,
+                target: Some(classname).filter(|t| !t.is_empty()),
+                auto: true,
+                flaky_score,
+                total_runs,
+            });
+        }
+        Ok(quarantined)
+    }
+
+    /// Whether the latest outcomes of a test all passed at the first attempt
+    fn passes_steadily(&self, test_name: &str, classname: &str) -> Result<bool> {
+        let mut stmt = self.conn.prepare(
+            "SELECT status FROM test_history
+             WHERE test_name = ?1 AND COALESCE(classname, '') = ?2
+                AND status IN ('passed', 'failed', 'timed_out', ?3)
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?4"
+        )?;
+        let statuses: Vec<String> = stmt
+            .query_map(params![test_name, classname, PASSED_ON_RETRY, STEADY_PASSES as i64], |row| row.get(0))?
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(statuses.len() == STEADY_PASSES && statuses.iter().all(|s| s == "passed"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::HistoryEntry;
+
+    fn record(db: &Database, run: usize, name: &str, status: &str) {
+        record_in(db, run, "net (lib)", name, status);
+    }
+
+    fn record_in(db: &Database, run: usize, target: &str, name: &str, status: &str) {
+        db.record_test_history(&format!("run-{}", run), &[HistoryEntry {
+            test_name: name.to_string(),
+            classname: Some(target.to_string()),
+            status: status.to_string(),
+            duration_ms: 1,
+        }]).unwrap();
+    }
+
+    #[test]
+    fn test_manual_quarantine() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("runx.db")).unwrap();
+
+        assert!(db.quarantine_test("net::fetch", None).unwrap());
+        assert!(!db.quarantine_test("net::fetch", None).unwrap());
+        // Still quarantined once it has a history in some target
+        record(&db, 0, "net::fetch", "failed");
+        assert!(db.quarantined_tests().unwrap().contains(Some("net (lib)"), "net::fetch"));
+
+        assert!(db.release_test("net::fetch", None).unwrap());
+        assert!(!db.release_test("net::fetch", None).unwrap());
+        assert!(db.get_quarantined_tests().unwrap().is_empty());
+
+        // Quarantining a test of one target leaves its namesakes alone
+        record_in(&db, 1, "api (lib)", "tests::it_works", "failed");
+        record_in(&db, 1, "cli (lib)", "tests::it_works", "failed");
+        assert!(db.quarantine_test("tests::it_works", Some("api (lib)")).unwrap());
+        let quarantine = db.quarantined_tests().unwrap();
+        assert!(quarantine.contains(Some("api (lib)"), "tests::it_works"));
+        assert!(!quarantine.contains(Some("cli (lib)"), "tests::it_works"));
+    }
+
+    #[test]
+    fn test_quarantine_policy() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open(&dir.path().join("runx.db")).unwrap();
+        for (run, status) in ["passed", "failed", "passed", "failed"].iter().enumerate() {
+            record(&db, run, "net::flaky", status);
+            record(&db, run, "net::released", status);
+            record(&db, run, "net::stable", "passed");
+        }
+        // Flaky once, then passing steadily
+        for (run, status) in ["passed", "failed", "passed", "failed", "passed", "passed", "passed"].iter().enumerate() {
+            record(&db, run, "net::settled", status);
+        }
+        // A regression that was fixed
+        for (run, status) in ["passed", "failed", "failed", "failed", "passed"].iter().enumerate() {
+            record(&db, run, "net::fixed", status);
+        }
+        db.quarantine_test("net::released", None).unwrap();
+        db.release_test("net::released", None).unwrap();
+
+        let policy = QuarantinePolicy { min_score: 30.0, min_runs: 5 };
+        assert!(db.apply_quarantine_policy(&policy).unwrap().is_empty());
+
+        let policy = QuarantinePolicy { min_score: 30.0, min_runs: 4 };
+        let labels: Vec<String> = db.apply_quarantine_policy(&policy).unwrap().iter().map(|t| t.label()).collect();
+        assert_eq!(labels, vec!["net::flaky (net (lib))"]);
+        let quarantined = db.get_quarantined_tests().unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert!(quarantined[0].auto);
+    }
+}
@@ -5,7 +5,7 @@ use rusqlite::Connection;
 
 /// Current schema version (used for documentation/debugging)
 #[allow(dead_code)]
-const SCHEMA_VERSION: i32 = 3;
+const SCHEMA_VERSION: i32 = 10;
 
 /// Run all pending migrations
 pub fn run_migrations(conn: &Connection) -> Result<()> {
@@ -38,6 +38,34 @@ pub fn run_migrations(conn: &Connection) -> Result<()> {
         migrate_v3(conn)?;
     }
 
+    if current_version < 4 {
+        migrate_v4(conn)?;
+    }
+
+    if current_version < 5 {
+        migrate_v5(conn)?;
+    }
+
+    if current_version < 6 {
+        migrate_v6(conn)?;
+    }
+
+    if current_version < 7 {
+        migrate_v7(conn)?;
+    }
+
+    if current_version < 8 {
+        migrate_v8(conn)?;
+    }
+
+    if current_version < 9 {
+        migrate_v9(conn)?;
+    }
+
+    if current_version < 10 {
+        migrate_v10(conn)?;
+    }
+
     Ok(())
 }
 
@@ -204,6 +232,159 @@ fn migrate_v3(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+/// Ignore reasons of `#[ignore = "..."]` tests
+fn migrate_v4(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        ALTER TABLE task_results ADD COLUMN ignore_reason TEXT;
+
+        INSERT INTO schema_migrations (version, applied_at) VALUES (4, datetime('now'));
+        "#,
+    )?;
+
+    Ok(())
+}
+
+/// Flaky tests keyed by name and target, with passes after retry. Nothing
+/// wrote to the v2 table, so it is recreated.
+fn migrate_v5(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        DROP TABLE IF EXISTS flaky_tests;
+
+        CREATE TABLE flaky_tests (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            test_name TEXT NOT NULL,
+            classname TEXT NOT NULL DEFAULT '',
+            task_name TEXT NOT NULL,
+            flaky_score REAL NOT NULL DEFAULT 0,
+            total_runs INTEGER NOT NULL DEFAULT 0,
+            pass_count INTEGER NOT NULL DEFAULT 0,
+            fail_count INTEGER NOT NULL DEFAULT 0,
+            retry_passes INTEGER NOT NULL DEFAULT 0,
+            quarantined INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL,
+            UNIQUE(test_name, classname)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_flaky_tests_score ON flaky_tests(flaky_score DESC);
+        CREATE INDEX IF NOT EXISTS idx_flaky_tests_quarantined ON flaky_tests(quarantined);
+
+        INSERT INTO schema_migrations (version, applied_at) VALUES (5, datetime('now'));
+        "#,
+    )?;
+
+    Ok(())
+}
+
+/// Attempts of retried tests
+fn migrate_v6(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        ALTER TABLE task_results ADD COLUMN retries INTEGER NOT NULL DEFAULT 0;
+
+        CREATE TABLE IF NOT EXISTS task_attempts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_result_id TEXT NOT NULL,
+            attempt INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            duration_ms INTEGER,
+            output TEXT,
+            error_message TEXT,
+            FOREIGN KEY (task_result_id) REFERENCES task_results(id),
+            UNIQUE(task_result_id, attempt)
+        );
+
+        INSERT INTO schema_migrations (version, applied_at) VALUES (6, datetime('now'));
+        "#,
+    )?;
+
+    Ok(())
+}
+
+/// Target of each test result, grouping tests into JUnit suites
+fn migrate_v7(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        ALTER TABLE task_results ADD COLUMN target TEXT;
+
+        INSERT INTO schema_migrations (version, applied_at) VALUES (7, datetime('now'));
+        "#,
+    )?;
+
+    Ok(())
+}
+
+/// Origin of runs imported from other tools
+fn migrate_v8(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        ALTER TABLE runs ADD COLUMN source TEXT;
+
+        INSERT INTO schema_migrations (version, applied_at) VALUES (8, datetime('now'));
+        "#,
+    )?;
+
+    Ok(())
+}
+
+/// Coverage of each run, per file and in total. Nothing wrote to the v2
+/// table, so it is recreated.
+fn migrate_v9(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        DROP TABLE IF EXISTS coverage_results;
+
+        CREATE TABLE coverage_results (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id TEXT NOT NULL,
+            -- NULL for the run's total
+            file_path TEXT,
+            line_coverage REAL,
+            branch_coverage REAL,
+            lines_covered INTEGER,
+            lines_total INTEGER,
+            branches_covered INTEGER,
+            branches_total INTEGER,
+            threshold_passed INTEGER,
+            FOREIGN KEY (run_id) REFERENCES runs(id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_coverage_run ON coverage_results(run_id);
+
+        INSERT INTO schema_migrations (version, applied_at) VALUES (9, datetime('now'));
+        "#,
+    )?;
+
+    Ok(())
+}
+
+/// Lines each test executed, recorded by `runx run --coverage-map`
+fn migrate_v10(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS test_coverage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            test_name TEXT NOT NULL,
+            -- Target label, '' when unknown
+            classname TEXT NOT NULL DEFAULT '',
+            file_path TEXT NOT NULL,
+            -- Executed lines, e.g. "3-7,12"
+            line_ranges TEXT NOT NULL,
+            run_id TEXT NOT NULL,
+            UNIQUE(test_name, classname, file_path),
+            FOREIGN KEY (run_id) REFERENCES runs(id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_test_coverage_file ON test_coverage(file_path);
+
+        INSERT INTO schema_migrations (version, applied_at) VALUES (10, datetime('now'));
+        "#,
+    )?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,5 +412,7 @@ mod tests {
         assert!(tables.contains(&"task_cache".to_string()));
         assert!(tables.contains(&"coverage_results".to_string()));
         assert!(tables.contains(&"artifacts".to_string()));
+        assert!(tables.contains(&"task_attempts".to_string()));
+        assert!(tables.contains(&"test_coverage".to_string()));
     }
 }
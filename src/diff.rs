@@ -0,0 +1,256 @@
+//! Side-by-side diffs of assertion operands
+//!
+//! Lines up the `left` and `right` values of a failed `assert_eq!` and marks
+//! the characters that differ. One-line `Debug` output is reflowed the way
+//! `{:#?}` prints it so large structs diff field by field.
+
+use serde::Serialize;
+use similar::{capture_diff_slices, Algorithm, ChangeTag, DiffTag, TextDiff};
+
+/// One-line values longer than this are reflowed before diffing
+const REFLOW_WIDTH: usize = 40;
+
+/// A run of characters within one side of a row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Segment {
+    pub text: String,
+    /// Whether these characters differ from the other side
+    pub changed: bool,
+}
+
+/// A row of the side-by-side view; a missing side means the line only
+/// exists on the other one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffRow {
+    pub left: Option<Vec<Segment>>,
+    pub right: Option<Vec<Segment>>,
+}
+
+impl DiffRow {
+    /// Whether both sides hold the same line
+    pub fn is_equal(&self) -> bool {
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => {
+                left.iter().chain(right).all(|s| !s.changed)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Diff two assertion operands line by line, then character by character
+/// within lines that were changed
+pub fn side_by_side(left: &str, right: &str) -> Vec<DiffRow> {
+    let (left, right) = if should_reflow(left) || should_reflow(right) {
+        (reflow_debug(left), reflow_debug(right))
+    } else {
+        (left.to_string(), right.to_string())
+    };
+    let old: Vec<&str> = left.lines().collect();
+    let new: Vec<&str> = right.lines().collect();
+
+    let mut rows = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, &old, &new) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        match tag {
+            DiffTag::Equal => {
+                rows.extend(old[old_range].iter().map(|line| DiffRow {
+                    left: Some(unchanged(line)),
+                    right: Some(unchanged(line)),
+                }));
+            }
+            DiffTag::Delete => {
+                rows.extend(old[old_range].iter().map(|line| DiffRow {
+                    left: Some(changed(line)),
+                    right: None,
+                }));
+            }
+            DiffTag::Insert => {
+                rows.extend(new[new_range].iter().map(|line| DiffRow {
+                    left: None,
+                    right: Some(changed(line)),
+                }));
+            }
+            DiffTag::Replace => {
+                let old_lines = &old[old_range];
+                let new_lines = &new[new_range];
+                for i in 0..old_lines.len().max(new_lines.len()) {
+                    rows.push(match (old_lines.get(i), new_lines.get(i)) {
+                        (Some(a), Some(b)) => {
+                            let (left, right) = char_diff(a, b);
+                            DiffRow { left: Some(left), right: Some(right) }
+                        }
+                        (a, b) => DiffRow {
+                            left: a.map(|l| changed(l)),
+                            right: b.map(|l| changed(l)),
+                        },
+                    });
+                }
+            }
+        }
+    }
+    rows
+}
+
+fn should_reflow(value: &str) -> bool {
+    !value.contains('\n') && value.len() > REFLOW_WIDTH
+}
+
+fn unchanged(line: &str) -> Vec<Segment> {
+    vec![Segment { text: line.to_string(), changed: false }]
+}
+
+fn changed(line: &str) -> Vec<Segment> {
+    vec![Segment { text: line.to_string(), changed: true }]
+}
+
+/// Split a pair of lines into the segments shared by both and the ones only
+/// on each side
+fn char_diff(left: &str, right: &str) -> (Vec<Segment>, Vec<Segment>) {
+    let mut old = Vec::new();
+    let mut new = Vec::new();
+    for change in TextDiff::from_chars(left, right).iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Equal => {
+                push_segment(&mut old, change.value(), false);
+                push_segment(&mut new, change.value(), false);
+            }
+            ChangeTag::Delete => push_segment(&mut old, change.value(), true),
+            ChangeTag::Insert => push_segment(&mut new, change.value(), true),
+        }
+    }
+    (old, new)
+}
+
+fn push_segment(segments: &mut Vec<Segment>, text: &str, changed: bool) {
+    match segments.last_mut() {
+        Some(last) if last.changed == changed => last.text.push_str(text),
+        _ => segments.push(Segment { text: text.to_string(), changed }),
+    }
+}
+
+/// Reflow a one-line `Debug` value as `{:#?}` would print it; a string
+/// literal is split on its `\n` escapes instead
+pub fn reflow_debug(value: &str) -> String {
+    let value = value.trim();
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        if inner.contains("\\n") {
+            return inner.split("\\n").collect::<Vec<_>>().join("\n");
+        }
+        return value.to_string();
+    }
+
+    let chars: Vec<char> = value.chars().collect();
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' => {
+                // Copy the literal as is, escapes included
+                out.push(c);
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        out.push(chars[i]);
+                        i += 1;
+                    }
+                    out.push(chars[i]);
+                    i += 1;
+                }
+                if i < chars.len() {
+                    out.push(c);
+                }
+            }
+            '{' | '[' | '(' => {
+                let close = match c { '{' => '}', '[' => ']', _ => ')' };
+                let next = chars[i + 1..].iter().position(|c| !c.is_whitespace()).map(|p| i + 1 + p);
+                if next.is_some_and(|n| chars[n] == close) {
+                    // Empty: `{}`, `[]`, `()`
+                    out.push(c);
+                    out.push(close);
+                    i = next.unwrap_or(i);
+                } else {
+                    depth += 1;
+                    out.push(c);
+                    newline(&mut out, depth);
+                    i = skip_spaces(&chars, i + 1) - 1;
+                }
+            }
+            '}' | ']' | ')' => {
+                out.truncate(out.trim_end().len());
+                if !out.ends_with(',') {
+                    out.push(',');
+                }
+                depth = depth.saturating_sub(1);
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(',');
+                newline(&mut out, depth);
+                i = skip_spaces(&chars, i + 1) - 1;
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+fn newline(out: &mut String, depth: usize) {
+    out.push('\n');
+    out.push_str(&"    ".repeat(depth));
+}
+
+fn skip_spaces(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && chars[i] == ' ' {
+        i += 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflow_debug() {
+        assert_eq!(
+            reflow_debug(r#"Point { x: 1, tags: ["a, b", "c"], empty: [] }"#),
+            "Point {\n    x: 1,\n    tags: [\n        \"a, b\",\n        \"c\",\n    ],\n    empty: [],\n}"
+        );
+        assert_eq!(reflow_debug("Some((1, '}'))"), "Some(\n    (\n        1,\n        '}',\n    ),\n)");
+        assert_eq!(reflow_debug(r#""first\nsecond""#), "first\nsecond");
+        assert_eq!(reflow_debug("42"), "42");
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let rows = side_by_side(
+            "User { name: \"alice\", age: 30, admin: false }",
+            "User { name: \"alice\", age: 31, admin: false, email: None }",
+        );
+
+        assert_eq!(rows.len(), 6);
+        assert!(rows[0].is_equal());
+        assert!(rows[1].is_equal());
+        assert_eq!(rows[2].left.as_ref().unwrap(), &vec![
+            Segment { text: "    age: 3".to_string(), changed: false },
+            Segment { text: "0".to_string(), changed: true },
+            Segment { text: ",".to_string(), changed: false },
+        ]);
+        assert!(!rows[2].is_equal());
+        assert!(rows[3].is_equal());
+        assert_eq!(rows[4].left, None);
+        assert_eq!(rows[4].right, Some(changed("    email: None,")));
+        assert!(rows[5].is_equal());
+    }
+
+    #[test]
+    fn test_short_values_stay_on_one_line() {
+        let rows = side_by_side("4", "5");
+        assert_eq!(rows, vec![DiffRow { left: Some(changed("4")), right: Some(changed("5")) }]);
+    }
+}
@@ -1,19 +1,286 @@
 //! Test discovery module
 //!
-//! Discovers tests by parsing output from `cargo test -- --list`
-//! and builds a hierarchical test tree.
+//! Builds every test target of the workspace once
+//! (`cargo test --no-run --message-format json`), lists each test binary
+//! separately and builds a tree rooted at package → target → module.
+//! cargo decides what is rebuilt; the tests of a binary are only listed
+//! again when cargo produced a new one.
+//! With the nextest backend, `cargo nextest list` is used instead.
 
 use anyhow::{Context, Result};
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::UNIX_EPOCH;
+
+use crate::source_index::SourceIndex;
+use crate::test_model::{TargetKind, Test, TestNode, TestStatus, TestTarget};
+use crate::test_runner::TestBackend;
+
+/// libtest's JSON listing reports ignored tests, ignore reasons and source
+/// locations in a single pass
+const JSON_LIST_ARGS: &[&str] = &["--list", "--format", "json", "-Z", "unstable-options"];
+
+/// A compiled test executable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestBinary {
+    pub target: TestTarget,
+    pub executable: PathBuf,
+    /// Package directory, used as working directory like `cargo test` does
+    pub package_dir: PathBuf,
+}
+
+/// Compiled test targets of a workspace and the tests they contain
+#[derive(Debug, Clone)]
+pub struct TestBuild {
+    pub binaries: Vec<TestBinary>,
+    /// Libraries whose documentation tests run through `cargo test --doc`
+    pub doc_targets: Vec<TestTarget>,
+    pub tests: Vec<Test>,
+}
+
+impl TestBuild {
+    /// Tests compiled into the given target
+    pub fn tests_in<'a>(&'a self, target: &'a TestTarget) -> impl Iterator<Item = &'a Test> {
+        self.tests.iter().filter(move |t| t.target.as_ref() == Some(target))
+    }
+}
+
+/// `cargo metadata --format-version 1 --no-deps` (only the fields we use)
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    packages: Vec<MetadataPackage>,
+    workspace_members: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetadataPackage {
+    id: String,
+    name: String,
+    manifest_path: PathBuf,
+    targets: Vec<MetadataTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetadataTarget {
+    name: String,
+    kind: Vec<String>,
+    #[serde(default)]
+    doctest: bool,
+    #[serde(default)]
+    src_path: PathBuf,
+}
+
+/// A line of `cargo ... --message-format json` output
+#[derive(Debug, Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum CargoMessage {
+    CompilerArtifact(Artifact),
+    CompilerMessage { message: Diagnostic },
+    BuildFinished { success: bool },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct Artifact {
+    package_id: String,
+    target: ArtifactTarget,
+    profile: ArtifactProfile,
+    #[serde(default)]
+    filenames: Vec<PathBuf>,
+    executable: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtifactTarget {
+    name: String,
+    kind: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtifactProfile {
+    test: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Diagnostic {
+    level: String,
+    rendered: Option<String>,
+}
+
+/// A line of libtest's `--list --format json` output
+#[derive(Debug, Deserialize)]
+struct ListRecord {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    name: Option<String>,
+    #[serde(default)]
+    ignore: bool,
+    #[serde(default)]
+    ignore_message: String,
+    #[serde(default)]
+    source_path: String,
+    #[serde(default)]
+    start_line: u32,
+}
+
+/// Workspace packages as reported by `cargo metadata`
+struct Workspace {
+    /// Package ID → (package name, package directory)
+    packages: HashMap<String, (String, PathBuf)>,
+    /// Members whose library has documentation tests
+    doctest_packages: Vec<TestTarget>,
+    /// Root source file of every member target
+    roots: Vec<(TestTarget, PathBuf)>,
+}
+
+/// What `cargo test --no-run` built for the workspace members
+#[derive(Debug)]
+struct BuildArtifacts {
+    binaries: Vec<TestBinary>,
+    /// Library file of each package, which its documentation tests are
+    /// compiled against
+    libraries: HashMap<String, PathBuf>,
+}
+
+/// Test listings of the last build, by the artifact they were listed from
+/// and its modification time: an artifact cargo rebuilt is listed again
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TestListings(HashMap<PathBuf, (u64, Vec<Test>)>);
+
+impl TestListings {
+    fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path).ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// The tests listed from `artifact`, unless it was rebuilt since
+    fn get(&self, artifact: &Path) -> Option<Vec<Test>> {
+        let (modified, tests) = self.0.get(artifact)?;
+        (modified_time(artifact)? == *modified).then(|| tests.clone())
+    }
+
+    fn insert(&mut self, artifact: &Path, tests: Vec<Test>) {
+        if let Some(modified) = modified_time(artifact) {
+            self.0.insert(artifact.to_path_buf(), (modified, tests));
+        }
+    }
+
+    /// A missing cache only costs listing the tests again next time
+    fn save(&self, path: &Path) {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Ok(json) = serde_json::to_string(self) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+}
 
-use crate::test_model::{Test, TestNode, TestStatus};
+/// Modification time of a file, in nanoseconds since the Unix epoch
+fn modified_time(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() as u64)
+}
 
-/// Discover all tests in the project
+/// Discover all tests in the workspace with `cargo test`
 pub fn discover_tests(project_dir: &Path) -> Result<TestNode> {
-    let output = run_cargo_test_list(project_dir)?;
-    let tests = parse_test_list(&output);
+    Ok(build_tree(build_tests(project_dir)?.tests))
+}
+
+/// Build and list the workspace's test targets. cargo only rebuilds what
+/// changed, and only rebuilt binaries are listed again.
+pub fn build_tests(project_dir: &Path) -> Result<TestBuild> {
+    let workspace = load_workspace(project_dir)?;
+    let artifacts = build_test_binaries(project_dir, &workspace)?;
+
+    let cache = cache_path(project_dir);
+    let previous = TestListings::load(&cache);
+    let mut listings = TestListings::default();
+
+    let mut tests = Vec::new();
+    for binary in &artifacts.binaries {
+        let listed = match previous.get(&binary.executable) {
+            Some(listed) => listed,
+            None => list_binary_tests(binary)?,
+        };
+        listings.insert(&binary.executable, listed.clone());
+        tests.extend(listed);
+    }
+    for target in &workspace.doctest_packages {
+        let library = artifacts.libraries.get(&target.package);
+        let Some(listed) = library.and_then(|lib| previous.get(lib))
+            .or_else(|| list_doc_tests(project_dir, target))
+        else {
+            continue;
+        };
+        if let Some(lib) = library {
+            listings.insert(lib, listed.clone());
+        }
+        tests.extend(listed);
+    }
+    locate_tests(&mut tests, &workspace.roots, project_dir);
+    listings.save(&cache);
+
+    Ok(TestBuild {
+        binaries: artifacts.binaries,
+        doc_targets: workspace.doctest_packages,
+        tests,
+    })
+}
 
+/// List the documentation tests of a library. They are optional: a crate
+/// whose docs fail to build still has its other tests listed.
+fn list_doc_tests(project_dir: &Path, target: &TestTarget) -> Option<Vec<Test>> {
+    let listed = run_doc_test_list(project_dir, &target.package, JSON_LIST_ARGS)
+        .ok()
+        .and_then(|output| parse_json_test_list(&output))
+        .or_else(|| {
+            run_doc_test_list(project_dir, &target.package, &["--list"])
+                .ok()
+                .map(|output| parse_test_list(&output))
+        })?;
+    Some(listed.into_iter().map(|t| t.with_target(target.clone())).collect())
+}
+
+/// Where the test listings of the last build are remembered
+fn cache_path(project_dir: &Path) -> PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| project_dir.join("target"))
+        .join("runx")
+        .join("test-listings.json")
+}
+
+/// Fill in the source location of the tests the listing did not locate by
+/// indexing the sources of their target
+fn locate_tests(tests: &mut [Test], roots: &[(TestTarget, PathBuf)], project_dir: &Path) {
+    let base = project_dir.canonicalize().unwrap_or_else(|_| project_dir.to_path_buf());
+
+    for (target, root) in roots {
+        let mut unlocated = tests.iter_mut()
+            .filter(|t| t.target.as_ref() == Some(target) && t.source_file.is_none())
+            .peekable();
+        if unlocated.peek().is_none() {
+            continue;
+        }
+
+        let index = SourceIndex::scan(root);
+        for test in unlocated {
+            if let Some(location) = index.locate(&test.full_name) {
+                let file = location.file.strip_prefix(&base).unwrap_or(&location.file);
+                test.source_file = Some(file.to_string_lossy().replace('\\', "/"));
+                test.line_number = Some(location.line);
+            }
+        }
+    }
+}
+
+fn build_tree(tests: Vec<Test>) -> TestNode {
     let mut root = TestNode::new_module("tests");
 
     for test in tests {
@@ -23,32 +290,227 @@ pub fn discover_tests(project_dir: &Path) -> Result<TestNode> {
     root.sort_children();
     root.update_counts();
 
-    Ok(root)
+    root
 }
 
-/// Run `cargo test -- --list` and capture output
-fn run_cargo_test_list(project_dir: &Path) -> Result<String> {
+fn load_workspace(project_dir: &Path) -> Result<Workspace> {
     let output = Command::new("cargo")
-        .args(["test", "--", "--list"])
+        .args(["metadata", "--format-version", "1", "--no-deps"])
         .current_dir(project_dir)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .output()
-        .context("Failed to execute cargo test --list")?;
+        .context("Failed to execute cargo metadata")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("cargo metadata failed: {}", stderr);
+    }
+
+    parse_metadata(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_metadata(output: &str) -> Result<Workspace> {
+    let metadata: Metadata = serde_json::from_str(output)
+        .context("Invalid cargo metadata output")?;
+
+    let mut packages = HashMap::new();
+    let mut doctest_packages = Vec::new();
+    let mut roots = Vec::new();
+    for package in metadata.packages {
+        if !metadata.workspace_members.contains(&package.id) {
+            continue;
+        }
+        for target in &package.targets {
+            if target.doctest && TargetKind::from_cargo(&target.kind) == TargetKind::Lib {
+                doctest_packages.push(TestTarget {
+                    package: package.name.clone(),
+                    name: target.name.clone(),
+                    kind: TargetKind::Doc,
+                });
+            }
+            if !target.kind.iter().any(|k| k == "custom-build") && !target.src_path.as_os_str().is_empty() {
+                let target_id = TestTarget {
+                    package: package.name.clone(),
+                    name: target.name.clone(),
+                    kind: TargetKind::from_cargo(&target.kind),
+                };
+                roots.push((target_id, target.src_path.clone()));
+            }
+        }
+        let dir = package.manifest_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        packages.insert(package.id, (package.name, dir));
+    }
 
-    // Cargo test --list outputs to stdout
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    Ok(Workspace { packages, doctest_packages, roots })
+}
 
-    // Also check stderr for any errors
-    if !output.status.success() && stdout.is_empty() {
+/// Build all test targets without running them and collect their executables.
+/// When nothing changed this only takes cargo's freshness check.
+fn build_test_binaries(project_dir: &Path, workspace: &Workspace) -> Result<BuildArtifacts> {
+    let output = Command::new("cargo")
+        .args(["test", "--workspace", "--no-run", "--message-format", "json"])
+        .current_dir(project_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to execute cargo test --no-run")?;
+
+    let artifacts = parse_build_output(&String::from_utf8_lossy(&output.stdout), workspace)?;
+
+    if !output.status.success() && artifacts.binaries.is_empty() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("cargo test --list failed: {}", stderr);
+        anyhow::bail!("cargo test --no-run failed: {}", stderr);
     }
 
-    Ok(stdout)
+    Ok(artifacts)
 }
 
-/// Parse the output of `cargo test -- --list`
+/// Parse `cargo test --no-run --message-format json` output into test
+/// binaries and member libraries
+fn parse_build_output(output: &str, workspace: &Workspace) -> Result<BuildArtifacts> {
+    let mut binaries = Vec::new();
+    let mut libraries = HashMap::new();
+    let mut errors = Vec::new();
+
+    for line in output.lines() {
+        let Ok(message) = serde_json::from_str::<CargoMessage>(line) else {
+            continue;
+        };
+        match message {
+            CargoMessage::CompilerArtifact(artifact) => {
+                let Some((package, package_dir)) = workspace.packages.get(&artifact.package_id) else {
+                    continue;
+                };
+                let kind = TargetKind::from_cargo(&artifact.target.kind);
+                if !artifact.profile.test && kind == TargetKind::Lib {
+                    if let Some(library) = artifact.filenames.into_iter().next() {
+                        libraries.insert(package.clone(), library);
+                    }
+                    continue;
+                }
+                let Some(executable) = artifact.executable else {
+                    continue;
+                };
+                if !artifact.profile.test {
+                    continue;
+                }
+                binaries.push(TestBinary {
+                    target: TestTarget {
+                        package: package.clone(),
+                        name: artifact.target.name,
+                        kind,
+                    },
+                    executable,
+                    package_dir: package_dir.clone(),
+                });
+            }
+            CargoMessage::CompilerMessage { message } if message.level == "error" => {
+                errors.extend(message.rendered);
+            }
+            CargoMessage::BuildFinished { success: false } => {
+                anyhow::bail!("Failed to build tests:\n{}", errors.join("\n"));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(BuildArtifacts { binaries, libraries })
+}
+
+/// List the tests of one binary, marking those that are `#[ignore]`d
+fn list_binary_tests(binary: &TestBinary) -> Result<Vec<Test>> {
+    let list = |args: &[&str]| -> Result<String> {
+        let output = Command::new(&binary.executable)
+            .args(args)
+            .env("RUSTC_BOOTSTRAP", "1")
+            .current_dir(&binary.package_dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .with_context(|| format!("Failed to list tests of {}", binary.executable.display()))?;
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    };
+
+    if let Some(tests) = parse_json_test_list(&list(JSON_LIST_ARGS)?) {
+        return Ok(tests.into_iter().map(|t| t.with_target(binary.target.clone())).collect());
+    }
+
+    // Harnesses without the JSON listing need a second pass for ignored tests
+    let ignored: Vec<String> = parse_test_list(&list(&["--list", "--ignored"])?)
+        .into_iter()
+        .map(|t| t.full_name)
+        .collect();
+
+    Ok(parse_test_list(&list(&["--list"])?)
+        .into_iter()
+        .map(|mut test| {
+            if ignored.contains(&test.full_name) {
+                test.status = TestStatus::Ignored;
+            }
+            test.with_target(binary.target.clone())
+        })
+        .collect())
+}
+
+/// Run `cargo test --doc -p <package> -- <list args>` and capture output
+fn run_doc_test_list(project_dir: &Path, package: &str, list_args: &[&str]) -> Result<String> {
+    let output = Command::new("cargo")
+        .args(["test", "--doc", "-p", package, "--"])
+        .args(list_args)
+        .env("RUSTC_BOOTSTRAP", "1")
+        .current_dir(project_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to execute cargo test --doc --list")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("cargo test --doc --list failed: {}", stderr);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parse libtest's `--list --format json` output, or `None` if the harness
+/// does not support it
+fn parse_json_test_list(output: &str) -> Option<Vec<Test>> {
+    let mut supported = false;
+    let mut tests = Vec::new();
+
+    for line in output.lines() {
+        let Ok(record) = serde_json::from_str::<ListRecord>(line) else {
+            continue;
+        };
+        if record.kind == "suite" && record.event == "discovery" {
+            supported = true;
+        }
+        if record.kind != "test" || record.event != "discovered" {
+            continue;
+        }
+        let Some(name) = record.name else {
+            continue;
+        };
+
+        let mut test = Test::from_name(&name);
+        if record.ignore {
+            test.status = TestStatus::Ignored;
+            test.ignore_reason = Some(record.ignore_message).filter(|m| !m.is_empty());
+        }
+        if !record.source_path.is_empty() {
+            test.source_file = Some(record.source_path);
+        }
+        if record.start_line > 0 {
+            test.line_number = Some(record.start_line);
+        }
+        tests.push(test);
+    }
+
+    supported.then_some(tests)
+}
+
+/// Parse the output of a test binary's `--list`
 ///
 /// Output format:
 /// ```text
@@ -88,7 +550,7 @@ fn parse_test_list(output: &str) -> Vec<Test> {
             // Check if it's an ignored test
             if suffix == "test" {
                 test.status = TestStatus::Pending;
-            } else if suffix == "bench" {
+            } else if suffix == "bench" || suffix == "benchmark" {
                 // Skip benchmarks for now
                 continue;
             }
@@ -138,49 +600,92 @@ pub fn get_project_name(project_dir: &Path) -> Result<String> {
         .unwrap_or_else(|| "project".to_string()))
 }
 
-/// Build ignored tests list from `cargo test -- --ignored --list`
-pub fn discover_ignored_tests(project_dir: &Path) -> Result<Vec<String>> {
+/// `cargo nextest list --message-format json` output (only the fields we use)
+#[derive(Debug, Deserialize)]
+struct NextestList {
+    #[serde(rename = "rust-suites", default)]
+    rust_suites: BTreeMap<String, NextestSuite>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NextestSuite {
+    #[serde(rename = "package-name", default)]
+    package_name: Option<String>,
+    #[serde(rename = "binary-name", default)]
+    binary_name: Option<String>,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    testcases: BTreeMap<String, NextestTestCase>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NextestTestCase {
+    #[serde(default)]
+    ignored: bool,
+}
+
+/// Discover tests with `cargo nextest list`, which also reports ignored tests
+pub fn discover_nextest_tests(project_dir: &Path) -> Result<TestNode> {
     let output = Command::new("cargo")
-        .args(["test", "--", "--ignored", "--list"])
+        .args(["nextest", "list", "--message-format", "json"])
         .current_dir(project_dir)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .output()
-        .context("Failed to execute cargo test --ignored --list")?;
+        .context("Failed to execute cargo nextest list")?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("cargo nextest list failed: {}", stderr);
+    }
 
-    let mut ignored = Vec::new();
-    for line in stdout.lines() {
-        let line = line.trim();
-        if let Some((name, _suffix)) = line.rsplit_once(": ") {
-            ignored.push(name.to_string());
-        }
+    let mut tests = parse_nextest_list(&String::from_utf8_lossy(&output.stdout))?;
+    // Locations are a nicety: nextest already listed the tests
+    if let Ok(workspace) = load_workspace(project_dir) {
+        locate_tests(&mut tests, &workspace.roots, project_dir);
     }
 
-    Ok(ignored)
+    Ok(build_tree(tests))
 }
 
-/// Mark ignored tests in the tree
-pub fn mark_ignored_tests(root: &mut TestNode, ignored_names: &[String]) {
-    for name in ignored_names {
-        if let Some(test) = root.find_test_mut(name) {
-            test.status = TestStatus::Ignored;
+/// Parse the JSON emitted by `cargo nextest list --message-format json`
+fn parse_nextest_list(output: &str) -> Result<Vec<Test>> {
+    let list: NextestList = serde_json::from_str(output)
+        .context("Invalid cargo nextest list output")?;
+
+    let mut tests = Vec::new();
+    for suite in list.rust_suites.into_values() {
+        let target = match (suite.package_name, suite.binary_name) {
+            (Some(package), Some(name)) => Some(TestTarget {
+                package,
+                name,
+                kind: TargetKind::from_cargo(suite.kind.as_slice()),
+            }),
+            _ => None,
+        };
+        for (name, case) in suite.testcases {
+            let mut test = Test::from_name(&name);
+            if case.ignored {
+                test.status = TestStatus::Ignored;
+            }
+            if let Some(ref target) = target {
+                test = test.with_target(target.clone());
+            }
+            tests.push(test);
         }
     }
-    root.update_counts();
+
+    Ok(tests)
 }
 
-/// Full discovery: tests + ignored tests
+/// Full discovery with the configured backend
 pub fn discover_all_tests(project_dir: &Path) -> Result<TestNode> {
-    let mut root = discover_tests(project_dir)?;
-
-    // Try to get ignored tests
-    if let Ok(ignored) = discover_ignored_tests(project_dir) {
-        mark_ignored_tests(&mut root, &ignored);
+    if TestBackend::configured(project_dir) == TestBackend::Nextest {
+        return discover_nextest_tests(project_dir);
     }
 
-    Ok(root)
+    discover_tests(project_dir)
 }
 
 #[cfg(test)]
@@ -211,6 +716,121 @@ other::test_three: test
         assert!(tests.is_empty());
     }
 
+    #[test]
+    fn test_parse_nextest_list() {
+        let output = r#"{
+  "test-count": 2,
+  "rust-suites": {
+    "demo": {
+      "binary-id": "demo",
+      "package-name": "demo",
+      "binary-name": "demo",
+      "kind": "lib",
+      "testcases": {
+        "tests::fast": { "ignored": false, "filter-match": { "status": "matches" } },
+        "tests::slow": { "ignored": true, "filter-match": { "status": "matches" } }
+      }
+    }
+  }
+}"#;
+
+        let tests = parse_nextest_list(output).unwrap();
+        assert_eq!(tests.len(), 2);
+        assert_eq!(tests[0].full_name, "tests::fast");
+        assert_eq!(tests[0].status, TestStatus::Pending);
+        assert_eq!(tests[1].status, TestStatus::Ignored);
+        assert_eq!(tests[0].tree_path(), vec!["demo", "lib", "tests"]);
+    }
+
+    #[test]
+    fn test_parse_metadata_and_build_output() {
+        let metadata = r#"{
+  "packages": [
+    { "id": "path+file:///ws/core#0.1.0", "name": "core", "manifest_path": "/ws/core/Cargo.toml", "targets": [
+      { "name": "core", "kind": ["lib"], "doctest": true, "src_path": "/ws/core/src/lib.rs" },
+      { "name": "api", "kind": ["test"], "doctest": false, "src_path": "/ws/core/tests/api.rs" },
+      { "name": "build-script-build", "kind": ["custom-build"], "doctest": false, "src_path": "/ws/core/build.rs" }
+    ] },
+    { "id": "path+file:///ws/cli#cli@0.1.0", "name": "cli", "manifest_path": "/ws/cli/Cargo.toml", "targets": [
+      { "name": "cli", "kind": ["bin"], "doctest": false }
+    ] }
+  ],
+  "workspace_members": ["path+file:///ws/core#0.1.0", "path+file:///ws/cli#cli@0.1.0"]
+}"#;
+        let workspace = parse_metadata(metadata).unwrap();
+        assert_eq!(workspace.doctest_packages.len(), 1);
+        assert_eq!(workspace.doctest_packages[0].kind, TargetKind::Doc);
+        assert_eq!(workspace.roots.len(), 2);
+        assert_eq!(workspace.roots[1].1, PathBuf::from("/ws/core/tests/api.rs"));
+
+        let build = r#"{"reason":"compiler-artifact","package_id":"registry+https://github.com/rust-lang/crates.io-index#serde@1.0.0","target":{"name":"serde","kind":["lib"]},"profile":{"test":false},"executable":null}
+{"reason":"compiler-artifact","package_id":"path+file:///ws/core#0.1.0","target":{"name":"core","kind":["lib"]},"profile":{"test":false},"filenames":["/ws/target/debug/deps/libcore-0.rlib"],"executable":null}
+{"reason":"compiler-artifact","package_id":"path+file:///ws/core#0.1.0","target":{"name":"core","kind":["lib"]},"profile":{"test":true},"executable":"/ws/target/debug/deps/core-1"}
+{"reason":"compiler-artifact","package_id":"path+file:///ws/core#0.1.0","target":{"name":"api","kind":["test"]},"profile":{"test":true},"executable":"/ws/target/debug/deps/api-2"}
+{"reason":"compiler-artifact","package_id":"path+file:///ws/cli#cli@0.1.0","target":{"name":"cli","kind":["bin"]},"profile":{"test":true},"executable":"/ws/target/debug/deps/cli-3"}
+{"reason":"build-finished","success":true}"#;
+        let BuildArtifacts { binaries, libraries } = parse_build_output(build, &workspace).unwrap();
+        let labels: Vec<_> = binaries.iter()
+            .map(|b| format!("{} {}", b.target.package, b.target.label()))
+            .collect();
+        assert_eq!(labels, vec!["core lib", "core integration api", "cli bin cli"]);
+        assert_eq!(binaries[1].executable, PathBuf::from("/ws/target/debug/deps/api-2"));
+        assert_eq!(binaries[2].package_dir, PathBuf::from("/ws/cli"));
+        assert_eq!(libraries.len(), 1);
+        assert_eq!(libraries["core"], PathBuf::from("/ws/target/debug/deps/libcore-0.rlib"));
+
+        let failed = r#"{"reason":"compiler-message","package_id":"path+file:///ws/core#0.1.0","message":{"level":"error","rendered":"error[E0425]: cannot find value `x`"}}
+{"reason":"build-finished","success":false}"#;
+        let err = parse_build_output(failed, &workspace).unwrap_err().to_string();
+        assert!(err.contains("E0425"));
+    }
+
+    #[test]
+    fn test_listings_follow_rebuilt_artifacts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let exe = dir.path().join("demo-test");
+        std::fs::write(&exe, "").unwrap();
+
+        let mut listings = TestListings::default();
+        listings.insert(&exe, vec![Test::from_name("tests::it_works")]);
+        let cache = dir.path().join("runx/test-listings.json");
+        listings.save(&cache);
+        let listings = TestListings::load(&cache);
+        assert_eq!(listings.get(&exe).unwrap().len(), 1);
+
+        // A binary cargo rebuilt is listed again
+        let rebuilt = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options().write(true).open(&exe).unwrap().set_modified(rebuilt).unwrap();
+        assert!(listings.get(&exe).is_none());
+
+        // So is one removed by `cargo clean`
+        std::fs::remove_file(&exe).unwrap();
+        assert!(listings.get(&exe).is_none());
+    }
+
+    #[test]
+    fn test_parse_json_test_list() {
+        let output = r#"{ "type": "suite", "event": "discovery" }
+{ "type": "test", "event": "discovered", "name": "tests::it_works", "ignore": false, "ignore_message": "", "source_path": "core/src/lib.rs", "start_line": 9, "start_col": 16, "end_line": 9, "end_col": 24 }
+{ "type": "test", "event": "discovered", "name": "tests::slow", "ignore": true, "ignore_message": "needs network", "source_path": "core/src/lib.rs", "start_line": 10, "start_col": 44, "end_line": 10, "end_col": 48 }
+{ "type": "test", "event": "discovered", "name": "tests::plain", "ignore": true, "ignore_message": "", "source_path": "", "start_line": 0, "start_col": 0, "end_line": 0, "end_col": 0 }
+{ "type": "suite", "event": "completed", "tests": 3, "benchmarks": 0, "total": 3, "ignored": 2 }"#;
+
+        let tests = parse_json_test_list(output).unwrap();
+        assert_eq!(tests.len(), 3);
+        assert_eq!(tests[0].status, TestStatus::Pending);
+        assert_eq!(tests[0].source_file.as_deref(), Some("core/src/lib.rs"));
+        assert_eq!(tests[0].line_number, Some(9));
+        assert_eq!(tests[1].status, TestStatus::Ignored);
+        assert_eq!(tests[1].ignore_reason.as_deref(), Some("needs network"));
+        assert_eq!(tests[2].status, TestStatus::Ignored);
+        assert_eq!(tests[2].ignore_reason, None);
+        assert_eq!(tests[2].source_file, None);
+
+        // Older harnesses print the plain listing
+        assert!(parse_json_test_list("tests::it_works: test\n").is_none());
+    }
+
     #[test]
     fn test_parse_with_doc_tests() {
         let output = r#"
@@ -0,0 +1,190 @@
+//! Opening tests in an editor
+//!
+//! Resolves where a test should be opened (its panic location when it
+//! failed, its definition otherwise) and builds the editor command with the
+//! line-number arguments each editor expects.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::EditorConfig;
+use crate::test_model::Test;
+
+/// Link template used by the dashboard when `[editor] url` is not set
+pub const DEFAULT_EDITOR_URL: &str = "editor://open?file={file}&line={line}&column={col}";
+
+/// A position in a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: u32,
+    pub column: Option<u32>,
+}
+
+impl Location {
+    /// Where to open a test: the panic location from its output if it points
+    /// to an existing file, otherwise the test's definition
+    pub fn of_test(test: &Test, project_dir: &Path) -> Option<Self> {
+        let panic = panic_location(&test.output)
+            .map(|l| l.resolve(project_dir))
+            .filter(|l| l.file.is_file());
+        panic.or_else(|| {
+            Some(Self {
+                file: project_dir.join(test.source_file.as_ref()?),
+                line: test.line_number.unwrap_or(1),
+                column: None,
+            })
+        })
+    }
+
+    /// Parse `file:line:col` as printed in panic messages
+    pub fn parse(spot: &str) -> Option<Self> {
+        let mut parts = spot.trim().trim_end_matches(':').rsplitn(3, ':');
+        let column = parts.next()?.parse().ok()?;
+        let line = parts.next()?.parse().ok()?;
+        let file = parts.next()?;
+        Some(Self { file: PathBuf::from(file), line, column: Some(column) })
+    }
+
+    /// Resolve a path relative to the workspace root, as rustc prints them
+    pub fn resolve(mut self, project_dir: &Path) -> Self {
+        self.file = project_dir.join(&self.file);
+        self
+    }
+}
+
+/// Find the `file:line:col` of the first panic in a test's output
+///
+/// Handles both `panicked at src/lib.rs:12:5:` and the pre-1.73
+/// `panicked at 'message', src/lib.rs:12:5` formats.
+pub fn panic_location(output: &[String]) -> Option<Location> {
+    output.iter().find_map(|line| {
+        let rest = &line[line.find("panicked at ")? + "panicked at ".len()..];
+        let spot = if rest.starts_with('\'') {
+            rest.rsplit_once("', ")?.1
+        } else {
+            rest
+        };
+        Location::parse(spot)
+    })
+}
+
+/// Fill a link template such as `vscode://file/{file}:{line}:{col}`
+pub fn editor_url(template: &str, location: &Location) -> String {
+    template
+        .replace("{file}", &location.file.display().to_string())
+        .replace("{line}", &location.line.to_string())
+        .replace("{col}", &location.column.unwrap_or(1).to_string())
+}
+
+/// The editor to use: `[editor] command`, then `$VISUAL`, then `$EDITOR`
+pub fn configured_editor(config: &EditorConfig) -> Option<String> {
+    config.command.clone()
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|e| !e.trim().is_empty())
+}
+
+/// Build the command opening `location` in `editor`, which may carry its own
+/// arguments (e.g. `"code --wait"`)
+pub fn editor_command(editor: &str, location: &Location) -> Option<Command> {
+    let mut words = editor.split_whitespace();
+    let program = words.next()?;
+    let mut command = Command::new(program);
+    command.args(words);
+
+    let file = location.file.display().to_string();
+    let line = location.line;
+    let column = location.column.unwrap_or(1);
+    let name = Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match name.as_str() {
+        "vi" | "vim" | "nvim" | "gvim" | "mvim" => {
+            command.arg(format!("+call cursor({}, {})", line, column)).arg(file)
+        }
+        "emacs" | "emacsclient" | "nano" => {
+            command.arg(format!("+{}:{}", line, column)).arg(file)
+        }
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            command.arg("--goto").arg(format!("{}:{}:{}", file, line, column))
+        }
+        "hx" | "helix" | "zed" | "subl" => {
+            command.arg(format!("{}:{}:{}", file, line, column))
+        }
+        _ => command.arg(file),
+    };
+
+    Some(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        command.get_args().map(|a| a.to_string_lossy().to_string()).collect()
+    }
+
+    #[test]
+    fn test_panic_location() {
+        let output = vec![
+            "running 1 test".to_string(),
+            "thread 'tests::fails' panicked at core/src/lib.rs:12:9:".to_string(),
+            "assertion failed".to_string(),
+        ];
+        assert_eq!(panic_location(&output), Some(Location {
+            file: PathBuf::from("core/src/lib.rs"),
+            line: 12,
+            column: Some(9),
+        }));
+
+        let old = vec!["thread 'a' panicked at 'x: y', src/main.rs:3:5".to_string()];
+        assert_eq!(panic_location(&old).unwrap().line, 3);
+        assert_eq!(panic_location(&["test a ... ok".to_string()]), None);
+    }
+
+    #[test]
+    fn test_editor_arguments() {
+        let location = Location { file: PathBuf::from("/ws/src/lib.rs"), line: 12, column: Some(9) };
+
+        let vim = editor_command("nvim", &location).unwrap();
+        assert_eq!(args(&vim), vec!["+call cursor(12, 9)", "/ws/src/lib.rs"]);
+        let emacs = editor_command("emacsclient -t", &location).unwrap();
+        assert_eq!(args(&emacs), vec!["-t", "+12:9", "/ws/src/lib.rs"]);
+        let code = editor_command("/usr/bin/code --wait", &location).unwrap();
+        assert_eq!(args(&code), vec!["--wait", "--goto", "/ws/src/lib.rs:12:9"]);
+        let helix = editor_command("hx", &location).unwrap();
+        assert_eq!(args(&helix), vec!["/ws/src/lib.rs:12:9"]);
+        let zed = editor_command("zed", &location).unwrap();
+        assert_eq!(args(&zed), vec!["/ws/src/lib.rs:12:9"]);
+        assert!(editor_command("  ", &location).is_none());
+
+        assert_eq!(
+            editor_url(DEFAULT_EDITOR_URL, &location),
+            "editor://open?file=/ws/src/lib.rs&line=12&column=9"
+        );
+    }
+
+    #[test]
+    fn test_location_of_test() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+
+        let mut test = Test::from_name("tests::fails");
+        test.source_file = Some("src/lib.rs".to_string());
+        test.line_number = Some(4);
+        assert_eq!(Location::of_test(&test, dir.path()).unwrap().line, 4);
+
+        // A panic inside the project wins over the definition
+        test.output = vec!["thread 'tests::fails' panicked at src/lib.rs:7:5:".to_string()];
+        assert_eq!(Location::of_test(&test, dir.path()).unwrap().line, 7);
+
+        // One in the standard library does not
+        test.output = vec!["thread 'tests::fails' panicked at /rustc/abc/library/core/src/option.rs:7:5:".to_string()];
+        assert_eq!(Location::of_test(&test, dir.path()).unwrap().line, 4);
+    }
+}
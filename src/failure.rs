@@ -0,0 +1,310 @@
+//! Structured test failures
+//!
+//! Extracts the panic message, assertion operands, location and backtrace
+//! from the output libtest captured for a failed test.
+
+use crate::diff::{side_by_side, DiffRow};
+use crate::editor::{panic_location, Location};
+
+/// What made a test fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// `assert_eq!` (or `pretty_assertions::assert_eq!`)
+    AssertEq,
+    /// `assert_ne!`
+    AssertNe,
+    /// `assert!`
+    Assert,
+    /// Any other panic (`panic!`, `unwrap()`, `expect()`, ...)
+    Panic,
+}
+
+impl FailureKind {
+    /// Label stored in `test_cases.error_type`
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::AssertEq => "assert_eq",
+            FailureKind::AssertNe => "assert_ne",
+            FailureKind::Assert => "assert",
+            FailureKind::Panic => "panic",
+        }
+    }
+
+    fn from_message(message: &str) -> Self {
+        if message.starts_with("assertion `left == right` failed") || message.contains("`(left == right)`") {
+            FailureKind::AssertEq
+        } else if message.starts_with("assertion `left != right` failed") || message.contains("`(left != right)`") {
+            FailureKind::AssertNe
+        } else if message.starts_with("assertion failed") {
+            FailureKind::Assert
+        } else {
+            FailureKind::Panic
+        }
+    }
+}
+
+/// A parsed test failure
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestFailure {
+    pub kind: FailureKind,
+    /// Panic message, possibly spanning several lines
+    pub message: String,
+    /// Where the panic happened
+    pub location: Option<Location>,
+    /// `left` operand of a failed `assert_eq!`/`assert_ne!`
+    pub left: Option<String>,
+    /// `right` operand of a failed `assert_eq!`/`assert_ne!`
+    pub right: Option<String>,
+    /// Frames printed under `stack backtrace:`
+    pub backtrace: Vec<String>,
+}
+
+impl TestFailure {
+    /// Parse the first panic of a failed test's output
+    pub fn parse(output: &[String]) -> Option<Self> {
+        // pretty_assertions colors its diff even when captured
+        let output: Vec<String> = output.iter().map(|l| strip_ansi(l)).collect();
+        let start = output.iter().position(|l| l.contains("panicked at "))?;
+        let mut location = panic_location(&output[start..=start]);
+        let rest = &output[start][output[start].find("panicked at ")? + "panicked at ".len()..];
+
+        let (message, mut end) = if let Some(quoted) = rest.strip_prefix('\'') {
+            // Before Rust 1.73: panicked at 'message', src/lib.rs:1:1
+            let mut text = quoted.to_string();
+            let mut end = start + 1;
+            while !text.contains("', ") && end < output.len() {
+                text.push('\n');
+                text.push_str(&output[end]);
+                end += 1;
+            }
+            let message = match text.rsplit_once("', ") {
+                Some((message, spot)) => {
+                    location = Location::parse(spot);
+                    message.to_string()
+                }
+                None => text,
+            };
+            (message, end)
+        } else {
+            let mut end = start + 1;
+            while end < output.len() && !ends_message(&output[end]) {
+                end += 1;
+            }
+            (output[start + 1..end].join("\n").trim_end().to_string(), end)
+        };
+
+        let mut backtrace = Vec::new();
+        while end < output.len() && output[end].trim().is_empty() {
+            end += 1;
+        }
+        if output.get(end).is_some_and(|l| l.trim() == "stack backtrace:") {
+            backtrace = output[end + 1..].iter()
+                .take_while(|l| !l.trim().is_empty() && !l.starts_with("note: "))
+                .cloned()
+                .collect();
+        }
+
+        let (left, right) = parse_operands(&message);
+        Some(Self {
+            kind: FailureKind::from_message(&message),
+            message,
+            location,
+            left,
+            right,
+            backtrace,
+        })
+    }
+
+    /// Failure known only by its message, e.g. from a JUnit report
+    pub fn from_message(message: &str) -> Self {
+        let (left, right) = parse_operands(message);
+        Self {
+            kind: FailureKind::from_message(message),
+            message: message.to_string(),
+            location: None,
+            left,
+            right,
+            backtrace: Vec::new(),
+        }
+    }
+
+    /// First line of the message, e.g. "assertion `left == right` failed"
+    pub fn headline(&self) -> &str {
+        self.message.lines().next().unwrap_or("test panicked")
+    }
+
+    /// Side-by-side diff of the operands of a failed `assert_eq!`
+    pub fn diff(&self) -> Option<Vec<DiffRow>> {
+        if self.kind != FailureKind::AssertEq {
+            return None;
+        }
+        Some(side_by_side(self.left.as_deref()?, self.right.as_deref()?))
+    }
+}
+
+/// Remove terminal color codes (`ESC [ ... m`)
+pub(crate) fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Lines after which a panic message cannot continue
+fn ends_message(line: &str) -> bool {
+    line.starts_with("note: ")
+        || line.trim() == "stack backtrace:"
+        || line.starts_with("thread '")
+        || line.starts_with("---- ")
+}
+
+/// Split the `left:`/`right:` operands out of an assertion message; values
+/// printed with `{:#?}` span several lines
+fn parse_operands(message: &str) -> (Option<String>, Option<String>) {
+    if let Some(diff) = message.split_once("\nDiff <").map(|(_, diff)| diff) {
+        return parse_pretty_diff(diff);
+    }
+
+    let mut left: Option<Vec<&str>> = None;
+    let mut right: Option<Vec<&str>> = None;
+
+    for line in message.lines().skip(1) {
+        let trimmed = line.trim_start();
+        if let Some(value) = trimmed.strip_prefix("left:") {
+            left = Some(vec![value.trim()]);
+        } else if let Some(value) = trimmed.strip_prefix("right:") {
+            right = Some(vec![value.trim()]);
+        } else if let Some(ref mut value) = right {
+            value.push(line);
+        } else if let Some(ref mut value) = left {
+            value.push(line);
+        }
+    }
+
+    // Old format: `left: `4`,`
+    let clean = |lines: Vec<&str>| {
+        let value = lines.join("\n");
+        let value = value.trim_end().trim_end_matches(',');
+        value.strip_prefix('`').and_then(|v| v.strip_suffix('`')).unwrap_or(value).to_string()
+    };
+    (left.map(clean), right.map(clean))
+}
+
+/// Rebuild both operands from pretty_assertions' diff, where lines start with
+/// `<` (left only), `>` (right only) or a space (both)
+fn parse_pretty_diff(diff: &str) -> (Option<String>, Option<String>) {
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    for line in diff.lines().skip(1) {
+        match line.chars().next() {
+            Some('<') => left.push(&line[1..]),
+            Some('>') => right.push(&line[1..]),
+            Some(' ') => {
+                left.push(&line[1..]);
+                right.push(&line[1..]);
+            }
+            _ => {
+                left.push(line);
+                right.push(line);
+            }
+        }
+    }
+    (Some(left.join("\n")), Some(right.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_assert_eq() {
+        let output = lines("
+thread 'tests::it_works' panicked at core/src/lib.rs:9:29:
+assertion `left == right` failed: sums differ
+  left: Point {
+    x: 1,
+}
+ right: Point {
+    x: 2,
+}
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace");
+
+        let failure = TestFailure::parse(&output).unwrap();
+        assert_eq!(failure.kind, FailureKind::AssertEq);
+        assert_eq!(failure.headline(), "assertion `left == right` failed: sums differ");
+        assert_eq!(failure.location.unwrap().file, PathBuf::from("core/src/lib.rs"));
+        assert_eq!(failure.left.as_deref(), Some("Point {\n    x: 1,\n}"));
+        assert_eq!(failure.right.as_deref(), Some("Point {\n    x: 2,\n}"));
+        assert!(failure.backtrace.is_empty());
+    }
+
+    #[test]
+    fn test_parse_panic_with_backtrace() {
+        let output = lines("thread 'tests::boom' panicked at src/main.rs:3:5:
+called `Option::unwrap()` on a `None` value
+stack backtrace:
+   0: rust_begin_unwind
+   1: core::panicking::panic_fmt
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.");
+
+        let failure = TestFailure::parse(&output).unwrap();
+        assert_eq!(failure.kind, FailureKind::Panic);
+        assert_eq!(failure.message, "called `Option::unwrap()` on a `None` value");
+        assert_eq!(failure.backtrace.len(), 2);
+        assert_eq!(failure.left, None);
+    }
+
+    #[test]
+    fn test_parse_old_format() {
+        let output = lines("thread 'a' panicked at 'assertion failed: `(left == right)`
+  left: `4`,
+ right: `5`', src/lib.rs:9:5");
+
+        let failure = TestFailure::parse(&output).unwrap();
+        assert_eq!(failure.kind, FailureKind::AssertEq);
+        assert_eq!(failure.location.unwrap().line, 9);
+        assert_eq!((failure.left.as_deref(), failure.right.as_deref()), (Some("4"), Some("5")));
+
+        assert!(TestFailure::parse(&lines("test a ... ok")).is_none());
+    }
+
+    #[test]
+    fn test_parse_pretty_assertions() {
+        let output = lines("thread 'tests::config' panicked at src/lib.rs:20:9:
+assertion failed: `(left == right)`
+
+Diff \u{1b}[31m< left\u{1b}[0m / \u{1b}[32mright >\u{1b}[0m :
+ Config {
+\u{1b}[31m<    port: \u{1b}[0m\u{1b}[1;31m80\u{1b}[0m\u{1b}[31m,\u{1b}[0m
+\u{1b}[32m>    port: \u{1b}[0m\u{1b}[1;32m8080\u{1b}[0m\u{1b}[32m,\u{1b}[0m
+     host: \"localhost\",
+ }
+
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace");
+
+        let failure = TestFailure::parse(&output).unwrap();
+        assert_eq!(failure.kind, FailureKind::AssertEq);
+        assert_eq!(failure.left.as_deref(), Some("Config {\n    port: 80,\n    host: \"localhost\",\n}"));
+        assert_eq!(failure.right.as_deref(), Some("Config {\n    port: 8080,\n    host: \"localhost\",\n}"));
+
+        let diff = failure.diff().unwrap();
+        assert_eq!(diff.iter().filter(|row| !row.is_equal()).count(), 1);
+    }
+}
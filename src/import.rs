@@ -0,0 +1,316 @@
+//! Import of test results produced outside runx
+//!
+//! Parses JUnit XML reports and libtest or nextest JSON event streams into a
+//! `TestRunResult`, which is then recorded like a native run.
+
+use anyhow::{Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::failure::TestFailure;
+use crate::test_model::{TestStatus, TestTarget};
+use crate::test_runner::{attach_failures, LibtestRecord, SingleTestResult, TestRunResult};
+
+/// Format of an imported report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// JUnit XML (`<testsuites>`/`<testsuite>`/`<testcase>`)
+    Junit,
+    /// `cargo test -- -Z unstable-options --format json`
+    LibtestJson,
+    /// `cargo nextest run --message-format libtest-json`
+    NextestJson,
+}
+
+impl ImportFormat {
+    /// Guess the format of a report from its content
+    pub fn detect(content: &str) -> Option<Self> {
+        if content.trim_start().starts_with('<') {
+            return Some(ImportFormat::Junit);
+        }
+        let records: Vec<LibtestRecord> = content.lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        if records.is_empty() {
+            return None;
+        }
+        // nextest names tests "<binary-id>$<test name>"
+        if records.iter().any(|r| r.name.as_deref().is_some_and(|n| n.contains('$'))) {
+            Some(ImportFormat::NextestJson)
+        } else {
+            Some(ImportFormat::LibtestJson)
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportFormat::Junit => "JUnit XML",
+            ImportFormat::LibtestJson => "libtest JSON",
+            ImportFormat::NextestJson => "nextest JSON",
+        }
+    }
+}
+
+/// Parse a report
+pub fn parse_report(content: &str, format: ImportFormat) -> Result<TestRunResult> {
+    let mut result = match format {
+        ImportFormat::Junit => parse_junit(content)?,
+        ImportFormat::LibtestJson | ImportFormat::NextestJson => parse_json(content),
+    };
+
+    for test in &result.test_results {
+        match test.status {
+            TestStatus::Passed => result.passed += 1,
+            TestStatus::Failed | TestStatus::TimedOut => result.failed += 1,
+            TestStatus::Ignored => result.ignored += 1,
+            _ => {}
+        }
+    }
+    result.success = result.failed == 0;
+    result.duration_ms = result.test_results.iter().filter_map(|t| t.duration_ms).sum();
+    Ok(result)
+}
+
+/// Results of a libtest or nextest JSON event stream
+fn parse_json(content: &str) -> TestRunResult {
+    let mut result = TestRunResult::default();
+    for line in content.lines() {
+        let Ok(record) = serde_json::from_str::<LibtestRecord>(line) else {
+            continue;
+        };
+        let status = match (record.kind.as_str(), record.event.as_str()) {
+            ("test", "ok") => TestStatus::Passed,
+            ("test", "failed") => TestStatus::Failed,
+            ("test", "ignored") => TestStatus::Ignored,
+            _ => continue,
+        };
+        let Some(name) = record.name else {
+            continue;
+        };
+        let (target, name) = match name.split_once('$') {
+            Some((binary_id, test)) => (Some(TestTarget::from_binary_id(binary_id)), test.to_string()),
+            None => (None, name),
+        };
+
+        let mut output: Vec<String> = record.stdout
+            .as_deref()
+            .map(|s| s.lines().map(String::from).collect())
+            .unwrap_or_default();
+        let mut ignore_reason = None;
+        if let Some(message) = record.message.filter(|m| !m.is_empty()) {
+            if status == TestStatus::Ignored {
+                ignore_reason = Some(message);
+            } else {
+                output.push(message);
+            }
+        }
+
+        result.test_results.push(SingleTestResult {
+            name,
+            status,
+            duration_ms: Some(record.exec_time.map(|secs| (secs * 1000.0).round() as u64).unwrap_or(0)),
+            output,
+            ignore_reason,
+            failure: None,
+            target,
+            attempts: Vec::new(),
+        });
+    }
+
+    attach_failures(&mut result);
+    result
+}
+
+/// Element whose text is being read
+#[derive(PartialEq)]
+enum JUnitText {
+    None,
+    Failure,
+    Output,
+}
+
+/// Results of a JUnit XML report
+fn parse_junit(content: &str) -> Result<TestRunResult> {
+    let mut reader = Reader::from_str(content);
+    reader.trim_text(true);
+
+    let mut result = TestRunResult::default();
+    let mut target: Option<TestTarget> = None;
+    let mut current: Option<SingleTestResult> = None;
+    let mut failure: Option<(Option<String>, String)> = None;
+    let mut reading = JUnitText::None;
+
+    loop {
+        let event = reader.read_event()
+            .with_context(|| format!("Invalid JUnit XML at byte {}", reader.buffer_position()))?;
+        let empty = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => match e.name().as_ref() {
+                b"testsuite" => {
+                    // Suites are targets: runx's labels or nextest's binary ids
+                    target = attribute(e, "name")?.map(|name| {
+                        TestTarget::from_qualified_label(&name).unwrap_or_else(|| TestTarget::from_binary_id(&name))
+                    });
+                }
+                b"testcase" => {
+                    let test = SingleTestResult {
+                        name: attribute(e, "name")?.unwrap_or_default(),
+                        status: TestStatus::Passed,
+                        duration_ms: attribute(e, "time")?
+                            .and_then(|t| t.parse::<f64>().ok())
+                            .map(|secs| (secs * 1000.0).round() as u64),
+                        output: Vec::new(),
+                        ignore_reason: None,
+                        failure: None,
+                        target: target.clone(),
+                        attempts: Vec::new(),
+                    };
+                    if empty {
+                        result.test_results.push(test);
+                    } else {
+                        current = Some(test);
+                    }
+                }
+                b"failure" | b"error" => {
+                    if let Some(ref mut test) = current {
+                        test.status = match attribute(e, "type")?.as_deref() {
+                            Some("timeout") => TestStatus::TimedOut,
+                            _ => TestStatus::Failed,
+                        };
+                        failure = Some((attribute(e, "message")?, String::new()));
+                        reading = if empty { JUnitText::None } else { JUnitText::Failure };
+                    }
+                }
+                b"skipped" => {
+                    if let Some(ref mut test) = current {
+                        test.status = TestStatus::Ignored;
+                        test.ignore_reason = attribute(e, "message")?.filter(|m| !m.is_empty());
+                    }
+                }
+                b"system-out" | b"system-err" if !empty => reading = JUnitText::Output,
+                _ => {}
+            },
+            Event::Text(ref text) => {
+                let text = text.unescape()?;
+                add_text(&mut current, &mut failure, &reading, &text);
+            }
+            Event::CData(text) => {
+                let text = String::from_utf8_lossy(&text.into_inner()).into_owned();
+                add_text(&mut current, &mut failure, &reading, &text);
+            }
+            Event::End(ref e) => match e.name().as_ref() {
+                b"failure" | b"error" | b"system-out" | b"system-err" => reading = JUnitText::None,
+                b"testcase" => {
+                    if let Some(mut test) = current.take() {
+                        if let Some((message, body)) = failure.take() {
+                            test.failure = TestFailure::parse(&test.output)
+                                .or_else(|| TestFailure::parse(&body.lines().map(String::from).collect::<Vec<_>>()))
+                                .or_else(|| {
+                                    let message = if body.trim().is_empty() { message? } else { body };
+                                    Some(TestFailure::from_message(message.trim_end()))
+                                });
+                        }
+                        result.test_results.push(test);
+                    }
+                }
+                b"testsuite" => target = None,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(result)
+}
+
+/// Unescaped value of an attribute
+fn attribute(element: &BytesStart, name: &str) -> Result<Option<String>> {
+    Ok(match element.try_get_attribute(name)? {
+        Some(attribute) => Some(attribute.unescape_value()?.into_owned()),
+        None => None,
+    })
+}
+
+/// Append text to the failure or output being read
+fn add_text(current: &mut Option<SingleTestResult>, failure: &mut Option<(Option<String>, String)>, reading: &JUnitText, text: &str) {
+    match reading {
+        JUnitText::Failure => {
+            if let Some((_, ref mut body)) = failure {
+                body.push_str(text);
+            }
+        }
+        JUnitText::Output => {
+            if let Some(ref mut test) = current {
+                test.output.extend(text.lines().map(String::from));
+            }
+        }
+        JUnitText::None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_model::TargetKind;
+
+    #[test]
+    fn test_parse_junit() {
+        let report = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="ws">
+  <testsuite name="core (lib)" tests="3">
+    <testcase name="tests::ok" classname="core::tests" time="0.250"/>
+    <testcase name="tests::broken" classname="core::tests" time="0.010">
+      <failure message="assertion `left == right` failed" type="assert_eq">assertion `left == right` failed
+  left: 4
+ right: 5</failure>
+      <system-out><![CDATA[some <output>]]></system-out>
+    </testcase>
+    <testcase name="tests::network" classname="core::tests" time="0">
+      <skipped message="needs network"/>
+    </testcase>
+  </testsuite>
+  <testsuite name="core::api">
+    <testcase name="slow" time="60"><failure type="timeout"/></testcase>
+  </testsuite>
+</testsuites>"#;
+
+        assert_eq!(ImportFormat::detect(report), Some(ImportFormat::Junit));
+        let result = parse_report(report, ImportFormat::Junit).unwrap();
+        assert_eq!((result.passed, result.failed, result.ignored), (1, 2, 1));
+
+        let tests = &result.test_results;
+        assert_eq!(tests[0].duration_ms, Some(250));
+        assert_eq!(tests[0].target.as_ref().unwrap().qualified_label(), "core (lib)");
+        let failure = tests[1].failure.as_ref().unwrap();
+        assert_eq!(failure.left.as_deref(), Some("4"));
+        assert_eq!(tests[1].output, vec!["some <output>"]);
+        assert_eq!(tests[2].ignore_reason.as_deref(), Some("needs network"));
+        assert_eq!(tests[3].status, TestStatus::TimedOut);
+        assert_eq!(tests[3].target.as_ref().unwrap().kind, TargetKind::Test);
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let libtest = r#"{ "type": "suite", "event": "started", "test_count": 2 }
+{ "type": "test", "event": "started", "name": "a::passes" }
+{ "type": "test", "name": "a::passes", "event": "ok", "exec_time": 0.012 }
+{ "type": "test", "name": "a::fails", "event": "failed", "stdout": "thread 'a::fails' panicked at src/lib.rs:3:5:\nboom\n" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1 }"#;
+        assert_eq!(ImportFormat::detect(libtest), Some(ImportFormat::LibtestJson));
+        let result = parse_report(libtest, ImportFormat::LibtestJson).unwrap();
+        assert_eq!((result.passed, result.failed), (1, 1));
+        assert_eq!(result.test_results[0].duration_ms, Some(12));
+        assert_eq!(result.test_results[1].failure.as_ref().unwrap().message, "boom");
+
+        let nextest = r#"{"type":"test","event":"ignored","name":"cli::bin/cli$tests::slow","message":"needs network"}"#;
+        assert_eq!(ImportFormat::detect(nextest), Some(ImportFormat::NextestJson));
+        let result = parse_report(nextest, ImportFormat::NextestJson).unwrap();
+        let test = &result.test_results[0];
+        assert_eq!(test.name, "tests::slow");
+        assert_eq!(test.target.as_ref().unwrap().qualified_label(), "cli (bin cli)");
+        assert_eq!(test.ignore_reason.as_deref(), Some("needs network"));
+
+        assert_eq!(ImportFormat::detect("not a report"), None);
+    }
+}
@@ -0,0 +1,307 @@
+//! JUnit XML export
+//!
+//! Writes a run in the JUnit XML format read by CI servers (GitLab, Jenkins):
+//! one `<testsuite>` per target and one `<testcase>` per test, with failures,
+//! skipped tests and captured output.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::Writer;
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::db::RunSummary;
+use crate::failure::strip_ansi;
+use crate::test_model::Test;
+use crate::test_runner::TestRunResult;
+
+/// A test, as written to a `<testcase>`
+#[derive(Debug, Clone)]
+pub struct JUnitCase {
+    pub name: String,
+    pub classname: String,
+    /// Database status: "passed", "failed", "timed_out", "skipped", ...
+    pub status: String,
+    pub duration_ms: u64,
+    /// Panic message of a failed test
+    pub message: Option<String>,
+    /// Failure kind, e.g. "assert_eq"
+    pub failure_type: Option<String>,
+    /// Reason given with `#[ignore = "..."]`
+    pub ignore_reason: Option<String>,
+    pub output: Option<String>,
+}
+
+impl JUnitCase {
+    fn failed(&self) -> bool {
+        self.status == "failed" || self.status == "timed_out"
+    }
+
+    fn skipped(&self) -> bool {
+        matches!(self.status.as_str(), "skipped" | "ignored" | "cancelled")
+    }
+}
+
+/// A run, with its tests grouped into suites by target
+#[derive(Debug, Clone)]
+pub struct JUnitReport {
+    pub name: String,
+    pub timestamp: DateTime<Utc>,
+    /// Suite name (target label) → tests
+    pub suites: BTreeMap<String, Vec<JUnitCase>>,
+}
+
+impl JUnitReport {
+    pub fn new(name: &str, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            name: name.to_string(),
+            timestamp,
+            suites: BTreeMap::new(),
+        }
+    }
+
+    /// Add a test to the suite of its target, or of the report when unknown
+    pub fn add(&mut self, target: Option<&str>, case: JUnitCase) {
+        let suite = target.unwrap_or(&self.name).to_string();
+        self.suites.entry(suite).or_default().push(case);
+    }
+
+    /// Report of a run that just finished
+    pub fn from_run_result(name: &str, timestamp: DateTime<Utc>, result: &TestRunResult) -> Self {
+        let mut report = Self::new(name, timestamp);
+        for test in &result.test_results {
+            let target = test.target.as_ref().map(|t| t.qualified_label());
+            report.add(target.as_deref(), JUnitCase {
+                name: test.name.clone(),
+                classname: classname(&test.name, target.as_deref()),
+                status: test.status.as_db_str().to_string(),
+                duration_ms: test.duration_ms.unwrap_or(0),
+                message: test.failure.as_ref().map(|f| f.message.clone()),
+                failure_type: test.failure.as_ref().map(|f| f.kind.label().to_string()),
+                ignore_reason: test.ignore_reason.clone(),
+                output: (!test.output.is_empty()).then(|| test.output.join("\n")),
+            });
+        }
+        report
+    }
+
+    /// Report of a recorded run
+    pub fn from_run_summary(name: &str, summary: &RunSummary) -> Self {
+        let mut report = Self::new(name, summary.run.started_at);
+        for task in &summary.tasks {
+            report.add(task.target.as_deref(), JUnitCase {
+                name: task.task_name.clone(),
+                classname: task.classname.clone()
+                    .unwrap_or_else(|| classname(&task.task_name, task.target.as_deref())),
+                status: task.status.clone(),
+                duration_ms: task.duration_ms.max(0) as u64,
+                message: task.error_message.clone(),
+                failure_type: task.error_type.clone(),
+                ignore_reason: task.ignore_reason.clone(),
+                output: task.output.clone(),
+            });
+        }
+        report
+    }
+
+    /// The report as a JUnit XML document
+    pub fn to_xml(&self) -> Result<String> {
+        let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+        let cases: Vec<&JUnitCase> = self.suites.values().flatten().collect();
+        writer
+            .create_element("testsuites")
+            .with_attributes(counts(&self.name, &cases).iter().map(|(k, v)| (*k, v.as_str())))
+            .write_inner_content(|writer| {
+                for (suite, cases) in &self.suites {
+                    let cases: Vec<&JUnitCase> = cases.iter().collect();
+                    let mut attributes = counts(suite, &cases);
+                    attributes.push(("timestamp", self.timestamp.format("%Y-%m-%dT%H:%M:%S").to_string()));
+                    writer
+                        .create_element("testsuite")
+                        .with_attributes(attributes.iter().map(|(k, v)| (*k, v.as_str())))
+                        .write_inner_content(|writer| {
+                            for case in cases {
+                                write_case(writer, case)?;
+                            }
+                            Ok::<_, quick_xml::Error>(())
+                        })?;
+                }
+                Ok::<_, quick_xml::Error>(())
+            })?;
+
+        Ok(String::from_utf8(writer.into_inner().into_inner())?)
+    }
+
+    /// Write the report to a file
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_xml()?)?;
+        Ok(())
+    }
+}
+
+/// `name`, `tests`, `failures`, `errors`, `skipped` and `time` attributes
+fn counts(name: &str, cases: &[&JUnitCase]) -> Vec<(&'static str, String)> {
+    let duration_ms: u64 = cases.iter().map(|c| c.duration_ms).sum();
+    vec![
+        ("name", name.to_string()),
+        ("tests", cases.len().to_string()),
+        ("failures", cases.iter().filter(|c| c.failed()).count().to_string()),
+        ("errors", "0".to_string()),
+        ("skipped", cases.iter().filter(|c| c.skipped()).count().to_string()),
+        ("time", seconds(duration_ms)),
+    ]
+}
+
+fn write_case<W: std::io::Write>(writer: &mut Writer<W>, case: &JUnitCase) -> quick_xml::Result<()> {
+    let time = seconds(case.duration_ms);
+    let element = writer.create_element("testcase").with_attributes([
+        ("name", case.name.as_str()),
+        ("classname", case.classname.as_str()),
+        ("time", time.as_str()),
+    ]);
+    if !case.failed() && !case.skipped() && case.output.is_none() {
+        element.write_empty()?;
+        return Ok(());
+    }
+
+    element.write_inner_content(|writer| {
+        if case.failed() {
+            let timed_out = case.status == "timed_out";
+            let message = match case.message {
+                Some(ref message) => message.clone(),
+                None if timed_out => "test timed out".to_string(),
+                None => "test failed".to_string(),
+            };
+            let kind = if timed_out { "timeout" } else { case.failure_type.as_deref().unwrap_or("panic") };
+            writer
+                .create_element("failure")
+                .with_attributes([
+                    ("message", xml_text(message.lines().next().unwrap_or_default()).as_str()),
+                    ("type", kind),
+                ])
+                .write_text_content(BytesText::new(&xml_text(&message)))?;
+        } else if case.skipped() {
+            let message = match case.ignore_reason {
+                Some(ref reason) => xml_text(reason),
+                None => case.status.clone(),
+            };
+            writer
+                .create_element("skipped")
+                .with_attribute(("message", message.as_str()))
+                .write_empty()?;
+        }
+        if let Some(ref output) = case.output {
+            writer
+                .create_element("system-out")
+                .write_text_content(BytesText::new(&xml_text(output)))?;
+        }
+        Ok::<_, quick_xml::Error>(())
+    })?;
+    Ok(())
+}
+
+/// JUnit class of a test: its package followed by its module path, e.g.
+/// "core::parser::tests" for `parser::tests::it_works` in "core (lib)".
+/// Doc tests are classed by file.
+pub fn classname(test_name: &str, target: Option<&str>) -> String {
+    let package = target.and_then(|t| t.split_once(" (")).map(|(package, _)| package);
+    let module = match test_name.split_once(" - ") {
+        Some((file, _)) => file.to_string(),
+        None => Test::from_name(test_name).module_path.join("::"),
+    };
+
+    match (package, module.is_empty()) {
+        (Some(package), true) => package.to_string(),
+        (Some(package), false) => format!("{}::{}", package, module),
+        (None, false) => module,
+        (None, true) => test_name.to_string(),
+    }
+}
+
+fn seconds(duration_ms: u64) -> String {
+    format!("{:.3}", duration_ms as f64 / 1000.0)
+}
+
+/// Text without terminal colors nor the control characters XML 1.0 forbids
+fn xml_text(text: &str) -> String {
+    strip_ansi(text).chars()
+        .filter(|&c| matches!(c, '\t' | '\n' | '\r') || (c >= ' ' && c != '\u{FFFE}' && c != '\u{FFFF}'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(name: &str, status: &str) -> JUnitCase {
+        JUnitCase {
+            name: name.to_string(),
+            classname: classname(name, Some("core (lib)")),
+            status: status.to_string(),
+            duration_ms: 1500,
+            message: (status == "failed").then(|| "assertion `left == right` failed\n  left: 4".to_string()),
+            failure_type: (status == "failed").then(|| "assert_eq".to_string()),
+            ignore_reason: (status == "skipped").then(|| "needs <network>".to_string()),
+            output: (status == "failed").then(|| "\x1b[31mthread panicked\x1b[0m".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_classname() {
+        assert_eq!(classname("parser::tests::it_works", Some("core (lib)")), "core::parser::tests");
+        assert_eq!(classname("it_works", Some("core (integration api)")), "core");
+        assert_eq!(classname("src/lib.rs - add (line 3)", Some("core (doc)")), "core::src/lib.rs");
+        assert_eq!(classname("tests::it_works", None), "tests");
+    }
+
+    #[test]
+    fn test_to_xml() {
+        let mut report = JUnitReport::new("ws", Utc::now());
+        report.add(Some("core (lib)"), case("tests::ok", "passed"));
+        report.add(Some("core (lib)"), case("tests::broken", "failed"));
+        report.add(Some("core (lib)"), case("tests::network", "skipped"));
+        report.add(Some("cli (bin cli)"), case("parses_args", "timed_out"));
+
+        let xml = report.to_xml().unwrap();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(xml.contains(r#"<testsuites name="ws" tests="4" failures="2" errors="0" skipped="1" time="6.000">"#));
+        assert!(xml.contains(r#"<testsuite name="core (lib)" tests="3" failures="1" errors="0" skipped="1" time="4.500""#));
+        assert!(xml.contains(r#"<testcase name="tests::ok" classname="core::tests" time="1.500"/>"#));
+        assert!(xml.contains(r#"<failure message="assertion `left == right` failed" type="assert_eq">"#));
+        assert!(xml.contains(r#"<skipped message="needs &lt;network&gt;"/>"#));
+        assert!(xml.contains(r#"<failure message="test timed out" type="timeout">"#));
+        assert!(xml.contains("<system-out>thread panicked</system-out>"));
+    }
+
+    #[test]
+    fn test_recorded_classname() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = crate::db::Database::open(&dir.path().join("runx.db")).unwrap();
+        db.create_run("run-1", 1).unwrap();
+        db.insert_task_result(&crate::db::TaskResult {
+            id: "task-1".to_string(),
+            run_id: "run-1".to_string(),
+            task_name: "parser::tests::broken".to_string(),
+            category: Some("test".to_string()),
+            status: "failed".to_string(),
+            duration_ms: 3,
+            started_at: Utc::now(),
+            output: None,
+            ignore_reason: None,
+            error_message: Some("boom".to_string()),
+            error_type: Some("panic".to_string()),
+            retries: 0,
+            target: Some("core (lib)".to_string()),
+            classname: None,
+        }).unwrap();
+
+        let summary = db.get_run_summary("run-1").unwrap().unwrap();
+        assert_eq!(summary.tasks[0].classname.as_deref(), Some("core::parser::tests"));
+        let report = JUnitReport::from_run_summary("ws", &summary);
+        assert_eq!(report.suites["core (lib)"][0].classname, "core::parser::tests");
+    }
+}
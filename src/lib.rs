@@ -1,7 +1,7 @@
 //! Runx - Rust Test Explorer
 //!
 //! A library for discovering, running, and managing Rust tests with:
-//! - Automatic test discovery via `cargo test -- --list`
+//! - Automatic test discovery from the compiled test binaries
 //! - Test tree with hierarchical module structure
 //! - Real-time test execution with streaming output
 //! - Watch mode with affected test detection
@@ -11,10 +11,21 @@
 
 // Core modules
 pub mod affected;
+pub mod compare;
+pub mod config;
+pub mod coverage;
 pub mod db;
+pub mod diff;
 pub mod discovery;
+pub mod editor;
+pub mod failure;
+pub mod import;
+pub mod junit;
+pub mod output;
+pub mod source_index;
 pub mod test_model;
 pub mod test_runner;
+pub mod trend;
 pub mod tui;
 pub mod watcher;
 
@@ -26,6 +37,6 @@ pub mod task;
 
 // Re-exports
 pub use discovery::{discover_all_tests, discover_tests, get_project_name, is_rust_project};
-pub use test_model::{FilterMode, Test, TestNode, TestStats, TestStatus};
+pub use test_model::{FilterMode, TargetKind, Test, TestNode, TestScope, TestStats, TestStatus, TestTarget};
 pub use test_runner::{TestEvent, TestRunResult, TestRunner};
 pub use server::start_server;
@@ -10,29 +10,56 @@
 
 mod affected;
 mod artifacts;
+mod compare;
+mod config;
+mod coverage;
 mod db;
+mod diff;
 mod discovery;
+mod editor;
+mod failure;
+mod import;
+mod junit;
+mod output;
 mod report;
 mod server;
+mod source_index;
 mod task;
 mod test_model;
 mod test_runner;
+mod trend;
 mod tui;
 mod watcher;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use colored::Colorize;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use chrono::Utc;
 use uuid::Uuid;
 
-use db::Database;
+use config::RunxConfig;
+use coverage::CoverageSession;
+use db::{Database, DurationBaseline, DurationRegression};
 use discovery::{discover_all_tests, get_project_name, is_rust_project};
-use test_model::TestStatus;
-use test_runner::TestRunner;
+use output::CliFormat;
+use test_model::{TargetKind, TestScope, TestStatus};
+use test_runner::{IgnoredTests, RetryPolicy, TestBackend, TestRunner};
 use watcher::TestWatcher;
 
+/// Print a progress message: on stdout for humans, on stderr when stdout
+/// carries machine-readable output
+macro_rules! progress {
+    ($format:expr, $($arg:tt)*) => {
+        if $format.is_human() {
+            println!($($arg)*);
+        } else {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
 const DEFAULT_DB_NAME: &str = ".runx.db";
 
 #[derive(Parser)]
@@ -48,29 +75,89 @@ struct Cli {
     dir: Option<PathBuf>,
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// Run tests (default if no subcommand)
-    Run {
-        /// Filter pattern to match test names
-        filter: Option<String>,
+#[derive(Args)]
+struct RunArgs {
+    /// Filter pattern to match test names
+    filter: Option<String>,
 
-        /// Run only failed tests from last run
-        #[arg(long)]
-        failed: bool,
+    /// Run only failed tests from last run
+    #[arg(long)]
+    failed: bool,
 
-        /// Verbose output
-        #[arg(short, long)]
-        verbose: bool,
+    /// Verbose output
+    #[arg(short, long)]
+    verbose: bool,
 
-        /// Generate HTML report after run
-        #[arg(long)]
-        report: bool,
+    /// Generate HTML report after run
+    #[arg(long)]
+    report: bool,
 
-        /// Retry failed tests N times
-        #[arg(long, value_name = "N")]
-        retry: Option<u32>,
-    },
+    /// Write the results as JUnit XML to FILE
+    #[arg(long, value_name = "FILE")]
+    junit: Option<PathBuf>,
+
+    /// Retry failed tests N times (default: `[retry] count` in runx.toml)
+    #[arg(long, value_name = "N")]
+    retry: Option<u32>,
+
+    /// Test runner backend (default: `[test] backend` in runx.toml, else cargo)
+    #[arg(long, value_enum)]
+    backend: Option<TestBackend>,
+
+    /// Number of tests to run concurrently (default: `[test] jobs` in runx.toml, else 1)
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+
+    /// Kill any test running longer than SECS (default: `[test] timeout` in runx.toml)
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: Option<u64>,
+
+    /// Stop the whole run after SECS (default: `[test] run_timeout` in runx.toml)
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    run_timeout: Option<u64>,
+
+    /// Only test the given workspace package (repeatable)
+    #[arg(short, long = "package", value_name = "PKG")]
+    package: Vec<String>,
+
+    /// Only run the given integration test target (repeatable)
+    #[arg(long = "test", value_name = "TARGET")]
+    test: Vec<String>,
+
+    /// Also run #[ignore]d tests
+    #[arg(long, conflicts_with = "ignored")]
+    include_ignored: bool,
+
+    /// Run only #[ignore]d tests
+    #[arg(long)]
+    ignored: bool,
+
+    /// Fail when a test is materially slower than its duration baseline
+    #[arg(long)]
+    fail_on_regression: bool,
+
+    /// Measure line coverage (LLVM source-based, with cargo-llvm-cov or llvm-tools)
+    #[arg(long)]
+    coverage: bool,
+
+    /// Fail when line coverage is below PCT percent (default: `[coverage] threshold` in runx.toml)
+    #[arg(long, value_name = "PCT", requires = "coverage")]
+    coverage_threshold: Option<f64>,
+
+    /// Also record the lines each test executes, running every test again on
+    /// its own, so that `runx watch` re-runs exactly the tests a change touches
+    #[arg(long, requires = "coverage")]
+    coverage_map: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t)]
+    format: CliFormat,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Run tests (default if no subcommand)
+    Run(RunArgs),
 
     /// List all discovered tests
     List {
@@ -80,6 +167,10 @@ enum Commands {
         /// Show full test paths
         #[arg(short, long)]
         full: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: CliFormat,
     },
 
     /// Watch for changes and re-run affected tests
@@ -89,7 +180,11 @@ enum Commands {
     },
 
     /// Discover tests without running them
-    Discover,
+    Discover {
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: CliFormat,
+    },
 
     /// Interactive TUI mode
     Tui,
@@ -113,10 +208,46 @@ enum Commands {
         /// Specific run ID to report on
         #[arg(long)]
         run: Option<String>,
+
+        /// Add the changes since this run to the report
+        #[arg(long, value_name = "RUN")]
+        compare: Option<String>,
     },
 
-    /// Show run history
+    /// Import results produced outside runx (JUnit XML, libtest or nextest JSON)
+    Import {
+        /// Report file
+        file: PathBuf,
+
+        /// Where the results come from, e.g. "docker" (default: the file name)
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Report format (default: detected from the content)
+        #[arg(long, value_enum)]
+        format: Option<import::ImportFormat>,
+    },
+
+    /// Export a recorded run for other tools
+    Export {
+        #[command(subcommand)]
+        format: ExportFormat,
+    },
+
+    /// Compare two runs (default: the previous run with the latest)
+    Diff {
+        /// Base run ID (or prefix); default: the run before `head`
+        base: Option<String>,
+
+        /// Run ID (or prefix) to compare with the base; default: the latest run
+        head: Option<String>,
+    },
+
+    /// Show run history, or every recorded execution of one test
     History {
+        /// Test name (or part of it) whose executions to show
+        test: Option<String>,
+
         /// Number of runs to show
         #[arg(short, long, default_value = "20")]
         limit: i32,
@@ -124,6 +255,10 @@ enum Commands {
         /// Clear all history
         #[arg(long)]
         clear: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: CliFormat,
     },
 
     /// Show statistics
@@ -131,7 +266,73 @@ enum Commands {
         /// Show flaky tests
         #[arg(long)]
         flaky: bool,
+
+        /// Show duration baselines and the last run's regressions
+        #[arg(long)]
+        slow: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: CliFormat,
+    },
+
+    /// Manage quarantined tests, whose failures do not fail the run
+    Quarantine {
+        #[command(subcommand)]
+        action: QuarantineAction,
+    },
+
+    /// Run the task pipeline declared in runx.toml
+    Tasks {
+        /// Tasks to run, with their dependencies (default: all)
+        names: Vec<String>,
+
+        /// List tasks in execution order without running them
+        #[arg(long)]
+        list: bool,
+
+        /// Generate HTML report after run
+        #[arg(long)]
+        report: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportFormat {
+    /// JUnit XML, as read by CI servers
+    Junit {
+        /// Run ID (or prefix); default: the latest run
+        #[arg(long)]
+        run: Option<String>,
+
+        /// Output file (default: standard output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum QuarantineAction {
+    /// Quarantine a test
+    Add {
+        /// Full test name (e.g. net::tests::fetch)
+        test: String,
+
+        /// Only in this target, e.g. "net (lib)" (default: every target)
+        #[arg(long)]
+        target: Option<String>,
     },
+    /// Take a test out of quarantine
+    Remove {
+        /// Full test name
+        test: String,
+
+        /// Only in this target (default: every target)
+        #[arg(long)]
+        target: Option<String>,
+    },
+    /// List quarantined tests
+    List,
 }
 
 fn main() {
@@ -167,17 +368,17 @@ fn run() -> Result<()> {
             // Default: run TUI
             cmd_tui(&project_dir, &db_path)
         }
-        Some(Commands::Run { filter, failed, verbose, report, retry }) => {
-            cmd_run(&project_dir, &db_path, filter, failed, verbose, report, retry)
+        Some(Commands::Run(args)) => {
+            cmd_run(&project_dir, &db_path, args)
         }
-        Some(Commands::List { filter, full }) => {
-            cmd_list(&project_dir, filter, full)
+        Some(Commands::List { filter, full, format }) => {
+            cmd_list(&project_dir, filter, full, format)
         }
         Some(Commands::Watch { filter }) => {
             cmd_watch(&project_dir, &db_path, filter)
         }
-        Some(Commands::Discover) => {
-            cmd_discover(&project_dir)
+        Some(Commands::Discover { format }) => {
+            cmd_discover(&project_dir, format)
         }
         Some(Commands::Tui) => {
             cmd_tui(&project_dir, &db_path)
@@ -185,132 +386,403 @@ fn run() -> Result<()> {
         Some(Commands::Dashboard { port, watch }) => {
             cmd_dashboard(&project_dir, &db_path, port, watch)
         }
-        Some(Commands::Report { output, run }) => {
-            cmd_report(&project_dir, &db_path, &output, run)
+        Some(Commands::Report { output, run, compare }) => {
+            cmd_report(&project_dir, &db_path, &output, run, compare)
+        }
+        Some(Commands::Import { file, source, format }) => {
+            cmd_import(&db_path, &file, source, format)
+        }
+        Some(Commands::Export { format }) => {
+            cmd_export(&project_dir, &db_path, format)
+        }
+        Some(Commands::Diff { base, head }) => {
+            cmd_diff(&db_path, base, head)
+        }
+        Some(Commands::History { test: Some(test), limit, format, .. }) => {
+            cmd_test_history(&db_path, &test, limit, format)
         }
-        Some(Commands::History { limit, clear }) => {
-            cmd_history(&db_path, limit, clear)
+        Some(Commands::History { test: None, limit, clear, format }) => {
+            cmd_history(&db_path, limit, clear, format)
         }
-        Some(Commands::Stats { flaky }) => {
-            cmd_stats(&db_path, flaky)
+        Some(Commands::Stats { flaky, slow, format }) => {
+            cmd_stats(&db_path, flaky, slow, format)
+        }
+        Some(Commands::Quarantine { action }) => {
+            cmd_quarantine(&db_path, action)
+        }
+        Some(Commands::Tasks { names, list, report }) => {
+            cmd_tasks(&project_dir, &db_path, &names, list, report)
         }
     }
 }
 
-fn cmd_run(
-    project_dir: &Path,
-    db_path: &Path,
-    filter: Option<String>,
-    failed: bool,
-    verbose: bool,
-    generate_report: bool,
-    retry: Option<u32>,
-) -> Result<()> {
+fn cmd_run(project_dir: &Path, db_path: &Path, args: RunArgs) -> Result<()> {
+    let RunArgs {
+        filter, failed, verbose, report: generate_report, junit, retry, backend, jobs, timeout, run_timeout,
+        package, test, include_ignored, ignored, fail_on_regression, format, coverage, coverage_threshold,
+        coverage_map,
+    } = args;
     let project_name = get_project_name(project_dir)?;
     let db = Database::open(db_path).ok();
+    let config = RunxConfig::load(project_dir).unwrap_or_default();
 
-    println!("\n{} {} {}\n", "🧪".cyan(), "Running tests for".bold(), project_name.cyan());
+    progress!(format, "\n{} {} {}\n", "🧪".cyan(), "Running tests for".bold(), project_name.cyan());
 
-    let runner = TestRunner::new(project_dir);
+    // Instrument the build before anything spawns a thread or a process
+    let coverage_session = if coverage {
+        let session = CoverageSession::start(project_dir)?;
+        progress!(format, "{} Measuring coverage with {}\n", "📈".cyan(), session.tool_name());
+        Some(session)
+    } else {
+        None
+    };
+    let coverage_threshold = coverage_threshold.or(config.coverage.threshold).filter(|_| coverage);
+
+    let mut runner = TestRunner::new(project_dir);
+    if let Some(backend) = backend {
+        runner = runner.with_backend(backend);
+    }
+    if let Some(jobs) = jobs {
+        runner = runner.with_jobs(jobs as usize);
+    }
+    if let Some(secs) = timeout {
+        runner = runner.with_test_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = run_timeout {
+        runner = runner.with_run_timeout(Duration::from_secs(secs));
+    }
+    runner = runner.with_scope(TestScope {
+        packages: package,
+        targets: test.into_iter().map(|name| (TargetKind::Test, name)).collect(),
+    });
+    if include_ignored {
+        runner = runner.with_ignored(IgnoredTests::Include);
+    } else if ignored {
+        runner = runner.with_ignored(IgnoredTests::Only);
+    }
+
+    // Report slow tests while they are still running
+    // (or stream every event as NDJSON)
+    let (event_tx, event_rx) = test_runner::create_event_channel();
+    runner = runner.with_event_sender(event_tx);
+    let events = std::thread::spawn(move || {
+        for event in event_rx {
+            if format == CliFormat::Ndjson {
+                let _ = output::print_record(&output::EventRecord::from(&event));
+            } else if let test_runner::TestEvent::Slow { test_name, elapsed_ms } = event {
+                progress!(format, "{} {} has been running for over {}s",
+                    "⚠".yellow(), test_name, elapsed_ms / 1000);
+            }
+        }
+    });
+
+    // Ctrl+C cancels the session (killing the test processes) instead of
+    // leaving the run unfinished in the database
+    let cancel = runner.cancel_handle();
+    watch_ctrl_c(cancel.clone());
+
+    // Load failed tests from last run (before this run is recorded)
+    let failed_tests = match (&db, failed) {
+        (Some(db), true) => Some(db.get_failed_tests_from_last_run()?),
+        _ => None,
+    };
+
+    // Quarantined tests run as usual, but their failures do not fail the run
+    let mut quarantined = match db {
+        Some(ref db) => db.quarantined_tests()?,
+        None => Default::default(),
+    };
 
     // Create run in database
     let run_id = Uuid::new_v4().to_string();
+    let run_started_at = Utc::now();
     if let Some(ref db) = db {
         db.create_run(&run_id, 0)?;
     }
 
     let mut result = if failed {
-        // Load failed tests from last run
-        if let Some(ref db) = db {
-            let failed_tests = db.get_failed_tests_from_last_run()?;
+        if let Some(failed_tests) = failed_tests {
             if failed_tests.is_empty() {
-                println!("{}", "No failed tests from last run".dimmed());
+                progress!(format, "{}", "No failed tests from last run".dimmed());
+                // Scripts still get an empty run in the format they asked for
+                if !format.is_human() {
+                    let result = test_runner::TestRunResult { success: true, ..Default::default() };
+                    let run = RunReport { run_id: &run_id, project_name: &project_name, result: &result, quarantined: &quarantined };
+                    print_run(format, &run, &[], None, None, verbose)?;
+                }
                 return Ok(());
             }
-            println!("{} {} failed test(s) to retry\n", "→".blue(), failed_tests.len());
+            progress!(format, "{} {} failed test(s) to retry\n", "→".blue(), failed_tests.len());
             runner.run_specific(&failed_tests)?
         } else {
-            println!("{}", "No database available, running all tests".dimmed());
+            progress!(format, "{}", "No database available, running all tests".dimmed());
             runner.run_all()?
         }
     } else if let Some(ref f) = filter {
-        println!("{} {}\n", "Filter:".dimmed(), f.cyan());
+        progress!(format, "{} {}\n", "Filter:".dimmed(), f.cyan());
         runner.run_filtered(f)?
     } else {
         runner.run_all()?
     };
 
-    // Retry failed tests if requested
-    if let Some(max_retries) = retry {
-        let mut retries = 0;
-        while result.failed > 0 && retries < max_retries {
-            retries += 1;
-            let failed_names: Vec<String> = result.test_results
-                .iter()
-                .filter(|t| t.status == TestStatus::Failed)
-                .map(|t| t.name.clone())
-                .collect();
-
-            println!("\n{} Retry {}/{} for {} failed test(s)...\n",
-                "🔄".yellow(), retries, max_retries, failed_names.len());
-
-            let retry_result = runner.run_specific(&failed_names)?;
-
-            // Update results
-            for retry_test in retry_result.test_results {
-                if let Some(orig) = result.test_results.iter_mut().find(|t| t.name == retry_test.name) {
-                    if retry_test.status == TestStatus::Passed {
-                        orig.status = TestStatus::Passed;
-                        result.passed += 1;
-                        result.failed -= 1;
-                    }
+    // Retry failed tests, within each test's budget
+    let retry_policy = RetryPolicy::from_config(&config.retry, retry);
+    let max_retries = retry_policy.max_retries();
+    let mut round = 0;
+    while round < max_retries && !cancel.is_cancelled() {
+        let to_retry = retry_policy.to_retry(&result);
+        if to_retry.is_empty() {
+            break;
+        }
+        round += 1;
+        progress!(format, "\n{} Retry {}/{} for {} failed test(s)...\n",
+            "🔄".yellow(), round, max_retries, to_retry.len());
+
+        result.merge_retry(runner.run_specific(&to_retry)?);
+    }
+    // Every event is printed before the results
+    let mapper = runner.detached();
+    drop(runner);
+    let _ = events.join();
+
+    let coverage = match coverage_session.as_ref().map(|session| session.report()) {
+        Some(Ok(report)) => Some(report),
+        Some(Err(e)) => {
+            progress!(format, "{} Coverage could not be measured: {:#}", "⚠".yellow(), e);
+            None
+        }
+        None => None,
+    };
+    let test_coverage = match coverage_session.as_ref().filter(|_| coverage_map) {
+        Some(session) => {
+            progress!(format, "{} Mapping the lines each test executes...", "📈".cyan());
+            match session.map_tests(&result, &mapper) {
+                Ok(map) => map,
+                Err(e) => {
+                    progress!(format, "{} Coverage could not be mapped per test: {:#}", "⚠".yellow(), e);
+                    Vec::new()
                 }
             }
         }
-    }
+        None => Vec::new(),
+    };
 
     // Save results to database
+    let mut regressions = Vec::new();
     if let Some(ref db) = db {
-        let started_at = Utc::now();
-        for test in &result.test_results {
-            let task_result = db::TaskResult {
-                id: Uuid::new_v4().to_string(),
-                run_id: run_id.clone(),
-                task_name: test.name.clone(),
-                category: Some("test".to_string()),
-                status: match test.status {
-                    TestStatus::Passed => "passed".to_string(),
-                    TestStatus::Failed => "failed".to_string(),
-                    TestStatus::Ignored => "skipped".to_string(),
-                    _ => "pending".to_string(),
-                },
-                duration_ms: test.duration_ms.unwrap_or(0) as i64,
-                started_at,
-                output: if test.output.is_empty() { None } else { Some(test.output.join("\n")) },
-            };
-            db.insert_task_result(&task_result)?;
+        db.save_test_results(&run_id, &result)?;
+        regressions = db.find_regressions(&run_id)?;
+        if let Some(ref report) = coverage {
+            db.save_coverage(&run_id, report, coverage_threshold)?;
+        }
+        if !test_coverage.is_empty() {
+            db.save_test_coverage(&run_id, &test_coverage)?;
+            progress!(format, "{} Recorded the lines executed by {} test(s)", "📈".cyan(), test_coverage.len());
+        }
+
+        if let Some(policy) = db::QuarantinePolicy::from_config(&config.quarantine) {
+            for test in db.apply_quarantine_policy(&policy)? {
+                progress!(format, "{} {} is flaky and was quarantined", "◌".yellow(), test.label());
+                quarantined.insert(test.target.as_deref(), &test.test_name);
+            }
+        }
+
+        if result.cancelled {
+            db.finish_run_with_status(&run_id, "cancelled", result.passed as i32, result.failed as i32)?;
+        } else if result.timed_out {
+            db.finish_run_with_status(&run_id, "timed_out", result.passed as i32, result.failed as i32)?;
+        } else {
+            let quarantined_failed = result.quarantined_failures(&quarantined).len();
+            db.finish_run_quarantined(&run_id, result.passed as i32, result.failed as i32, quarantined_failed as i32)?;
+        }
+    }
+
+    let blocking = result.blocking_failures(&quarantined);
+    let run = RunReport { run_id: &run_id, project_name: &project_name, result: &result, quarantined: &quarantined };
+    print_run(format, &run, &regressions, coverage.as_ref(), coverage_threshold, verbose)?;
+
+    // Generate report if requested
+    if generate_report {
+        let report_path = project_dir.join("runx-report.html");
+        progress!(format, "{} Generating report...", "📊".cyan());
+
+        if let Some(ref db) = db {
+            if let Some(summary) = db.get_run_summary(&run_id)? {
+                let task_results: Vec<task::TaskResult> = summary.tasks.iter()
+                    .map(task::TaskResult::from_db)
+                    .collect();
+
+                let comparison = compare_runs(db, None, &run_id).ok();
+                let coverage = db.get_coverage(&run_id)?;
+                report::generate_report(&project_name, &task_results, comparison.as_ref(), coverage.as_ref(), &report_path)?;
+                progress!(format, "{} Report saved to {}\n", "✓".green(), report_path.display());
+            }
         }
-        db.finish_run(&run_id, result.passed as i32, result.failed as i32)?;
     }
 
-    // Print results
+    if let Some(ref path) = junit {
+        junit::JUnitReport::from_run_result(&project_name, run_started_at, &result).write(path)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+        progress!(format, "{} JUnit report saved to {}\n", "✓".green(), path.display());
+    }
+
+    if result.cancelled {
+        // Conventional exit status for SIGINT
+        std::process::exit(130);
+    }
+    if blocking > 0 || result.timed_out {
+        std::process::exit(1);
+    }
+    if fail_on_regression && !regressions.is_empty() {
+        progress!(format, "{} {} duration regression(s) (--fail-on-regression)\n", "✗".red(), regressions.len());
+        std::process::exit(1);
+    }
+    if let Some(threshold) = coverage_threshold {
+        // Coverage that could not be measured does not meet any threshold
+        let line_coverage = coverage.as_ref().map(|c| c.total.line_coverage()).unwrap_or(0.0);
+        if line_coverage < threshold {
+            progress!(format, "{} Line coverage {:.1}% is below the {}% threshold\n", "✗".red(), line_coverage, threshold);
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// A finished run, as printed at the end of `runx run`
+struct RunReport<'a> {
+    run_id: &'a str,
+    project_name: &'a str,
+    result: &'a test_runner::TestRunResult,
+    quarantined: &'a db::Quarantine,
+}
+
+/// Print a finished run in the requested format
+fn print_run(
+    format: CliFormat,
+    run: &RunReport,
+    regressions: &[DurationRegression],
+    coverage: Option<&coverage::CoverageReport>,
+    coverage_threshold: Option<f64>,
+    verbose: bool,
+) -> Result<()> {
+    let RunReport { run_id, project_name, result, quarantined } = *run;
+    match format {
+        CliFormat::Human => {
+            print_run_summary(result, quarantined, regressions, verbose);
+            if let Some(report) = coverage {
+                print_coverage(report, coverage_threshold, verbose);
+            }
+        }
+        CliFormat::Json => {
+            let run = output::RunOutput::new(run_id, project_name, result, quarantined, regressions)
+                .with_coverage(coverage);
+            output::print_document(format, &run)?;
+        }
+        CliFormat::Ndjson => {
+            let run = output::RunOutput::new(run_id, project_name, result, quarantined, regressions)
+                .with_coverage(coverage);
+            output::print_record(&output::EventRecord::Summary(&run))?;
+        }
+        CliFormat::Tap => println!("{}", output::tap(result, quarantined)),
+        CliFormat::Plain => {
+            for test in &result.test_results {
+                let target = test.target.as_ref().map(|t| t.qualified_label()).unwrap_or_default();
+                let duration = test.duration_ms.unwrap_or(0).to_string();
+                println!("{}", output::plain_line(&[test.status.as_db_str(), &test.name, &target, &duration]));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Line coverage of a run, with every file when verbose
+fn print_coverage(report: &coverage::CoverageReport, threshold: Option<f64>, verbose: bool) {
+    let total = &report.total;
+    let branches = match total.branch_coverage() {
+        Some(pct) => format!(", {:.1}% of branches", pct),
+        None => String::new(),
+    };
+    let verdict = match threshold {
+        Some(t) if total.meets(t) => format!(" {}", format!("(threshold {}%)", t).green()),
+        Some(t) => format!(" {}", format!("(below the {}% threshold)", t).red()),
+        None => String::new(),
+    };
+    println!("{} Coverage: {:.1}% of lines ({}/{}){}{}\n",
+        "📈".cyan(), total.line_coverage(), total.lines_covered, total.lines_total, branches, verdict);
+
+    if verbose {
+        let mut files: Vec<_> = report.files.iter().collect();
+        files.sort_by(|a, b| a.summary.line_coverage().total_cmp(&b.summary.line_coverage()));
+        for file in files {
+            println!("  {:>5.1}% {} {}", file.summary.line_coverage(), file.path,
+                format!("({}/{})", file.summary.lines_covered, file.summary.lines_total).dimmed());
+        }
+        println!();
+    }
+}
+
+/// Colored summary of a finished run
+fn print_run_summary(
+    result: &test_runner::TestRunResult,
+    quarantined: &db::Quarantine,
+    regressions: &[DurationRegression],
+    verbose: bool,
+) {
     println!("\n{}", "─".repeat(50).dimmed());
 
-    if result.failed > 0 {
+    let quarantined_failures = result.quarantined_failures(quarantined);
+    let blocking = result.blocking_failures(quarantined);
+    let quarantined_count = if quarantined_failures.is_empty() {
+        String::new()
+    } else {
+        format!(", {} quarantined", quarantined_failures.len().to_string().yellow())
+    };
+    let passed_on_retry = result.passed_on_retry();
+    let retried_count = if passed_on_retry.is_empty() {
+        String::new()
+    } else {
+        format!(" ({} on retry)", passed_on_retry.len().to_string().yellow())
+    };
+
+    if result.cancelled {
+        let in_flight: Vec<_> = result.test_results.iter()
+            .filter(|t| t.status == TestStatus::Cancelled)
+            .collect();
         println!(
-            "\n{} {} passed, {} failed, {} ignored\n",
-            "Results:".bold(),
+            "\n{} {} passed, {} failed, {} cancelled\n",
+            "⏹  Cancelled:".yellow().bold(),
             result.passed.to_string().green(),
             result.failed.to_string().red(),
+            in_flight.len().to_string().yellow()
+        );
+        for test in in_flight {
+            println!("  {} {}", "⊗".yellow(), test.name);
+        }
+    } else if blocking > 0 || result.timed_out {
+        if result.timed_out {
+            println!("\n{} Run timeout reached, remaining tests were not run", "⧗".red());
+        }
+        println!(
+            "\n{} {} passed{}, {} failed{}, {} ignored\n",
+            "Results:".bold(),
+            result.passed.to_string().green(),
+            retried_count,
+            blocking.to_string().red(),
+            quarantined_count,
             result.ignored.to_string().dimmed()
         );
 
         // Show failed tests
-        if verbose || result.failed <= 10 {
+        if blocking > 0 && (verbose || blocking <= 10) {
             println!("{}", "Failed tests:".red().bold());
             for test in &result.test_results {
-                if test.status == TestStatus::Failed {
-                    println!("  {} {}", "✗".red(), test.name);
+                if test.status.is_failure() && !test.is_quarantined(quarantined) {
+                    if test.status == TestStatus::TimedOut {
+                        println!("  {} {} {}", "⧗".red(), test.name, "(timed out)".dimmed());
+                    } else {
+                        println!("  {} {}", "✗".red(), test.name);
+                    }
                     if verbose && !test.output.is_empty() {
                         for line in &test.output {
                             println!("    {}", line.dimmed());
@@ -321,46 +793,92 @@ fn cmd_run(
         }
     } else {
         println!(
-            "\n{} {} passed, {} ignored\n",
+            "\n{} {} passed{}{}, {} ignored\n",
             "✓".green(),
             result.passed.to_string().green(),
+            retried_count,
+            quarantined_count,
             result.ignored.to_string().dimmed()
         );
     }
 
-    // Generate report if requested
-    if generate_report {
-        let report_path = project_dir.join("runx-report.html");
-        println!("{} Generating report...", "📊".cyan());
+    if !passed_on_retry.is_empty() && !result.cancelled {
+        println!("{}", "Passed on retry (flaky):".yellow().bold());
+        for test in &passed_on_retry {
+            println!("  {} {} {}", "↻".yellow(), test.name,
+                format!("(attempt {})", test.attempts.len() + 1).dimmed());
+            if verbose {
+                for line in test.attempts.iter().flat_map(|a| &a.output) {
+                    println!("    {}", line.dimmed());
+                }
+            }
+        }
+        println!();
+    }
 
-        if let Some(ref db) = db {
-            if let Some(summary) = db.get_run_summary(&run_id)? {
-                let task_results: Vec<task::TaskResult> = summary.tasks.iter().map(|t| {
-                    task::TaskResult {
-                        name: t.task_name.clone(),
-                        success: t.status == "passed",
-                        duration_ms: t.duration_ms as u128,
-                        category: t.category.clone(),
-                    }
-                }).collect();
+    if !regressions.is_empty() && !result.cancelled {
+        println!("{}", "Slower than their baseline:".yellow().bold());
+        for regression in regressions {
+            println!("  {} {}{} {}ms {}", "⚠".yellow(), regression.baseline.test_name,
+                baseline_target(&regression.baseline).dimmed(), regression.duration_ms,
+                format!("({:.1}× the median of {:.0}ms, p95 {:.0}ms)",
+                    regression.slowdown(), regression.baseline.median_ms, regression.baseline.p95_ms).dimmed());
+        }
+        println!();
+    }
 
-                report::generate_report(&project_name, &task_results, &report_path)?;
-                println!("{} Report saved to {}\n", "✓".green(), report_path.display());
+    if !quarantined_failures.is_empty() && !result.cancelled {
+        println!("{}", "Quarantined failures (not failing the run):".yellow().bold());
+        for test in &quarantined_failures {
+            println!("  {} {}", "◌".yellow(), test.name);
+            if verbose {
+                for line in &test.output {
+                    println!("    {}", line.dimmed());
+                }
             }
         }
+        println!();
     }
 
-    if result.failed > 0 {
-        std::process::exit(1);
+    if verbose && result.ignored > 0 {
+        println!("{}", "Ignored tests:".dimmed().bold());
+        for test in result.test_results.iter().filter(|t| t.status == TestStatus::Ignored) {
+            match test.ignore_reason {
+                Some(ref reason) => println!("  {} {} {}", "⊘".dimmed(), test.name, format!("({})", reason).dimmed()),
+                None => println!("  {} {}", "⊘".dimmed(), test.name),
+            }
+        }
+        println!();
+    }
+}
+
+/// Target of a baseline, as shown after its test name
+fn baseline_target(baseline: &DurationBaseline) -> String {
+    if baseline.classname.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", baseline.classname)
     }
+}
 
-    Ok(())
+/// Cancel the test session on the first Ctrl+C
+fn watch_ctrl_c(cancel: test_runner::CancelHandle) {
+    std::thread::spawn(move || {
+        let Ok(rt) = tokio::runtime::Builder::new_current_thread().enable_all().build() else {
+            return;
+        };
+        if rt.block_on(tokio::signal::ctrl_c()).is_ok() {
+            eprintln!("\n{} Cancelling test run...", "⏹".yellow());
+            cancel.cancel();
+        }
+    });
 }
 
-fn cmd_list(project_dir: &Path, filter: Option<String>, full: bool) -> Result<()> {
+fn cmd_list(project_dir: &Path, filter: Option<String>, full: bool, format: CliFormat) -> Result<()> {
+    format.ensure_not_tap("list")?;
     let project_name = get_project_name(project_dir)?;
 
-    println!("\n{} {}\n", "📦".cyan(), project_name.bold());
+    progress!(format, "\n{} {}\n", "📦".cyan(), project_name.bold());
 
     let tree = discover_all_tests(project_dir)?;
     let all_tests = tree.all_tests();
@@ -374,6 +892,23 @@ fn cmd_list(project_dir: &Path, filter: Option<String>, full: bool) -> Result<()
         all_tests
     };
 
+    match format {
+        CliFormat::Json => return output::print_document(format, &output::TestsOutput { tests: &tests }),
+        CliFormat::Ndjson => {
+            for test in &tests {
+                output::print_record(test)?;
+            }
+            return Ok(());
+        }
+        CliFormat::Plain => {
+            for test in &tests {
+                println!("{}", plain_test(test));
+            }
+            return Ok(());
+        }
+        CliFormat::Human | CliFormat::Tap => {}
+    }
+
     if tests.is_empty() {
         if filter.is_some() {
             println!("  {}", "No tests match the filter".dimmed());
@@ -389,26 +924,37 @@ fn cmd_list(project_dir: &Path, filter: Option<String>, full: bool) -> Result<()
     if full {
         for test in &tests {
             let status = test.status.symbol();
-            println!("  {} {}", status, test.full_name);
+            let location = test.location().map(|l| format!(" {}", l.dimmed())).unwrap_or_default();
+            match test.ignore_reason {
+                Some(ref reason) => println!("  {} {}{} {}", status, test.full_name, location, format!("({})", reason).dimmed()),
+                None => println!("  {} {}{}", status, test.full_name, location),
+            }
         }
     } else {
-        // Group by first module level
-        let mut current_module = String::new();
+        // Group by package target, or by first module level
+        let mut current_group = String::new();
         for test in &tests {
-            let module = test.module_path.first()
-                .map(|s| s.as_str())
-                .unwrap_or("(root)");
+            let (group, name) = match test.target {
+                Some(ref target) => (format!("{} › {}", target.package, target.label()), &test.full_name),
+                None => (
+                    test.module_path.first().cloned().unwrap_or_else(|| "(root)".to_string()),
+                    &test.short_name,
+                ),
+            };
 
-            if module != current_module {
-                if !current_module.is_empty() {
+            if group != current_group {
+                if !current_group.is_empty() {
                     println!();
                 }
-                println!("  {} {}", "▸".cyan(), module.bold());
-                current_module = module.to_string();
+                println!("  {} {}", "▸".cyan(), group.bold());
+                current_group = group;
             }
 
             let status = test.status.symbol();
-            println!("    {} {}", status, test.short_name);
+            match test.ignore_reason {
+                Some(ref reason) => println!("    {} {} {}", status, name, format!("({})", reason).dimmed()),
+                None => println!("    {} {}", status, name),
+            }
         }
     }
 
@@ -416,38 +962,83 @@ fn cmd_list(project_dir: &Path, filter: Option<String>, full: bool) -> Result<()
     Ok(())
 }
 
+/// `plain` line of a discovered test: name, target, location, ignore reason
+fn plain_test(test: &test_model::Test) -> String {
+    let target = test.target.as_ref().map(|t| t.qualified_label()).unwrap_or_default();
+    output::plain_line(&[
+        test.full_name.as_str(),
+        &target,
+        &test.location().unwrap_or_default(),
+        test.ignore_reason.as_deref().unwrap_or_default(),
+    ])
+}
+
 fn cmd_watch(project_dir: &Path, db_path: &Path, filter: Option<String>) -> Result<()> {
     let db = Database::open(db_path).ok();
     let mut watcher = TestWatcher::new(project_dir, filter, db);
     watcher.start()
 }
 
-fn cmd_discover(project_dir: &Path) -> Result<()> {
+fn cmd_discover(project_dir: &Path, format: CliFormat) -> Result<()> {
+    format.ensure_not_tap("discover")?;
     let project_name = get_project_name(project_dir)?;
 
-    println!("\n{} Discovering tests in {}...\n", "🔍".cyan(), project_name.cyan());
+    progress!(format, "\n{} Discovering tests in {}...\n", "🔍".cyan(), project_name.cyan());
 
     let tree = discover_all_tests(project_dir)?;
 
     let stats = test_model::TestStats::from_tree(&tree);
 
-    println!("{} {} test(s) discovered\n", "✓".green(), stats.total);
-
-    // Show summary by module
-    let mut modules: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    // Show summary by package and target
+    let mut packages: std::collections::BTreeMap<String, std::collections::BTreeMap<String, usize>> =
+        std::collections::BTreeMap::new();
     for test in tree.all_tests() {
-        let module = test.module_path.first().cloned()
-            .unwrap_or_else(|| "(root)".to_string());
-        *modules.entry(module).or_insert(0) += 1;
+        let path = test.tree_path();
+        let package = path.first().cloned().unwrap_or_else(|| "(root)".to_string());
+        let group = match test.target {
+            Some(ref target) => target.label(),
+            None => package.clone(),
+        };
+        *packages.entry(package).or_default().entry(group).or_insert(0) += 1;
+    }
+
+    match format {
+        CliFormat::Json => {
+            let tests = tree.all_tests();
+            return output::print_document(format, &output::DiscoveryOutput {
+                total: stats.total,
+                ignored: stats.ignored,
+                packages: &packages,
+                tests: &tests,
+            });
+        }
+        CliFormat::Ndjson => {
+            for test in tree.all_tests() {
+                output::print_record(test)?;
+            }
+            return Ok(());
+        }
+        CliFormat::Plain => {
+            for (package, targets) in &packages {
+                for (target, count) in targets {
+                    println!("{}", output::plain_line(&[package.as_str(), target, &count.to_string()]));
+                }
+            }
+            return Ok(());
+        }
+        CliFormat::Human | CliFormat::Tap => {}
     }
 
-    if !modules.is_empty() {
-        println!("{}", "Modules:".bold());
-        let mut sorted: Vec<_> = modules.into_iter().collect();
-        sorted.sort_by(|a, b| b.1.cmp(&a.1));
+    println!("{} {} test(s) discovered\n", "✓".green(), stats.total);
 
-        for (module, count) in sorted {
-            println!("  {} {} ({})", "▸".cyan(), module, count);
+    if !packages.is_empty() {
+        println!("{}", "Packages:".bold());
+        for (package, targets) in packages {
+            let total: usize = targets.values().sum();
+            println!("  {} {} ({})", "▸".cyan(), package.bold(), total);
+            for (target, count) in targets {
+                println!("      {} ({})", target, count);
+            }
         }
     }
 
@@ -484,57 +1075,231 @@ fn cmd_report(
     db_path: &Path,
     output: &Path,
     run_id: Option<String>,
+    compare: Option<String>,
 ) -> Result<()> {
     let db = Database::open(db_path)
         .context("No database found. Run some tests first with 'runx run'")?;
 
     let project_name = get_project_name(project_dir)?;
 
-    // Get the run to report on
-    let run_id = if let Some(id) = run_id {
-        id
-    } else {
-        // Get last run
-        let runs = db.get_recent_runs(1)?;
-        if runs.is_empty() {
-            anyhow::bail!("No runs found. Run some tests first with 'runx run'");
-        }
-        runs[0].id.clone()
-    };
+    // Get the run to report on (the last one by default)
+    let run_id = resolve_run(&db, run_id.as_deref())?;
 
     println!("{} Generating report for run {}...\n", "📊".cyan(), &run_id[..8]);
 
     let summary = db.get_run_summary(&run_id)?
         .context("Run not found")?;
 
-    let task_results: Vec<task::TaskResult> = summary.tasks.iter().map(|t| {
-        task::TaskResult {
-            name: t.task_name.clone(),
-            success: t.status == "passed",
-            duration_ms: t.duration_ms as u128,
-            category: t.category.clone(),
-        }
-    }).collect();
+    let task_results: Vec<task::TaskResult> = summary.tasks.iter()
+        .map(task::TaskResult::from_db)
+        .collect();
 
-    report::generate_report(&project_name, &task_results, output)?;
+    let comparison = match compare {
+        Some(base) => Some(compare_runs(&db, Some(&base), &run_id)?),
+        None => None,
+    };
+    let coverage = db.get_coverage(&run_id)?;
+    report::generate_report(&project_name, &task_results, comparison.as_ref(), coverage.as_ref(), output)?;
 
     println!("{} Report saved to {}\n", "✓".green(), output.display());
     Ok(())
 }
 
-fn cmd_history(db_path: &Path, limit: i32, clear: bool) -> Result<()> {
+/// Full id of a run given by id or prefix, or of the latest run
+fn resolve_run(db: &Database, id: Option<&str>) -> Result<String> {
+    match id {
+        Some(id) => db.resolve_run_id(id)?.with_context(|| format!("Run '{}' not found", id)),
+        None => db.get_recent_runs(1)?
+            .into_iter()
+            .next()
+            .map(|run| run.id)
+            .context("No runs found. Run some tests first with 'runx run'"),
+    }
+}
+
+fn cmd_import(db_path: &Path, file: &Path, source: Option<String>, format: Option<import::ImportFormat>) -> Result<()> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Could not read {}", file.display()))?;
+    let format = match format {
+        Some(format) => format,
+        None => import::ImportFormat::detect(&content)
+            .context("Unknown report format; pass --format junit, libtest-json or nextest-json")?,
+    };
+    let result = import::parse_report(&content, format)?;
+    if result.test_results.is_empty() {
+        anyhow::bail!("No test results found in {}", file.display());
+    }
+
+    let source = source.unwrap_or_else(|| {
+        file.file_name().map_or_else(|| file.display().to_string(), |name| name.to_string_lossy().into_owned())
+    });
+    let db = Database::open(db_path)?;
+    let run_id = Uuid::new_v4().to_string();
+    db.create_run(&run_id, result.total() as i32)?;
+    db.set_run_source(&run_id, &source)?;
+    db.save_test_results(&run_id, &result)?;
+    let quarantined = db.quarantined_tests()?;
+    let quarantined_failed = result.quarantined_failures(&quarantined).len();
+    db.finish_run_quarantined(&run_id, result.passed as i32, result.failed as i32, quarantined_failed as i32)?;
+
+    println!(
+        "\n{} Imported {} {} result(s) from {} as run {} {}",
+        "✓".green(),
+        result.total(),
+        format.label(),
+        file.display(),
+        &run_id[..8],
+        format!("[{}]", source).dimmed()
+    );
+    println!(
+        "  {} passed, {} failed, {} ignored\n",
+        result.passed.to_string().green(),
+        result.failed.to_string().red(),
+        result.ignored.to_string().yellow()
+    );
+    Ok(())
+}
+
+fn cmd_export(project_dir: &Path, db_path: &Path, format: ExportFormat) -> Result<()> {
+    let db = Database::open(db_path)
+        .context("No database found. Run some tests first with 'runx run'")?;
+    let project_name = get_project_name(project_dir)?;
+
+    match format {
+        ExportFormat::Junit { run, output } => {
+            let run_id = resolve_run(&db, run.as_deref())?;
+            let summary = db.get_run_summary(&run_id)?.context("Run not found")?;
+            let report = junit::JUnitReport::from_run_summary(&project_name, &summary);
+            match output {
+                Some(path) => {
+                    report.write(&path).with_context(|| format!("Could not write {}", path.display()))?;
+                    eprintln!("{} JUnit report for run {} saved to {}", "✓".green(), &run_id[..8], path.display());
+                }
+                None => println!("{}", report.to_xml()?),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compare `base` with `head`, the run preceding `head` by default
+fn compare_runs(db: &Database, base: Option<&str>, head: &str) -> Result<compare::RunComparison> {
+    let base = match base {
+        Some(id) => resolve_run(db, Some(id))?,
+        None => db.previous_run_id(head)?.context("No earlier run to compare with")?,
+    };
+    let base = db.get_run_summary(&base)?.context("Run not found")?;
+    let head = db.get_run_summary(head)?.context("Run not found")?;
+    Ok(compare::RunComparison::new(&base, &head))
+}
+
+fn cmd_diff(db_path: &Path, base: Option<String>, head: Option<String>) -> Result<()> {
+    let db = Database::open(db_path)
+        .context("No database found. Run some tests first with 'runx run'")?;
+
+    let head = resolve_run(&db, head.as_deref())?;
+    let comparison = compare_runs(&db, base.as_deref(), &head)?;
+
+    println!("\n{} Comparing {} → {}\n", "🔀".cyan(), &comparison.base[..8], &comparison.head[..8]);
+    if comparison.is_empty() {
+        println!("{}\n", "No changes between the two runs".dimmed());
+        return Ok(());
+    }
+
+    let section = |title: &str, count: usize| {
+        println!("{} ({})", title.bold(), count);
+    };
+    if !comparison.newly_failing.is_empty() {
+        section("Newly failing", comparison.newly_failing.len());
+        for change in &comparison.newly_failing {
+            println!("  {} {} {}", "✗".red(), change.label(), format!("({} → {})", change.before, change.after).dimmed());
+            if let Some(message) = change.error_message.as_ref().and_then(|m| m.lines().next()) {
+                println!("    {}", message.red());
+            }
+        }
+        println!();
+    }
+    if !comparison.newly_passing.is_empty() {
+        section("Newly passing", comparison.newly_passing.len());
+        for change in &comparison.newly_passing {
+            println!("  {} {} {}", "✓".green(), change.label(), format!("({} → {})", change.before, change.after).dimmed());
+        }
+        println!();
+    }
+    if !comparison.added.is_empty() {
+        section("Added", comparison.added.len());
+        for test in &comparison.added {
+            println!("  {} {}", "+".green(), test.label());
+        }
+        println!();
+    }
+    if !comparison.removed.is_empty() {
+        section("Removed", comparison.removed.len());
+        for test in &comparison.removed {
+            println!("  {} {}", "-".red(), test.label());
+        }
+        println!();
+    }
+    for (title, changes, icon) in [
+        ("Slower", &comparison.slower, "▲".red()),
+        ("Faster", &comparison.faster, "▼".green()),
+    ] {
+        if changes.is_empty() {
+            continue;
+        }
+        section(title, changes.len());
+        for change in changes {
+            println!("  {} {} {}ms → {}ms {}", icon, change.label(), change.before_ms, change.after_ms,
+                format!("({:+.0}%)", change.change_pct()).dimmed());
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn cmd_history(db_path: &Path, limit: i32, clear: bool, format: CliFormat) -> Result<()> {
+    format.ensure_not_tap("history")?;
     let db = Database::open(db_path)
         .context("No database found. Run some tests first with 'runx run'")?;
 
     if clear {
-        println!("{} Clearing all history...", "🗑".yellow());
+        progress!(format, "{} Clearing all history...", "🗑".yellow());
         let deleted = db.clear_all_history()?;
-        println!("{} Deleted {} records\n", "✓".green(), deleted);
+        progress!(format, "{} Deleted {} records\n", "✓".green(), deleted);
         return Ok(());
     }
 
     let runs = db.get_recent_runs(limit)?;
 
+    match format {
+        CliFormat::Json => return output::print_document(format, &output::RunsOutput { runs: &runs }),
+        CliFormat::Ndjson => {
+            for run in &runs {
+                output::print_record(run)?;
+            }
+            return Ok(());
+        }
+        CliFormat::Plain => {
+            for run in &runs {
+                let duration = run.finished_at
+                    .map(|f| (f - run.started_at).num_milliseconds())
+                    .unwrap_or(0);
+                println!("{}", output::plain_line(&[
+                    run.id.as_str(),
+                    &run.status,
+                    &run.passed.to_string(),
+                    &run.failed.to_string(),
+                    &duration.to_string(),
+                    &run.started_at.to_rfc3339(),
+                    run.source.as_deref().unwrap_or_default(),
+                ]));
+            }
+            return Ok(());
+        }
+        CliFormat::Human | CliFormat::Tap => {}
+    }
+
     if runs.is_empty() {
         println!("{}", "No runs found. Run some tests first with 'runx run'".dimmed());
         return Ok(());
@@ -549,14 +1314,20 @@ fn cmd_history(db_path: &Path, limit: i32, clear: bool) -> Result<()> {
             .map(|f| (f - run.started_at).num_milliseconds())
             .unwrap_or(0);
 
+        let source = match run.source {
+            Some(ref source) => format!(" │ {}", format!("imported from {}", source).dimmed()),
+            None => String::new(),
+        };
+
         println!(
-            "{} {} │ {} passed, {} failed │ {}ms │ {}",
+            "{} {} │ {} passed, {} failed │ {}ms │ {}{}",
             status_icon,
             &run.id[..8],
             run.passed.to_string().green(),
             run.failed.to_string().red(),
             duration,
-            run.started_at.format("%Y-%m-%d %H:%M:%S")
+            run.started_at.format("%Y-%m-%d %H:%M:%S"),
+            source
         );
     }
 
@@ -566,11 +1337,173 @@ fn cmd_history(db_path: &Path, limit: i32, clear: bool) -> Result<()> {
     Ok(())
 }
 
-fn cmd_stats(db_path: &Path, show_flaky: bool) -> Result<()> {
+fn cmd_test_history(db_path: &Path, pattern: &str, limit: i32, format: CliFormat) -> Result<()> {
+    format.ensure_not_tap("history")?;
+    let db = Database::open(db_path)
+        .context("No database found. Run some tests first with 'runx run'")?;
+
+    let names = db.find_test_names(pattern)?;
+    let test_name = match names.as_slice() {
+        [] if !format.is_human() => anyhow::bail!("No recorded executions of '{}'", pattern),
+        [_, _, ..] if !format.is_human() => {
+            anyhow::bail!("'{}' matches {} tests: {}", pattern, names.len(), names.join(", "))
+        }
+        [] => {
+            println!("{}", format!("No recorded executions of '{}'", pattern).dimmed());
+            return Ok(());
+        }
+        [name] => name,
+        _ => {
+            println!("\n{} '{}' matches {} tests:\n", "→".blue(), pattern, names.len());
+            for name in &names {
+                println!("  {}", name);
+            }
+            println!();
+            return Ok(());
+        }
+    };
+
+    let executions = db.get_test_history(test_name, limit)?;
+    match format {
+        CliFormat::Json => {
+            return output::print_document(format, &output::TestHistoryOutput { test_name, executions: &executions });
+        }
+        CliFormat::Ndjson => {
+            for execution in &executions {
+                output::print_record(&output::ExecutionRecord { test_name, execution })?;
+            }
+            return Ok(());
+        }
+        CliFormat::Plain => {
+            for execution in &executions {
+                println!("{}", output::plain_line(&[
+                    execution.run_id.as_str(),
+                    &execution.classname,
+                    &execution.status,
+                    &execution.duration_ms.to_string(),
+                    &execution.created_at.to_rfc3339(),
+                ]));
+            }
+            return Ok(());
+        }
+        CliFormat::Human | CliFormat::Tap => {}
+    }
+
+    println!("\n{} {} (last {})\n", "📜".cyan(), test_name.bold(), executions.len());
+
+    for (target, runs) in db::by_target(executions.clone()) {
+        if !target.is_empty() {
+            println!("  {}", target.bold());
+        }
+        let durations: Vec<u64> = runs.iter().map(|e| e.duration_ms.max(0) as u64).collect();
+        println!("  {}  {}", "Outcome ".dimmed(), trend::outcome_strip(runs.iter().map(|e| e.status.as_str())));
+        println!("  {}  {}", "Duration".dimmed(), trend::sparkline(&durations).cyan());
+        let passed = runs.iter().filter(|e| e.passed()).count();
+        println!("  {}  {}/{} passed", "Passes  ".dimmed(), passed, runs.len());
+        if let Some(first) = db::failing_since(&runs) {
+            println!("  {} Failing since run {} ({})",
+                "✗".red(), &first.run_id[..8.min(first.run_id.len())],
+                first.created_at.format("%Y-%m-%d %H:%M:%S"));
+        }
+        println!();
+    }
+
+    println!("{}", "─".repeat(70).dimmed());
+    for execution in executions.iter().rev() {
+        let icon = if execution.status == db::PASSED_ON_RETRY {
+            "↻".yellow()
+        } else if execution.passed() {
+            "✓".green()
+        } else if execution.failed() {
+            "✗".red()
+        } else {
+            "⊘".dimmed()
+        };
+        let target = if execution.classname.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", execution.classname)
+        };
+        println!(
+            "{} {} │ {:>6}ms │ {}{}",
+            icon,
+            &execution.run_id[..8.min(execution.run_id.len())],
+            execution.duration_ms,
+            execution.created_at.format("%Y-%m-%d %H:%M:%S"),
+            target.dimmed()
+        );
+        if let Some(ref failure) = execution.failure {
+            println!("    {}", failure.red());
+        }
+    }
+    println!("{}", "─".repeat(70).dimmed());
+    println!();
+
+    Ok(())
+}
+
+fn cmd_quarantine(db_path: &Path, action: QuarantineAction) -> Result<()> {
+    let db = Database::open(db_path)?;
+
+    match action {
+        QuarantineAction::Add { test, target } => {
+            if db.quarantine_test(&test, target.as_deref())? {
+                println!("{} {} quarantined: its failures no longer fail runs", "◌".yellow(), test.bold());
+            } else {
+                println!("{} {} is already quarantined", "◌".dimmed(), test);
+            }
+        }
+        QuarantineAction::Remove { test, target } => {
+            if db.release_test(&test, target.as_deref())? {
+                println!("{} {} removed from quarantine", "✓".green(), test.bold());
+            } else {
+                println!("{} {} is not quarantined", "•".dimmed(), test);
+            }
+        }
+        QuarantineAction::List => {
+            let tests = db.get_quarantined_tests()?;
+            if tests.is_empty() {
+                println!("{}", "No quarantined tests".dimmed());
+            } else {
+                println!("\n{} Quarantined tests\n", "◌".yellow());
+                for test in &tests {
+                    let origin = if test.auto { "auto" } else { "manual" };
+                    println!(
+                        "  {} {} {}",
+                        "◌".yellow(),
+                        test.label(),
+                        format!("({}, flaky score {:.0} over {} runs)", origin, test.flaky_score, test.total_runs).dimmed()
+                    );
+                }
+                println!();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_stats(db_path: &Path, show_flaky: bool, show_slow: bool, format: CliFormat) -> Result<()> {
+    format.ensure_not_tap("stats")?;
     let db = Database::open(db_path)
         .context("No database found. Run some tests first with 'runx run'")?;
 
     let stats = db.get_dashboard_stats()?;
+    if !format.is_human() {
+        let flaky = if show_flaky { Some(db.get_flaky_tests(10)?) } else { None };
+        let regressions = match db.get_recent_runs(1)?.first() {
+            Some(run) if show_slow => Some(db.find_regressions(&run.id)?),
+            _ if show_slow => Some(Vec::new()),
+            _ => None,
+        };
+        let baselines = if show_slow { Some(db.get_duration_baselines(10)?) } else { None };
+        return print_stats(format, &output::StatsOutput {
+            stats: &stats,
+            flaky: flaky.as_deref(),
+            regressions: regressions.as_deref(),
+            baselines: baselines.as_deref(),
+        });
+    }
 
     println!("\n{} Runx Statistics\n", "📊".cyan());
     println!("{}", "─".repeat(50).dimmed());
@@ -597,12 +1530,25 @@ fn cmd_stats(db_path: &Path, show_flaky: bool) -> Result<()> {
                 } else {
                     0.0
                 };
+                let retries = if test.retry_passes > 0 {
+                    format!(", passed on retry {}x", test.retry_passes)
+                } else {
+                    String::new()
+                };
+                let target = if test.classname.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", test.classname)
+                };
                 println!(
-                    "  {} {} ({:.0}% failure rate, {} runs)",
+                    "  {} {}{} (flaky score {:.0}, {:.0}% failure rate over {} runs{})",
                     "•".yellow(),
                     test.test_name,
+                    target.dimmed(),
+                    test.flaky_score,
                     flaky_pct,
-                    test.total_runs
+                    test.total_runs,
+                    retries
                 );
             }
         }
@@ -610,6 +1556,46 @@ fn cmd_stats(db_path: &Path, show_flaky: bool) -> Result<()> {
         println!();
     }
 
+    if show_slow {
+        if let Some(run) = db.get_recent_runs(1)?.first() {
+            let regressions = db.find_regressions(&run.id)?;
+            println!("\n{} Duration Regressions (run {})\n", "⚠".yellow(), &run.id[..8]);
+            if regressions.is_empty() {
+                println!("  {}", "No test was slower than its baseline".dimmed());
+            }
+            for regression in &regressions {
+                println!(
+                    "  {} {}{} {}ms {}",
+                    "▲".red(),
+                    regression.baseline.test_name,
+                    baseline_target(&regression.baseline).dimmed(),
+                    regression.duration_ms,
+                    format!("({:.1}× the median of {:.0}ms)", regression.slowdown(), regression.baseline.median_ms).dimmed()
+                );
+            }
+        }
+
+        println!("\n{} Slowest Tests (last passing runs)\n", "🐢".cyan());
+        let baselines = db.get_duration_baselines(10)?;
+        if baselines.is_empty() {
+            println!("  {}", "Not enough passing runs to compute baselines".dimmed());
+        }
+        for baseline in &baselines {
+            println!(
+                "  {} {}{} median {:.0}ms, p95 {:.0}ms, MAD {:.0}ms {}",
+                "•".blue(),
+                baseline.test_name,
+                baseline_target(baseline).dimmed(),
+                baseline.median_ms,
+                baseline.p95_ms,
+                baseline.mad_ms,
+                format!("({} runs)", baseline.samples).dimmed()
+            );
+        }
+
+        println!();
+    }
+
     // Pass rate history
     if !stats.pass_rate_history.is_empty() {
         println!("\n{} Pass Rate Trend (7 days)\n", "📈".cyan());
@@ -630,3 +1616,167 @@ fn cmd_stats(db_path: &Path, show_flaky: bool) -> Result<()> {
 
     Ok(())
 }
+
+/// Statistics as JSON or NDJSON, or as `plain` lines: a "key value" line per
+/// figure, then one line per flaky test, regression and baseline, prefixed
+/// with its kind
+fn print_stats(format: CliFormat, output: &output::StatsOutput) -> Result<()> {
+    if format != CliFormat::Plain {
+        return output::print_document(format, output);
+    }
+
+    let stats = output.stats;
+    println!("{}", output::plain_line(&["total_runs", &stats.total_runs.to_string()]));
+    println!("{}", output::plain_line(&["total_tasks_executed", &stats.total_tasks_executed.to_string()]));
+    println!("{}", output::plain_line(&["overall_pass_rate", &format!("{:.1}", stats.overall_pass_rate)]));
+    println!("{}", output::plain_line(&["avg_duration_ms", &stats.avg_duration_ms.to_string()]));
+    for test in output.flaky.unwrap_or_default() {
+        println!("{}", output::plain_line(&[
+            "flaky",
+            test.test_name.as_str(),
+            &test.classname,
+            &format!("{:.0}", test.flaky_score),
+            &test.fail_count.to_string(),
+            &test.total_runs.to_string(),
+        ]));
+    }
+    for regression in output.regressions.unwrap_or_default() {
+        println!("{}", output::plain_line(&[
+            "regression",
+            regression.baseline.test_name.as_str(),
+            &regression.baseline.classname,
+            &regression.duration_ms.to_string(),
+            &format!("{:.0}", regression.baseline.median_ms),
+        ]));
+    }
+    for baseline in output.baselines.unwrap_or_default() {
+        println!("{}", output::plain_line(&[
+            "baseline",
+            baseline.test_name.as_str(),
+            &baseline.classname,
+            &format!("{:.0}", baseline.median_ms),
+            &format!("{:.0}", baseline.p95_ms),
+            &format!("{:.0}", baseline.mad_ms),
+            &baseline.samples.to_string(),
+        ]));
+    }
+    Ok(())
+}
+
+fn cmd_tasks(
+    project_dir: &Path,
+    db_path: &Path,
+    names: &[String],
+    list: bool,
+    generate_report: bool,
+) -> Result<()> {
+    let config = RunxConfig::load(project_dir)?;
+    let graph = task::TaskGraph::new(config.tasks)?;
+
+    if graph.is_empty() {
+        println!("{}", format!("No [tasks] defined in {}", config::CONFIG_FILE).dimmed());
+        return Ok(());
+    }
+
+    let selected = graph.select(names)?;
+
+    if list {
+        println!("\n{} Tasks ({})\n", "📋".cyan(), config::CONFIG_FILE);
+        for (i, name) in selected.iter().enumerate() {
+            let Some(task) = graph.get(name) else { continue };
+            let category = task.category.as_deref()
+                .map(|c| format!(" [{}]", c))
+                .unwrap_or_default();
+            let deps = if task.depends_on.is_empty() {
+                String::new()
+            } else {
+                format!(" ← {}", task.depends_on.join(", "))
+            };
+            println!("  {:>2}. {}{}{}", i + 1, name.bold(), category.dimmed(), deps.dimmed());
+            println!("      {}", task.cmd.dimmed());
+        }
+        println!();
+        return Ok(());
+    }
+
+    let project_name = config.project.name.clone()
+        .map(Ok)
+        .unwrap_or_else(|| get_project_name(project_dir))?;
+    let db = Database::open(db_path).ok();
+
+    println!("\n{} {} {}\n", "⚙".cyan(), "Running tasks for".bold(), project_name.cyan());
+
+    let run_id = Uuid::new_v4().to_string();
+    if let Some(ref db) = db {
+        db.create_run(&run_id, selected.len() as i32)?;
+    }
+
+    let pipeline = task::TaskPipeline::new(project_dir, &graph);
+    let runs = pipeline.run(&selected, |event| match event {
+        task::TaskEvent::Started { name } => {
+            println!("  {} {}", "▶".blue(), name);
+        }
+        task::TaskEvent::Finished(run) => match run.status {
+            task::TaskStatus::Passed => {
+                println!("  {} {} {}", "✓".green(), run.name, format!("({}ms)", run.duration_ms).dimmed());
+            }
+            task::TaskStatus::Failed => {
+                println!("  {} {} {}", "✗".red(), run.name, format!("({}ms)", run.duration_ms).dimmed());
+                for line in &run.output {
+                    println!("    {}", line.dimmed());
+                }
+            }
+            task::TaskStatus::Skipped => {
+                println!("  {} {} {}", "⊘".dimmed(), run.name, "(skipped: dependency failed)".dimmed());
+            }
+        },
+    })?;
+
+    let passed = runs.iter().filter(|r| r.status == task::TaskStatus::Passed).count();
+    let failed = runs.iter().filter(|r| r.status == task::TaskStatus::Failed).count();
+    let skipped = runs.len() - passed - failed;
+
+    if let Some(ref db) = db {
+        for run in &runs {
+            db.insert_task_result(&db::TaskResult {
+                id: Uuid::new_v4().to_string(),
+                run_id: run_id.clone(),
+                task_name: run.name.clone(),
+                category: run.category.clone(),
+                status: run.status.as_str().to_string(),
+                duration_ms: run.duration_ms as i64,
+                started_at: run.started_at,
+                output: if run.output.is_empty() { None } else { Some(run.output.join("\n")) },
+                ignore_reason: None,
+                error_message: None,
+                error_type: None,
+                retries: 0,
+                target: None,
+                classname: None,
+            })?;
+        }
+        db.finish_run(&run_id, passed as i32, failed as i32)?;
+    }
+
+    println!("\n{}", "─".repeat(50).dimmed());
+    println!(
+        "\n{} {} passed, {} failed, {} skipped\n",
+        "Tasks:".bold(),
+        passed.to_string().green(),
+        failed.to_string().red(),
+        skipped.to_string().dimmed()
+    );
+
+    if generate_report {
+        let report_path = project_dir.join("runx-report.html");
+        let task_results: Vec<task::TaskResult> = runs.iter().map(|r| r.to_result()).collect();
+        report::generate_report(&project_name, &task_results, None, None, &report_path)?;
+        println!("{} Report saved to {}\n", "✓".green(), report_path.display());
+    }
+
+    if failed > 0 || skipped > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,393 @@
+//! Machine-readable CLI output
+//!
+//! `--format json|ndjson|tap|plain` replaces the colored text of `run`,
+//! `list`, `discover`, `history` and `stats`. JSON documents and NDJSON
+//! records carry `"version": OUTPUT_VERSION`; fields may be added within a
+//! version, but are only renamed or removed with a new one.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::coverage::CoverageReport;
+use crate::db::{DashboardStats, DurationBaseline, DurationRegression, FlakyTest, Quarantine, Run, TestExecution, PASSED_ON_RETRY};
+use crate::editor::Location;
+use crate::test_model::{Test, TestStatus};
+use crate::test_runner::{SingleTestResult, TestEvent, TestRunResult};
+
+/// Version of the JSON, NDJSON and plain outputs
+pub const OUTPUT_VERSION: u32 = 1;
+
+/// Output format of a CLI command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum CliFormat {
+    /// Colored text for humans
+    #[default]
+    Human,
+    /// One JSON document
+    Json,
+    /// One JSON object per line (per event for `runx run`)
+    Ndjson,
+    /// Test Anything Protocol (`runx run` only)
+    Tap,
+    /// Tab-separated fields, one record per line
+    Plain,
+}
+
+impl CliFormat {
+    pub fn is_human(&self) -> bool {
+        *self == CliFormat::Human
+    }
+
+    /// Fail for TAP, which only describes test runs
+    pub fn ensure_not_tap(&self, command: &str) -> Result<()> {
+        if *self == CliFormat::Tap {
+            anyhow::bail!("--format tap is only supported by 'runx run', not 'runx {}'", command);
+        }
+        Ok(())
+    }
+}
+
+/// A document or record with the output version
+#[derive(Serialize)]
+struct Versioned<'a, T: Serialize> {
+    version: u32,
+    #[serde(flatten)]
+    body: &'a T,
+}
+
+/// Print a whole document: indented for `json`, on one line otherwise
+pub fn print_document<T: Serialize>(format: CliFormat, document: &T) -> Result<()> {
+    let versioned = Versioned { version: OUTPUT_VERSION, body: document };
+    if format == CliFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&versioned)?);
+    } else {
+        println!("{}", serde_json::to_string(&versioned)?);
+    }
+    Ok(())
+}
+
+/// Print one NDJSON record
+pub fn print_record<T: Serialize>(record: &T) -> Result<()> {
+    println!("{}", serde_json::to_string(&Versioned { version: OUTPUT_VERSION, body: record })?);
+    Ok(())
+}
+
+/// One `plain` line: fields separated by tabs, which are replaced (like
+/// newlines) by spaces inside fields
+pub fn plain_line<S: AsRef<str>>(fields: &[S]) -> String {
+    fields.iter()
+        .map(|f| f.as_ref().replace(['\t', '\n', '\r'], " "))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// A runner event, as streamed by `runx run --format ndjson`
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventRecord<'a> {
+    Started { test_name: &'a str },
+    Output { test_name: &'a str, line: &'a str },
+    Slow { test_name: &'a str, elapsed_ms: u64 },
+    Completed { test_name: &'a str, status: &'static str, duration_ms: u64 },
+    AllCompleted { passed: usize, failed: usize, ignored: usize },
+    Error { message: &'a str },
+    /// Final record, with the whole run
+    Summary(&'a RunOutput<'a>),
+}
+
+impl<'a> From<&'a TestEvent> for EventRecord<'a> {
+    fn from(event: &'a TestEvent) -> Self {
+        match event {
+            TestEvent::Started { test_name } => EventRecord::Started { test_name },
+            TestEvent::Output { test_name, line } => EventRecord::Output { test_name, line },
+            TestEvent::Slow { test_name, elapsed_ms } => EventRecord::Slow { test_name, elapsed_ms: *elapsed_ms },
+            TestEvent::Completed { test_name, status, duration_ms } => EventRecord::Completed {
+                test_name,
+                status: status.as_db_str(),
+                duration_ms: *duration_ms,
+            },
+            TestEvent::AllCompleted { passed, failed, ignored } => EventRecord::AllCompleted {
+                passed: *passed,
+                failed: *failed,
+                ignored: *ignored,
+            },
+            TestEvent::Error { message } => EventRecord::Error { message },
+        }
+    }
+}
+
+/// A finished run
+#[derive(Serialize)]
+pub struct RunOutput<'a> {
+    pub run_id: &'a str,
+    pub project: &'a str,
+    /// Whether the run passes: no failure outside quarantine, no timeout
+    pub success: bool,
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub duration_ms: u64,
+    pub cancelled: bool,
+    pub timed_out: bool,
+    pub tests: Vec<TestOutput<'a>>,
+    pub regressions: &'a [DurationRegression],
+    /// Line coverage, with `--coverage`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<&'a CoverageReport>,
+}
+
+/// A test of a finished run
+#[derive(Serialize)]
+pub struct TestOutput<'a> {
+    pub name: &'a str,
+    /// Target label, e.g. "core (lib)"
+    pub target: Option<String>,
+    /// "passed", "passed_on_retry", "failed", "timed_out", "skipped" or "cancelled"
+    pub status: &'static str,
+    pub duration_ms: Option<u64>,
+    pub ignore_reason: Option<&'a str>,
+    pub quarantined: bool,
+    /// Times the test was run again after failing
+    pub retries: usize,
+    pub failure: Option<FailureOutput<'a>>,
+    pub output: &'a [String],
+}
+
+/// The panic of a failed test
+#[derive(Serialize)]
+pub struct FailureOutput<'a> {
+    /// "assert_eq", "assert_ne", "assert" or "panic"
+    pub kind: &'static str,
+    pub message: &'a str,
+    /// "file:line:column" of the panic
+    pub location: Option<String>,
+}
+
+impl<'a> RunOutput<'a> {
+    pub fn new(run_id: &'a str, project: &'a str, result: &'a TestRunResult, quarantined: &Quarantine, regressions: &'a [DurationRegression]) -> Self {
+        Self {
+            run_id,
+            project,
+            success: result.blocking_failures(quarantined) == 0 && !result.timed_out && !result.cancelled,
+            passed: result.passed,
+            failed: result.failed,
+            ignored: result.ignored,
+            duration_ms: result.duration_ms,
+            cancelled: result.cancelled,
+            timed_out: result.timed_out,
+            tests: result.test_results.iter().map(|test| TestOutput {
+                name: &test.name,
+                target: test.target.as_ref().map(|t| t.qualified_label()),
+                status: if test.passed_on_retry() { PASSED_ON_RETRY } else { test.status.as_db_str() },
+                duration_ms: test.duration_ms,
+                ignore_reason: test.ignore_reason.as_deref(),
+                quarantined: test.is_quarantined(quarantined),
+                retries: test.attempts.len(),
+                failure: test.failure.as_ref().map(|f| FailureOutput {
+                    kind: f.kind.label(),
+                    message: &f.message,
+                    location: f.location.as_ref().map(spot),
+                }),
+                output: &test.output,
+            }).collect(),
+            regressions,
+            coverage: None,
+        }
+    }
+
+    pub fn with_coverage(mut self, coverage: Option<&'a CoverageReport>) -> Self {
+        self.coverage = coverage;
+        self
+    }
+}
+
+/// Discovered tests (`runx list`)
+#[derive(Serialize)]
+pub struct TestsOutput<'a> {
+    pub tests: &'a [&'a Test],
+}
+
+/// Discovered tests with their count per package and target (`runx discover`)
+#[derive(Serialize)]
+pub struct DiscoveryOutput<'a> {
+    pub total: usize,
+    pub ignored: usize,
+    /// Package → target label → number of tests
+    pub packages: &'a BTreeMap<String, BTreeMap<String, usize>>,
+    pub tests: &'a [&'a Test],
+}
+
+/// Recent runs (`runx history`)
+#[derive(Serialize)]
+pub struct RunsOutput<'a> {
+    pub runs: &'a [Run],
+}
+
+/// Recorded executions of a test, oldest first (`runx history <test>`)
+#[derive(Serialize)]
+pub struct TestHistoryOutput<'a> {
+    pub test_name: &'a str,
+    pub executions: &'a [TestExecution],
+}
+
+/// An execution of a test, as an NDJSON record
+#[derive(Serialize)]
+pub struct ExecutionRecord<'a> {
+    pub test_name: &'a str,
+    #[serde(flatten)]
+    pub execution: &'a TestExecution,
+}
+
+/// Statistics (`runx stats`), with the sections that were asked for
+#[derive(Serialize)]
+pub struct StatsOutput<'a> {
+    #[serde(flatten)]
+    pub stats: &'a DashboardStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flaky: Option<&'a [FlakyTest]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regressions: Option<&'a [DurationRegression]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baselines: Option<&'a [DurationBaseline]>,
+}
+
+/// A run in TAP version 14. Quarantined failures are marked `# TODO`, so
+/// that they do not fail the run for TAP consumers either.
+pub fn tap(result: &TestRunResult, quarantined: &Quarantine) -> String {
+    let mut lines = vec!["TAP version 14".to_string(), format!("1..{}", result.test_results.len())];
+    for (i, test) in result.test_results.iter().enumerate() {
+        lines.push(tap_line(i + 1, test, test.is_quarantined(quarantined)));
+        if test.status.is_failure() {
+            lines.push("  ---".to_string());
+            let message = match test.failure {
+                Some(ref failure) => failure.message.clone(),
+                None => test.output.join("\n"),
+            };
+            lines.push(format!("  message: {}", serde_json::to_string(&message).unwrap_or_default()));
+            lines.push(format!("  severity: {}", if test.status == TestStatus::TimedOut { "timeout" } else { "fail" }));
+            if let Some(location) = test.failure.as_ref().and_then(|f| f.location.as_ref()) {
+                lines.push(format!("  at: {}", serde_json::to_string(&spot(location)).unwrap_or_default()));
+            }
+            if let Some(ms) = test.duration_ms {
+                lines.push(format!("  duration_ms: {}", ms));
+            }
+            lines.push("  ...".to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// "file:line:column" of a location
+fn spot(location: &Location) -> String {
+    match location.column {
+        Some(column) => format!("{}:{}:{}", location.file.display(), location.line, column),
+        None => format!("{}:{}", location.file.display(), location.line),
+    }
+}
+
+fn tap_line(number: usize, test: &SingleTestResult, quarantined: bool) -> String {
+    let name = match test.target {
+        Some(ref target) => format!("{} [{}]", test.name, target.qualified_label()),
+        None => test.name.clone(),
+    };
+    // '#' starts a directive in TAP
+    let name = name.replace('#', "\\#");
+    match test.status {
+        TestStatus::Ignored => match test.ignore_reason {
+            Some(ref reason) => format!("ok {} - {} # SKIP {}", number, name, reason),
+            None => format!("ok {} - {} # SKIP", number, name),
+        },
+        TestStatus::Cancelled => format!("not ok {} - {} # SKIP cancelled", number, name),
+        status if status.is_failure() && quarantined => format!("not ok {} - {} # TODO quarantined", number, name),
+        status if status.is_failure() => format!("not ok {} - {}", number, name),
+        _ => format!("ok {} - {}", number, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::failure::TestFailure;
+
+    fn test(name: &str, status: TestStatus) -> SingleTestResult {
+        SingleTestResult {
+            name: name.to_string(),
+            status,
+            duration_ms: Some(3),
+            output: Vec::new(),
+            ignore_reason: (status == TestStatus::Ignored).then(|| "needs network".to_string()),
+            failure: (status == TestStatus::Failed).then(|| TestFailure::from_message("boom")),
+            target: None,
+            attempts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_tap() {
+        let result = TestRunResult {
+            test_results: vec![
+                test("a::ok", TestStatus::Passed),
+                test("a::fails", TestStatus::Failed),
+                test("a::flaky", TestStatus::Failed),
+                test("a::network", TestStatus::Ignored),
+            ],
+            ..Default::default()
+        };
+        let mut quarantined = Quarantine::default();
+        quarantined.insert(None, "a::flaky");
+
+        assert_eq!(tap(&result, &quarantined), [
+            "TAP version 14",
+            "1..4",
+            "ok 1 - a::ok",
+            "not ok 2 - a::fails",
+            "  ---",
+            "  message: \"boom\"",
+            "  severity: fail",
+            "  duration_ms: 3",
+            "  ...",
+            "not ok 3 - a::flaky # TODO quarantined",
+            "  ---",
+            "  message: \"boom\"",
+            "  severity: fail",
+            "  duration_ms: 3",
+            "  ...",
+            "ok 4 - a::network # SKIP needs network",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_versioned_records() {
+        let event = TestEvent::Completed { test_name: "a::ok".to_string(), status: TestStatus::Passed, duration_ms: 3 };
+        let record = Versioned { version: OUTPUT_VERSION, body: &EventRecord::from(&event) };
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"version":1,"type":"completed","test_name":"a::ok","status":"passed","duration_ms":3}"#
+        );
+        assert_eq!(plain_line(&["a\tb", "c\nd"]), "a b\tc d");
+    }
+
+    #[test]
+    fn test_run_output_passed_on_retry() {
+        let mut retried = test("a::flaky", TestStatus::Passed);
+        retried.attempts.push(crate::test_runner::TestAttempt {
+            status: TestStatus::Failed,
+            duration_ms: Some(1),
+            output: Vec::new(),
+            failure: None,
+        });
+        let result = TestRunResult {
+            passed: 2,
+            success: true,
+            test_results: vec![test("a::ok", TestStatus::Passed), retried],
+            ..Default::default()
+        };
+
+        let run = RunOutput::new("run-1", "ws", &result, &Quarantine::default(), &[]);
+        assert!(run.success);
+        let statuses: Vec<&str> = run.tests.iter().map(|t| t.status).collect();
+        assert_eq!(statuses, vec!["passed", "passed_on_retry"]);
+        assert_eq!(run.tests[1].retries, 1);
+    }
+}
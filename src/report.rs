@@ -2,20 +2,32 @@ use anyhow::Result;
 use std::fs;
 use std::path::Path;
 
+use crate::compare::RunComparison;
+use crate::db::RunCoverage;
+use crate::diff::{DiffRow, Segment};
+use crate::failure::TestFailure;
 use crate::task::TaskResult;
 
-/// Generate an HTML dashboard report
+/// Generate an HTML dashboard report, with the changes since another run
+/// when `comparison` is given and the line coverage when it was measured
 pub fn generate_report(
     project_name: &str,
     results: &[TaskResult],
+    comparison: Option<&RunComparison>,
+    coverage: Option<&RunCoverage>,
     output_path: &Path,
 ) -> Result<()> {
-    let html = build_html(project_name, results);
+    let html = build_html(project_name, results, comparison, coverage);
     fs::write(output_path, html)?;
     Ok(())
 }
 
-fn build_html(project_name: &str, results: &[TaskResult]) -> String {
+fn build_html(
+    project_name: &str,
+    results: &[TaskResult],
+    comparison: Option<&RunComparison>,
+    coverage: Option<&RunCoverage>,
+) -> String {
     let total = results.len();
     let passed = results.iter().filter(|r| r.success).count();
     let failed = total - passed;
@@ -45,9 +57,10 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
         .enumerate()
         .map(|(i, r)| {
             let cat = r.category.as_deref().unwrap_or("other");
+            let (status_class, status_text) = status_badge(r);
             format!(
-                "{{id:{},name:'{}',success:{},duration:{},category:'{}'}}",
-                i, r.name, r.success, r.duration_ms, cat
+                "{{id:{},name:'{}',success:{},statusClass:'{}',statusText:'{}',duration:{},category:'{}'}}",
+                i, r.name, r.success, status_class, status_text, r.duration_ms, cat
             )
         })
         .collect::<Vec<_>>()
@@ -72,6 +85,10 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
         .map(|(i, r)| build_task_card(i, r))
         .collect();
 
+    let comparison_section = comparison.map(build_comparison_section).unwrap_or_default();
+    let coverage_card = coverage.map(build_coverage_card).unwrap_or_default();
+    let coverage_section = coverage.map(build_coverage_section).unwrap_or_default();
+
     format!(
         r##"<!DOCTYPE html>
 <html lang="en">
@@ -123,6 +140,7 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
         .summary-card.failure {{ border-left: 4px solid #ef5350; }}
         .summary-card.time {{ border-left: 4px solid #00d4ff; }}
         .summary-card.rate {{ border-left: 4px solid #ffd700; }}
+        .summary-card.coverage {{ border-left: 4px solid #9c27b0; }}
         .summary-value {{ font-size: 28px; font-weight: bold; }}
         .summary-value.green {{ color: #26a69a; }}
         .summary-value.red {{ color: #ef5350; }}
@@ -220,6 +238,7 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
         }}
         .sidebar-item .status.pass {{ background: #1b4332; color: #26a69a; }}
         .sidebar-item .status.fail {{ background: #4a1c1c; color: #ef5350; }}
+        .sidebar-item .status.warn {{ background: #3d3d00; color: #ffd700; }}
         .sidebar-item .duration {{ font-size: 11px; color: #666; }}
         .cat-badge {{
             display: inline-block;
@@ -283,6 +302,7 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
         }}
         .task-badge.pass {{ background: #1b4332; color: #26a69a; }}
         .task-badge.fail {{ background: #4a1c1c; color: #ef5350; }}
+        .task-badge.warn {{ background: #3d3d00; color: #ffd700; }}
         .task-stats {{
             display: flex;
             gap: 20px;
@@ -298,6 +318,37 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
         .stat-value {{ font-size: 24px; font-weight: bold; color: #00d4ff; }}
         .stat-label {{ font-size: 11px; color: #888; margin-top: 5px; }}
 
+        /* Failure */
+        .failure-card {{ margin-bottom: 20px; }}
+        .failure-kind {{
+            font-family: monospace;
+            font-size: 12px;
+            padding: 2px 8px;
+            border-radius: 4px;
+            background: #4a1c1c;
+            color: #ef5350;
+        }}
+        .failure-location {{ color: #888; font-family: monospace; font-size: 13px; margin-bottom: 10px; }}
+        .failure-message {{ color: #ef9a9a; white-space: pre-wrap; font-size: 13px; }}
+        .diff-table {{ width: 100%; border-collapse: collapse; table-layout: fixed; font-family: monospace; font-size: 13px; }}
+        .diff-table th {{ text-align: left; padding: 6px 10px; font-weight: normal; }}
+        .diff-table th.diff-left {{ color: #ef5350; }}
+        .diff-table th.diff-right {{ color: #26a69a; }}
+        .diff-table td {{
+            padding: 1px 10px;
+            white-space: pre-wrap;
+            word-break: break-all;
+            vertical-align: top;
+            color: #888;
+            background: #0f0f1a;
+        }}
+        .diff-table td.diff-left {{ border-right: 1px solid #2d2d44; }}
+        .diff-table td.diff-left.diff-differs {{ color: #ef9a9a; background: #2a1515; }}
+        .diff-table td.diff-right.diff-differs {{ color: #a5d6a7; background: #152a1f; }}
+        .diff-table td.diff-missing {{ background: #161622; }}
+        .diff-table td.diff-left .diff-changed {{ background: #7f2a2a; color: #fff; }}
+        .diff-table td.diff-right .diff-changed {{ background: #1f6b45; color: #fff; }}
+
         /* Overview */
         #overview {{ display: block; }}
         .section-title {{
@@ -319,6 +370,8 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
         .matrix-table tr:hover {{ background: #1f2b47; cursor: pointer; }}
         .pass {{ color: #26a69a; font-weight: bold; }}
         .fail {{ color: #ef5350; font-weight: bold; }}
+        .warn {{ color: #ffd700; font-weight: bold; }}
+        .comparison-table td.detail {{ color: #888; font-family: monospace; font-size: 13px; }}
 
         /* Empty state */
         .no-results {{
@@ -352,7 +405,7 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
         <div class="summary-card rate">
             <div class="summary-value gold">{pass_rate:.1}%</div>
             <div class="summary-label">Pass Rate</div>
-        </div>
+        </div>{coverage_card}
     </div>
 
     <div class="filter-bar">
@@ -388,7 +441,7 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
                         <div id="pie-chart" class="chart-container"></div>
                     </div>
                 </div>
-
+{comparison_section}{coverage_section}
                 <h3 class="section-title" style="margin-top: 30px;">All Tests</h3>
                 <table class="matrix-table" id="resultsTable">
                     <thead>
@@ -430,7 +483,7 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
                         <div class="name">${{task.name}} ${{catBadge}}</div>
                         <div class="duration">${{task.duration}}ms</div>
                     </div>
-                    <span class="status ${{task.success ? 'pass' : 'fail'}}">${{task.success ? 'PASS' : 'FAIL'}}</span>
+                    <span class="status ${{task.statusClass}}">${{task.statusText}}</span>
                 `;
                 item.onclick = () => showTask(i);
                 sidebar.appendChild(item);
@@ -441,7 +494,7 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
                 row.dataset.success = task.success;
                 row.innerHTML = `
                     <td>${{task.name}} ${{catBadge}}</td>
-                    <td class="${{task.success ? 'pass' : 'fail'}}">${{task.success ? '✓ Passed' : '✗ Failed'}}</td>
+                    <td class="${{task.statusClass}}">${{task.statusText}}</td>
                     <td>${{task.category}}</td>
                     <td>${{task.duration}}ms</td>
                 `;
@@ -575,13 +628,26 @@ fn build_html(project_name: &str, results: &[TaskResult]) -> String {
         pass_rate = pass_rate,
         task_data_js = task_data_js,
         task_cards = task_cards,
+        comparison_section = comparison_section,
         category_buttons = category_buttons,
     )
 }
 
+/// CSS class and label for a result's status badge
+fn status_badge(result: &TaskResult) -> (&'static str, &'static str) {
+    match result.status.as_str() {
+        "passed" if result.passed_on_retry => ("warn", "PASSED ON RETRY"),
+        "passed" => ("pass", "PASSED"),
+        "timed_out" => ("fail", "TIMED OUT"),
+        "cancelled" => ("warn", "CANCELLED"),
+        "skipped" | "ignored" => ("warn", "SKIPPED"),
+        _ if result.success => ("pass", "PASSED"),
+        _ => ("fail", "FAILED"),
+    }
+}
+
 fn build_task_card(index: usize, result: &TaskResult) -> String {
-    let status_class = if result.success { "pass" } else { "fail" };
-    let status_text = if result.success { "PASSED" } else { "FAILED" };
+    let (status_class, status_text) = status_badge(result);
 
     format!(
         r##"<div id="task_{index}" class="task-detail">
@@ -598,7 +664,7 @@ fn build_task_card(index: usize, result: &TaskResult) -> String {
                 <div class="stat-value" style="color: {color}">{status_icon}</div>
                 <div class="stat-label">Status</div>
             </div>
-        </div>
+        </div>{ignore_note}{failure_note}
         <div class="chart-card">
             <h3>Execution Timeline</h3>
             <div id="task_chart_{index}" class="chart-container"></div>
@@ -631,9 +697,189 @@ fn build_task_card(index: usize, result: &TaskResult) -> String {
         duration = result.duration_ms,
         color = if result.success { "#26a69a" } else { "#ef5350" },
         status_icon = if result.success { "✓" } else { "✗" },
+        ignore_note = match result.ignore_reason {
+            Some(ref reason) => format!(
+                r#"
+        <div class="chart-card">
+            <h3>Ignored</h3>
+            <p>{}</p>
+        </div>"#,
+                escape_html(reason)
+            ),
+            None => String::new(),
+        },
+        failure_note = result.failure.as_ref().map(build_failure_card).unwrap_or_default(),
+    )
+}
+
+/// Panic message, location and `assert_eq!` diff of a failed test
+fn build_failure_card(failure: &TestFailure) -> String {
+    let location = failure.location.as_ref()
+        .map(|l| format!(
+            r#"<p class="failure-location">at {}:{}:{}</p>"#,
+            escape_html(&l.file.display().to_string()),
+            l.line,
+            l.column.unwrap_or(1)
+        ))
+        .unwrap_or_default();
+    let details = match failure.diff() {
+        Some(rows) => build_diff_table(&rows),
+        None => format!(r#"<pre class="failure-message">{}</pre>"#, escape_html(&failure.message)),
+    };
+
+    format!(
+        r#"
+        <div class="chart-card failure-card">
+            <h3><span class="failure-kind">{kind}</span> {headline}</h3>
+            {location}
+            {details}
+        </div>"#,
+        kind = failure.kind.label(),
+        headline = escape_html(failure.headline()),
+        location = location,
+        details = details,
+    )
+}
+
+/// Side-by-side left/right table with the changed characters highlighted
+fn build_diff_table(rows: &[DiffRow]) -> String {
+    let cell = |segments: &Option<Vec<Segment>>, side: &str, equal: bool| match segments {
+        Some(segments) => {
+            let text: String = segments.iter()
+                .map(|s| if s.changed {
+                    format!(r#"<span class="diff-changed">{}</span>"#, escape_html(&s.text))
+                } else {
+                    escape_html(&s.text)
+                })
+                .collect();
+            let class = if equal { "" } else { " diff-differs" };
+            format!(r#"<td class="diff-{}{}">{}</td>"#, side, class, text)
+        }
+        None => format!(r#"<td class="diff-{} diff-missing"></td>"#, side),
+    };
+
+    let body: String = rows.iter()
+        .map(|row| {
+            let equal = row.is_equal();
+            format!("<tr>{}{}</tr>", cell(&row.left, "left", equal), cell(&row.right, "right", equal))
+        })
+        .collect();
+    format!(
+        r#"<table class="diff-table"><tr><th class="diff-left">left</th><th class="diff-right">right</th></tr>{}</table>"#,
+        body
     )
 }
 
+/// Table of the tests that changed since the base run
+fn build_comparison_section(comparison: &RunComparison) -> String {
+    let row = |class: &str, change: &str, name: &str, detail: String| {
+        format!(
+            r#"<tr><td class="{}">{}</td><td>{}</td><td class="detail">{}</td></tr>"#,
+            class, change, escape_html(name), escape_html(&detail)
+        )
+    };
+
+    let mut rows = String::new();
+    for change in &comparison.newly_failing {
+        let message = change.error_message.as_deref().and_then(|m| m.lines().next()).unwrap_or_default();
+        rows += &row("fail", "Newly failing", &change.label(), format!("{} → {} {}", change.before, change.after, message));
+    }
+    for change in &comparison.newly_passing {
+        rows += &row("pass", "Newly passing", &change.label(), format!("{} → {}", change.before, change.after));
+    }
+    for test in &comparison.added {
+        rows += &row("pass", "Added", &test.label(), String::new());
+    }
+    for test in &comparison.removed {
+        rows += &row("warn", "Removed", &test.label(), String::new());
+    }
+    for (class, label, changes) in [("fail", "Slower", &comparison.slower), ("pass", "Faster", &comparison.faster)] {
+        for change in changes {
+            let detail = format!("{}ms → {}ms ({:+.0}%)", change.before_ms, change.after_ms, change.change_pct());
+            rows += &row(class, label, &change.label(), detail);
+        }
+    }
+
+    let base = &comparison.base[..8.min(comparison.base.len())];
+    let body = if rows.is_empty() {
+        format!(r#"<p class="no-results">No changes since run {}</p>"#, base)
+    } else {
+        format!(
+            r#"<table class="matrix-table comparison-table">
+                    <thead><tr><th>Change</th><th>Test</th><th>Details</th></tr></thead>
+                    <tbody>{}</tbody>
+                </table>"#,
+            rows
+        )
+    };
+    format!(
+        r#"
+                <h3 class="section-title" style="margin-top: 30px;">Changes since run {}</h3>
+                {}
+"#,
+        base, body
+    )
+}
+
+fn build_coverage_card(coverage: &RunCoverage) -> String {
+    let color = match coverage.threshold_passed {
+        Some(false) => "red",
+        _ => "green",
+    };
+    format!(
+        r#"
+        <div class="summary-card coverage">
+            <div class="summary-value {}">{:.1}%</div>
+            <div class="summary-label">Line Coverage</div>
+        </div>"#,
+        color,
+        coverage.report.total.line_coverage()
+    )
+}
+
+/// Coverage of every file, least covered first
+fn build_coverage_section(coverage: &RunCoverage) -> String {
+    let mut files: Vec<_> = coverage.report.files.iter().collect();
+    files.sort_by(|a, b| a.summary.line_coverage().total_cmp(&b.summary.line_coverage()));
+
+    let rows: String = files
+        .iter()
+        .map(|file| {
+            let pct = file.summary.line_coverage();
+            let class = if pct >= 80.0 { "pass" } else if pct >= 50.0 { "warn" } else { "fail" };
+            format!(
+                r#"<tr><td class="detail">{}</td><td class="{}">{:.1}%</td><td>{}/{}</td></tr>"#,
+                escape_html(&file.path), class, pct, file.summary.lines_covered, file.summary.lines_total
+            )
+        })
+        .collect();
+
+    let total = &coverage.report.total;
+    let threshold = match coverage.threshold_passed {
+        Some(true) => r#" <span class="pass">threshold met</span>"#,
+        Some(false) => r#" <span class="fail">below threshold</span>"#,
+        None => "",
+    };
+    format!(
+        r#"
+                <h3 class="section-title" style="margin-top: 30px;">Coverage: {:.1}% of lines ({}/{}){}</h3>
+                <table class="matrix-table comparison-table">
+                    <thead><tr><th>File</th><th>Lines</th><th>Covered</th></tr></thead>
+                    <tbody>{}</tbody>
+                </table>
+"#,
+        total.line_coverage(), total.lines_covered, total.lines_total, threshold, rows
+    )
+}
+
+/// Escape text interpolated into the HTML document
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 fn chrono_lite_now() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let duration = SystemTime::now()
@@ -11,12 +11,22 @@ use axum::{
 };
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 use tower_http::cors::CorsLayer;
 
-use crate::db::{Database, DashboardStats, TaskResult as DbTaskResult};
+use crate::compare::RunComparison;
+use crate::config::RunxConfig;
+use crate::db::{
+    by_target, failing_since, Database, DashboardStats, DurationRegression, QuarantinePolicy, RunCoverage, RunSummary,
+    TaskAttempt, TaskResult as DbTaskResult, TestExecution,
+};
+use crate::diff::DiffRow;
+use crate::editor::{editor_url, panic_location, DEFAULT_EDITOR_URL};
+use crate::failure::TestFailure;
+use crate::test_runner::CancelHandle;
 
 // WebSocket message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +36,7 @@ pub enum WsMessage {
     TaskStarted { run_id: String, task_name: String },
     TaskCompleted { run_id: String, task: DbTaskResult },
     RunCompleted { run_id: String, passed: i32, failed: i32 },
+    RunCancelled { run_id: String, passed: i32, failed: i32 },
     Stats(DashboardStats),
     ArtifactsUpdated,
     FileChanged { files: Vec<String> },
@@ -44,11 +55,15 @@ pub struct DebugEvent {
     pub error: Option<String>,
 }
 
+/// Cancellation handles of the runs currently executing, by run id
+pub type ActiveRuns = Arc<Mutex<HashMap<String, CancelHandle>>>;
+
 pub struct AppState {
     pub db_path: std::path::PathBuf,
     pub project_dir: std::path::PathBuf,
     pub tx: broadcast::Sender<WsMessage>,
     pub shutdown_tx: broadcast::Sender<()>,
+    pub active_runs: ActiveRuns,
 }
 
 impl AppState {
@@ -67,12 +82,14 @@ pub async fn start_server(
 ) -> Result<()> {
     let (tx, _) = broadcast::channel::<WsMessage>(100);
     let (shutdown_tx, mut shutdown_rx) = broadcast::channel::<()>(1);
+    let active_runs = ActiveRuns::default();
 
     let state = Arc::new(AppState {
         db_path: db_path.clone(),
         project_dir: project_dir.clone(),
         tx: tx.clone(),
         shutdown_tx,
+        active_runs: active_runs.clone(),
     });
 
     let app = Router::new()
@@ -80,6 +97,9 @@ pub async fn start_server(
         .route("/api/stats", get(get_stats))
         .route("/api/runs", get(get_runs))
         .route("/api/runs/:id", get(get_run))
+        .route("/api/runs/:id/cancel", post(cancel_run_handler))
+        .route("/api/runs/:id/compare/:other", get(compare_runs_handler))
+        .route("/api/tests/:name/history", get(get_test_history))
         .route("/api/artifacts", get(get_artifacts))
         .route("/api/artifacts/:test_name", get(get_artifact))
         .route("/api/clear-history", post(clear_history_handler))
@@ -101,18 +121,20 @@ pub async fn start_server(
         let initial_tx = tx.clone();
         let initial_db_path = db_path.clone();
         let initial_project_dir = project_dir.clone();
+        let initial_active_runs = active_runs.clone();
 
         std::thread::spawn(move || {
-            run_tests_and_broadcast(&initial_project_dir, &initial_db_path, &initial_tx);
+            run_tests_and_broadcast(&initial_project_dir, &initial_db_path, &initial_tx, &initial_active_runs);
         });
 
         // Start file watcher in background
         let watch_tx = tx.clone();
         let watch_db_path = db_path.clone();
         let watch_project_dir = project_dir.clone();
+        let watch_active_runs = active_runs.clone();
 
         std::thread::spawn(move || {
-            if let Err(e) = run_file_watcher(watch_project_dir, watch_db_path, watch_tx) {
+            if let Err(e) = run_file_watcher(watch_project_dir, watch_db_path, watch_tx, watch_active_runs) {
                 eprintln!("Watcher error: {}", e);
             }
         });
@@ -139,6 +161,7 @@ fn run_file_watcher(
     project_dir: std::path::PathBuf,
     db_path: std::path::PathBuf,
     tx: broadcast::Sender<WsMessage>,
+    active_runs: ActiveRuns,
 ) -> Result<()> {
     use std::sync::mpsc;
 
@@ -202,7 +225,7 @@ fn run_file_watcher(
             println!("🔄 Running tests...\n");
 
             // Run tests and save to DB
-            run_tests_and_broadcast(&project_dir, &db_path, &tx);
+            run_tests_and_broadcast(&project_dir, &db_path, &tx, &active_runs);
         }
     }
 }
@@ -211,10 +234,10 @@ fn run_tests_and_broadcast(
     project_dir: &std::path::Path,
     db_path: &std::path::Path,
     tx: &broadcast::Sender<WsMessage>,
+    active_runs: &ActiveRuns,
 ) {
     use crate::test_runner::{TestRunner, TestRunResult, SingleTestResult};
     use crate::test_model::TestStatus;
-    use chrono::Utc;
     use uuid::Uuid;
     use std::process::Command;
     use std::io::{BufRead, BufReader};
@@ -230,18 +253,33 @@ fn run_tests_and_broadcast(
         }
     };
 
+    // Create run in DB and register it so it can be cancelled while running
+    let _ = db.create_run(&run_id, 0);
+    let cancel = CancelHandle::new();
+    active_runs.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(run_id.clone(), cancel.clone());
+
+    // Notify run started
+    let _ = tx.send(WsMessage::RunStarted {
+        run_id: run_id.clone(),
+        total_tasks: 0,
+    });
+
     let mut total_passed = 0usize;
     let mut total_failed = 0usize;
     let mut all_results: Vec<SingleTestResult> = Vec::new();
+    let mut run_timed_out = false;
 
     // 1. Run Rust tests (cargo test)
     println!("🦀 Running Rust tests in: {}", project_dir.display());
-    let runner = TestRunner::new(project_dir);
+    let runner = TestRunner::new(project_dir).with_cancel_handle(cancel.clone());
     match runner.run_all() {
         Ok(r) => {
             println!("   ✓ Rust: {} passed, {} failed", r.passed, r.failed);
             total_passed += r.passed;
             total_failed += r.failed;
+            run_timed_out = r.timed_out;
             all_results.extend(r.test_results);
         },
         Err(e) => {
@@ -257,7 +295,7 @@ fn run_tests_and_broadcast(
             Some(project_dir.to_path_buf())
         };
 
-        if let Some(ref fe_dir) = frontend_dir {
+        if let Some(ref fe_dir) = frontend_dir.filter(|_| !cancel.is_cancelled()) {
         let package_json_path = fe_dir.join("package.json");
         if package_json_path.exists() {
             // Parse package.json to detect test framework
@@ -315,8 +353,9 @@ fn run_tests_and_broadcast(
                     .stdout(std::process::Stdio::piped())
                     .stderr(std::process::Stdio::piped());
 
-                match cmd.spawn() {
-                    Ok(mut child) => {
+                match cancel.spawn(&mut cmd) {
+                    Ok(None) => {}
+                    Ok(Some(mut child)) => {
                         let stdout = child.stdout.take();
                         let stderr = child.stderr.take();
 
@@ -327,19 +366,20 @@ fn run_tests_and_broadcast(
                         // Collect all output
                         if let Some(stdout) = stdout {
                             let reader = BufReader::new(stdout);
-                            for line in reader.lines().flatten() {
+                            for line in reader.lines().map_while(Result::ok) {
                                 output_lines.push(line);
                             }
                         }
 
                         if let Some(stderr) = stderr {
                             let reader = BufReader::new(stderr);
-                            for line in reader.lines().flatten() {
+                            for line in reader.lines().map_while(Result::ok) {
                                 output_lines.push(line);
                             }
                         }
 
                         let _ = child.wait();
+                        cancel.release(child.id());
 
                         // Try to parse JSON output (Vitest/Jest)
                         let full_output = output_lines.join("\n");
@@ -371,6 +411,10 @@ fn run_tests_and_broadcast(
                                                 status,
                                                 duration_ms: Some(duration),
                                                 output: vec![],
+                                                ignore_reason: None,
+                                                failure: None,
+                                                target: None,
+                                                attempts: Vec::new(),
                                             });
                                         }
                                     }
@@ -395,6 +439,10 @@ fn run_tests_and_broadcast(
                                             status: TestStatus::Passed,
                                             duration_ms: Some(1),
                                             output: vec![],
+                                            ignore_reason: None,
+                                            failure: None,
+                                            target: None,
+                                            attempts: Vec::new(),
                                         });
                                     }
                                 } else if trimmed.starts_with("×") || trimmed.starts_with("✗") || trimmed.contains(" FAIL ") {
@@ -411,6 +459,10 @@ fn run_tests_and_broadcast(
                                             status: TestStatus::Failed,
                                             duration_ms: Some(1),
                                             output: vec![],
+                                            ignore_reason: None,
+                                            failure: None,
+                                            target: None,
+                                            attempts: Vec::new(),
                                         });
                                     }
                                 }
@@ -429,6 +481,10 @@ fn run_tests_and_broadcast(
         }
     }
 
+    active_runs.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&run_id);
+
     // Create combined result
     let result = TestRunResult {
         success: total_failed == 0,
@@ -437,60 +493,46 @@ fn run_tests_and_broadcast(
         ignored: 0,
         duration_ms: 0,
         test_results: all_results,
+        cancelled: cancel.is_cancelled(),
+        timed_out: run_timed_out,
     };
 
-    // Notify run started
-    let _ = tx.send(WsMessage::RunStarted {
-        run_id: run_id.clone(),
-        total_tasks: result.test_results.len() as i32,
-    });
-
-    // Create run in DB
-    let _ = db.create_run(&run_id, result.test_results.len() as i32);
-
     // Save each test result
-    for test_result in &result.test_results {
-        let status = match test_result.status {
-            crate::test_model::TestStatus::Passed => "passed",
-            crate::test_model::TestStatus::Failed => "failed",
-            crate::test_model::TestStatus::Ignored => "ignored",
-            crate::test_model::TestStatus::Pending => "pending",
-            crate::test_model::TestStatus::Running => "running",
-        };
-
-        let db_result = crate::db::TaskResult {
-            id: Uuid::new_v4().to_string(),
-            run_id: run_id.clone(),
-            task_name: test_result.name.clone(),
-            category: None,
-            status: status.to_string(),
-            duration_ms: test_result.duration_ms.unwrap_or(0) as i64,
-            started_at: Utc::now(),
-            output: if test_result.output.is_empty() {
-                None
-            } else {
-                Some(test_result.output.join("\n"))
-            },
-        };
-
-        let _ = db.insert_task_result(&db_result);
-
-        // Notify task completed
+    for task in db.save_test_results(&run_id, &result).unwrap_or_default() {
         let _ = tx.send(WsMessage::TaskCompleted {
             run_id: run_id.clone(),
-            task: db_result,
+            task,
         });
     }
 
-    // Finish run
-    let _ = db.finish_run(&run_id, result.passed as i32, result.failed as i32);
+    // Quarantined failures do not make the run fail
+    let config = RunxConfig::load(project_dir).unwrap_or_default();
+    if let Some(policy) = QuarantinePolicy::from_config(&config.quarantine) {
+        let _ = db.apply_quarantine_policy(&policy);
+    }
+    let quarantined = db.quarantined_tests().unwrap_or_default();
+    let quarantined_failed = result.quarantined_failures(&quarantined).len() as i32;
 
-    // Notify run completed
-    let _ = tx.send(WsMessage::RunCompleted {
-        run_id: run_id.clone(),
-        passed: result.passed as i32,
-        failed: result.failed as i32,
-    });
+    // Finish run
+    if result.cancelled {
+        let _ = db.finish_run_with_status(&run_id, "cancelled", result.passed as i32, result.failed as i32);
+        let _ = tx.send(WsMessage::RunCancelled {
+            run_id: run_id.clone(),
+            passed: result.passed as i32,
+            failed: result.failed as i32,
+        });
+    } else {
+        if result.timed_out {
+            let _ = db.finish_run_with_status(&run_id, "timed_out", result.passed as i32, result.failed as i32);
+        } else {
+            let _ = db.finish_run_quarantined(&run_id, result.passed as i32, result.failed as i32, quarantined_failed);
+        }
+        let _ = tx.send(WsMessage::RunCompleted {
+            run_id: run_id.clone(),
+            passed: result.passed as i32,
+            failed: result.failed as i32,
+        });
+    }
 
     // Notify artifacts updated
     let _ = tx.send(WsMessage::ArtifactsUpdated);
@@ -501,7 +543,9 @@ fn run_tests_and_broadcast(
     }
 
     // Print summary
-    if result.failed > 0 {
+    if result.cancelled {
+        println!("⏹  Cancelled: {} passed, {} failed", result.passed, result.failed);
+    } else if result.failed > 0 {
         println!("❌ {} passed, {} failed", result.passed, result.failed);
     } else {
         println!("✅ {} passed", result.passed);
@@ -538,16 +582,36 @@ async fn run_tests_handler(State(state): State<Arc<AppState>>) -> impl IntoRespo
     let project_dir = state.project_dir.clone();
     let db_path = state.db_path.clone();
     let tx = state.tx.clone();
+    let active_runs = state.active_runs.clone();
 
     // Run tests in a background thread to not block the response
     std::thread::spawn(move || {
         println!("\n🧪 Running tests via dashboard...\n");
-        run_tests_and_broadcast(&project_dir, &db_path, &tx);
+        run_tests_and_broadcast(&project_dir, &db_path, &tx, &active_runs);
     });
 
     (StatusCode::OK, "Tests started")
 }
 
+async fn cancel_run_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let cancel = state.active_runs.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&id)
+        .cloned();
+
+    match cancel {
+        Some(cancel) => {
+            println!("\n⏹  Cancelling run {} via dashboard", id);
+            let _ = tokio::task::spawn_blocking(move || cancel.cancel()).await;
+            (StatusCode::OK, "Run cancelled").into_response()
+        }
+        None => (StatusCode::NOT_FOUND, "Run not active").into_response(),
+    }
+}
+
 async fn shutdown_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let _ = state.shutdown_tx.send(());
     "Server shutting down"
@@ -580,7 +644,19 @@ async fn get_runs(State(state): State<Arc<AppState>>) -> impl IntoResponse {
 async fn get_run(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> impl IntoResponse {
     match state.get_db() {
         Ok(db) => match db.get_run_summary(&id) {
-            Ok(Some(summary)) => Json(summary).into_response(),
+            Ok(Some(summary)) => {
+                let editor_links = editor_links(&summary.tasks, &state.project_dir);
+                let diffs = assertion_diffs(&summary.tasks);
+                let quarantine = db.quarantined_tests().unwrap_or_default();
+                let quarantined = summary.tasks.iter()
+                    .filter(|t| quarantine.contains(t.target.as_deref(), &t.task_name))
+                    .map(|t| t.id.clone())
+                    .collect();
+                let attempts = db.get_task_attempts_for_run(&id).unwrap_or_default();
+                let regressions = regressions_by_task(&summary.tasks, db.find_regressions(&id).unwrap_or_default());
+                let coverage = db.get_coverage(&id).unwrap_or_default();
+                Json(RunDetail { summary, editor_links, diffs, quarantined, attempts, regressions, coverage }).into_response()
+            }
             Ok(None) => (StatusCode::NOT_FOUND, "Run not found").into_response(),
             Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
         },
@@ -588,6 +664,132 @@ async fn get_run(State(state): State<Arc<AppState>>, Path(id): Path<String>) ->
     }
 }
 
+/// Compare run `id` (the base) with run `other`; both may be id prefixes
+async fn compare_runs_handler(
+    State(state): State<Arc<AppState>>,
+    Path((id, other)): Path<(String, String)>,
+) -> impl IntoResponse {
+    fn summary(db: &Database, id: &str) -> anyhow::Result<Option<RunSummary>> {
+        match db.resolve_run_id(id)? {
+            Some(id) => db.get_run_summary(&id),
+            None => Ok(None),
+        }
+    }
+
+    match state.get_db() {
+        Ok(db) => match (summary(&db, &id), summary(&db, &other)) {
+            (Ok(Some(base)), Ok(Some(head))) => Json(RunComparison::new(&base, &head)).into_response(),
+            (Err(e), _) | (_, Err(e)) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+            _ => (StatusCode::NOT_FOUND, "Run not found").into_response(),
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Executions of a test kept in a test's history page
+const TEST_HISTORY_LIMIT: i32 = 100;
+
+async fn get_test_history(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> impl IntoResponse {
+    match state.get_db() {
+        Ok(db) => match db.get_test_history(&name, TEST_HISTORY_LIMIT) {
+            Ok(executions) if executions.is_empty() => {
+                (StatusCode::NOT_FOUND, "Test not found").into_response()
+            }
+            Ok(executions) => {
+                let targets = by_target(executions)
+                    .into_iter()
+                    .map(|(classname, executions)| TargetHistory {
+                        failing_since: failing_since(&executions).cloned(),
+                        classname,
+                        executions,
+                    })
+                    .collect();
+                Json(TestHistory { test_name: name, targets }).into_response()
+            }
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        },
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Recorded executions of one test, per target
+#[derive(Serialize)]
+struct TestHistory {
+    test_name: String,
+    targets: Vec<TargetHistory>,
+}
+
+/// Executions of a test in one target, oldest first
+#[derive(Serialize)]
+struct TargetHistory {
+    /// Target label (empty when unknown)
+    classname: String,
+    executions: Vec<TestExecution>,
+    /// Execution from which the test has been failing, if it still fails
+    failing_since: Option<TestExecution>,
+}
+
+/// A run with "open in editor" links and assertion diffs for its tasks
+#[derive(Serialize)]
+struct RunDetail {
+    #[serde(flatten)]
+    summary: RunSummary,
+    /// Task result id → editor URL of its panic location
+    editor_links: HashMap<String, String>,
+    /// Task result id → side-by-side diff of its failed `assert_eq!`
+    diffs: HashMap<String, Vec<DiffRow>>,
+    /// Results of the tests currently in quarantine in their target
+    quarantined: Vec<String>,
+    /// Task result id → every attempt of the tests that were retried
+    attempts: HashMap<String, Vec<TaskAttempt>>,
+    /// Task result id → how it was slower than its duration baseline
+    regressions: HashMap<String, DurationRegression>,
+    /// Line coverage, for runs measured with `--coverage`
+    coverage: Option<RunCoverage>,
+}
+
+/// Editor links for the tasks that panicked inside the project, built from
+/// the `[editor] url` template
+fn editor_links(tasks: &[DbTaskResult], project_dir: &std::path::Path) -> HashMap<String, String> {
+    let config = RunxConfig::load(project_dir).unwrap_or_default();
+    let template = config.editor.url.unwrap_or_else(|| DEFAULT_EDITOR_URL.to_string());
+    let project_dir = project_dir.canonicalize().unwrap_or_else(|_| project_dir.to_path_buf());
+
+    tasks.iter()
+        .filter_map(|task| {
+            let output: Vec<String> = task.output.as_deref()?.lines().map(String::from).collect();
+            let location = panic_location(&output)?.resolve(&project_dir);
+            location.file.is_file()
+                .then(|| (task.id.clone(), editor_url(&template, &location)))
+        })
+        .collect()
+}
+
+/// Side-by-side diffs of the failed tasks whose output holds an `assert_eq!`
+fn assertion_diffs(tasks: &[DbTaskResult]) -> HashMap<String, Vec<DiffRow>> {
+    tasks.iter()
+        .filter(|task| task.status == "failed")
+        .filter_map(|task| {
+            let output: Vec<String> = task.output.as_deref()?.lines().map(String::from).collect();
+            let diff = TestFailure::parse(&output)?.diff()?;
+            Some((task.id.clone(), diff))
+        })
+        .collect()
+}
+
+/// Duration regressions of a run, matched to its tasks by target and name
+fn regressions_by_task(tasks: &[DbTaskResult], regressions: Vec<DurationRegression>) -> HashMap<String, DurationRegression> {
+    regressions.into_iter()
+        .filter_map(|r| {
+            let task = tasks.iter().find(|t| {
+                t.task_name == r.baseline.test_name
+                    && t.target.as_deref().unwrap_or("") == r.baseline.classname
+            })?;
+            Some((task.id.clone(), r))
+        })
+        .collect()
+}
+
 async fn ws_handler(
     State(state): State<Arc<AppState>>,
     ws: WebSocketUpgrade,
@@ -0,0 +1,468 @@
+//! Source indexer
+//!
+//! Finds where test functions are defined without compiling anything.
+//! Starting from a target's root file (`src/lib.rs`, `src/main.rs`,
+//! `tests/*.rs`, `benches/*.rs`, `examples/*.rs`), it follows `mod`
+//! declarations, inline modules and `#[path]` attributes, and records every
+//! function carrying a test attribute (`#[test]`, `#[tokio::test]`,
+//! `#[rstest]`, `#[test_case(...)]`, ...) under its module path.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Attributes (last path segment) that turn a function into a test
+const TEST_ATTRIBUTES: &[&str] = &["test", "rstest", "test_case", "quickcheck", "wasm_bindgen_test"];
+
+/// Where a test function is defined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    /// 1-based line of the function name
+    pub line: u32,
+}
+
+/// Test functions of one crate, keyed by their path inside the crate
+/// (e.g. "parser::tests::test_parse")
+#[derive(Debug, Clone, Default)]
+pub struct SourceIndex {
+    tests: HashMap<String, SourceLocation>,
+}
+
+impl SourceIndex {
+    /// Index the crate whose root source file is `root`
+    pub fn scan(root: &Path) -> Self {
+        let mut index = Self::default();
+        let dir = root.parent().map(Path::to_path_buf).unwrap_or_default();
+        index.scan_file(root, &[], &dir, &mut Vec::new());
+        index
+    }
+
+    /// Location of a test by its libtest name
+    ///
+    /// Parameterized tests are listed below their function
+    /// (`my_test::case_1`), so the closest indexed ancestor is used.
+    pub fn locate(&self, test_name: &str) -> Option<&SourceLocation> {
+        let mut name = test_name;
+        loop {
+            if let Some(location) = self.tests.get(name) {
+                return Some(location);
+            }
+            name = name.rsplit_once("::")?.0;
+        }
+    }
+
+    /// Scan one file of module `module_path`; `mod foo;` declarations are
+    /// looked up in `module_dir`
+    fn scan_file(&mut self, file: &Path, module_path: &[String], module_dir: &Path, visited: &mut Vec<PathBuf>) {
+        // A file included twice (e.g. through `#[path]`) is scanned once
+        if visited.iter().any(|v| v == file) {
+            return;
+        }
+        visited.push(file.to_path_buf());
+        let Ok(source) = std::fs::read_to_string(file) else {
+            return;
+        };
+        let file_dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        let tokens = tokenize(&source);
+
+        // Open braces: `Some` for inline modules, `None` for any other block
+        let mut scopes: Vec<Option<(String, PathBuf)>> = Vec::new();
+        let mut attributes: Vec<Attribute> = Vec::new();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let current_path = || {
+                let mut path = module_path.to_vec();
+                path.extend(scopes.iter().flatten().map(|(name, _)| name.clone()));
+                path
+            };
+            let current_dir = scopes.iter().rev().flatten().next()
+                .map(|(_, dir)| dir.clone())
+                .unwrap_or_else(|| module_dir.to_path_buf());
+
+            match &tokens[i].kind {
+                TokenKind::Punct('#') => {
+                    if let Some((attribute, next)) = parse_attribute(&tokens, i) {
+                        attributes.extend(attribute);
+                        i = next;
+                        continue;
+                    }
+                }
+                TokenKind::Ident(word) if word == "mod" => {
+                    if let Some(TokenKind::Ident(name)) = tokens.get(i + 1).map(|t| &t.kind) {
+                        let name = name.clone();
+                        let explicit = attributes.iter().find(|a| a.path == "path").and_then(|a| a.value.clone());
+                        match tokens.get(i + 2).map(|t| &t.kind) {
+                            Some(TokenKind::Punct(';')) => {
+                                let mut child_path = current_path();
+                                child_path.push(name.clone());
+                                if let Some(child) = resolve_module(&name, explicit.as_deref(), &current_dir, &file_dir, scopes.iter().flatten().next().is_some()) {
+                                    // Files loaded through `#[path]` own their directory like `mod.rs`
+                                    let child_dir = if explicit.is_some() || is_mod_rs(&child) {
+                                        child.parent().map(Path::to_path_buf).unwrap_or_default()
+                                    } else {
+                                        current_dir.join(&name)
+                                    };
+                                    self.scan_file(&child, &child_path, &child_dir, visited);
+                                }
+                                attributes.clear();
+                                i += 3;
+                                continue;
+                            }
+                            Some(TokenKind::Punct('{')) => {
+                                let dir = explicit.map(|p| current_dir.join(p)).unwrap_or_else(|| current_dir.join(&name));
+                                scopes.push(Some((name, dir)));
+                                attributes.clear();
+                                i += 3;
+                                continue;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                TokenKind::Ident(word) if word == "fn" => {
+                    if let Some(token) = tokens.get(i + 1) {
+                        if let TokenKind::Ident(name) = &token.kind {
+                            if attributes.iter().any(Attribute::is_test) {
+                                let mut path = current_path();
+                                path.push(name.clone());
+                                self.tests.insert(path.join("::"), SourceLocation {
+                                    file: file.to_path_buf(),
+                                    line: token.line,
+                                });
+                            }
+                        }
+                    }
+                    attributes.clear();
+                }
+                TokenKind::Punct('{') => {
+                    scopes.push(None);
+                    attributes.clear();
+                }
+                TokenKind::Punct('}') => {
+                    scopes.pop();
+                    attributes.clear();
+                }
+                TokenKind::Punct(';') => attributes.clear(),
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+/// `foo.rs` files own a `foo/` directory for their submodules,
+/// `mod.rs` and crate roots own the directory they are in
+fn is_mod_rs(file: &Path) -> bool {
+    file.file_name().is_some_and(|n| n == "mod.rs")
+}
+
+/// Find the file of `mod name;`
+fn resolve_module(name: &str, explicit: Option<&str>, module_dir: &Path, file_dir: &Path, inline: bool) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        // Outside inline modules, `#[path]` is relative to the current file
+        let base = if inline { module_dir } else { file_dir };
+        let file = base.join(path);
+        return file.is_file().then_some(file);
+    }
+    [module_dir.join(format!("{}.rs", name)), module_dir.join(name).join("mod.rs")]
+        .into_iter()
+        .find(|p| p.is_file())
+}
+
+/// An outer attribute such as `#[tokio::test(flavor = "multi_thread")]`
+#[derive(Debug)]
+struct Attribute {
+    /// Path of the attribute, e.g. "tokio::test"
+    path: String,
+    /// String value of `#[name = "value"]`
+    value: Option<String>,
+}
+
+impl Attribute {
+    fn is_test(&self) -> bool {
+        let name = self.path.rsplit("::").next().unwrap_or(&self.path);
+        TEST_ATTRIBUTES.contains(&name)
+    }
+}
+
+/// Parse the attribute starting at the `#` token at `start`, returning it
+/// (`None` for inner attributes) and the index after its closing `]`
+fn parse_attribute(tokens: &[Token], start: usize) -> Option<(Option<Attribute>, usize)> {
+    let mut i = start + 1;
+    let inner = matches!(tokens.get(i).map(|t| &t.kind), Some(TokenKind::Punct('!')));
+    if inner {
+        i += 1;
+    }
+    if !matches!(tokens.get(i).map(|t| &t.kind), Some(TokenKind::Punct('['))) {
+        return None;
+    }
+    i += 1;
+
+    let mut path = Vec::new();
+    while let Some(TokenKind::Ident(segment)) = tokens.get(i).map(|t| &t.kind) {
+        path.push(segment.clone());
+        i += 1;
+        match (tokens.get(i).map(|t| &t.kind), tokens.get(i + 1).map(|t| &t.kind)) {
+            (Some(TokenKind::Punct(':')), Some(TokenKind::Punct(':'))) => i += 2,
+            _ => break,
+        }
+    }
+    let value = match (tokens.get(i).map(|t| &t.kind), tokens.get(i + 1).map(|t| &t.kind)) {
+        (Some(TokenKind::Punct('=')), Some(TokenKind::Str(value))) => Some(value.clone()),
+        _ => None,
+    };
+
+    // Skip to the matching `]`
+    let mut depth = 1;
+    while depth > 0 {
+        match tokens.get(i)?.kind {
+            TokenKind::Punct('[') => depth += 1,
+            TokenKind::Punct(']') => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let attribute = (!inner).then(|| Attribute { path: path.join("::"), value });
+    Some((attribute, i))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Str(String),
+    Punct(char),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: u32,
+}
+
+/// Split Rust source into identifiers, string literals and punctuation,
+/// dropping comments, numbers, char literals and lifetimes
+fn tokenize(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+            }
+            _ if c.is_whitespace() => i += 1,
+            '/' if next == Some('/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('*') => {
+                let mut depth = 0;
+                while i < chars.len() {
+                    if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                        depth += 1;
+                        i += 2;
+                    } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        if chars[i] == '\n' {
+                            line += 1;
+                        }
+                        i += 1;
+                    }
+                }
+            }
+            '"' => {
+                let start_line = line;
+                let (value, end) = read_string(&chars, i + 1, &mut line);
+                tokens.push(Token { kind: TokenKind::Str(value), line: start_line });
+                i = end;
+            }
+            'r' | 'b' | 'c' if is_raw_string(&chars, i) => {
+                let start_line = line;
+                let mut j = i;
+                while chars[j] != '#' && chars[j] != '"' {
+                    j += 1;
+                }
+                let mut hashes = 0;
+                while chars[j] == '#' {
+                    hashes += 1;
+                    j += 1;
+                }
+                j += 1;
+                let mut value = String::new();
+                while j < chars.len() {
+                    if chars[j] == '"' && (1..=hashes).all(|k| chars.get(j + k) == Some(&'#')) {
+                        j += hashes + 1;
+                        break;
+                    }
+                    if chars[j] == '\n' {
+                        line += 1;
+                    }
+                    value.push(chars[j]);
+                    j += 1;
+                }
+                tokens.push(Token { kind: TokenKind::Str(value), line: start_line });
+                i = j;
+            }
+            'b' | 'c' if next == Some('"') => {
+                let start_line = line;
+                let (value, end) = read_string(&chars, i + 2, &mut line);
+                tokens.push(Token { kind: TokenKind::Str(value), line: start_line });
+                i = end;
+            }
+            '\'' => {
+                // Char literal ('a', '\n') or lifetime ('a)
+                if next == Some('\\') {
+                    i += 2;
+                    while i < chars.len() && chars[i] != '\'' {
+                        i += 1;
+                    }
+                    i += 1;
+                } else if chars.get(i + 2) == Some(&'\'') {
+                    i += 3;
+                } else {
+                    i += 1;
+                    while i < chars.len() && is_ident_char(chars[i]) {
+                        i += 1;
+                    }
+                }
+            }
+            _ if c.is_ascii_digit() => {
+                while i < chars.len() && (is_ident_char(chars[i]) || chars[i] == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+                    i += 1;
+                }
+            }
+            _ if is_ident_char(c) => {
+                let start = i;
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+                let mut word: String = chars[start..i].iter().collect();
+                // Raw identifiers: r#type
+                if word == "r" && chars.get(i) == Some(&'#') && chars.get(i + 1).is_some_and(|c| is_ident_char(*c)) {
+                    let start = i + 1;
+                    i = start;
+                    while i < chars.len() && is_ident_char(chars[i]) {
+                        i += 1;
+                    }
+                    word = chars[start..i].iter().collect();
+                }
+                tokens.push(Token { kind: TokenKind::Ident(word), line });
+            }
+            _ => {
+                tokens.push(Token { kind: TokenKind::Punct(c), line });
+                i += 1;
+            }
+        }
+    }
+
+    tokens
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// `r"..."`, `r#"..."#`, `br"..."`, `cr"..."`
+fn is_raw_string(chars: &[char], i: usize) -> bool {
+    if i > 0 && is_ident_char(chars[i - 1]) {
+        return false;
+    }
+    let mut j = i;
+    if chars[j] == 'b' || chars[j] == 'c' {
+        j += 1;
+    }
+    if chars.get(j) != Some(&'r') {
+        return false;
+    }
+    j += 1;
+    while chars.get(j) == Some(&'#') {
+        j += 1;
+    }
+    chars.get(j) == Some(&'"')
+}
+
+/// Read a string literal body starting after its opening quote, returning
+/// the unescaped-enough value and the index after the closing quote
+fn read_string(chars: &[char], start: usize, line: &mut u32) -> (String, usize) {
+    let mut value = String::new();
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '"' => return (value, i + 1),
+            '\\' => {
+                if let Some(&escaped) = chars.get(i + 1) {
+                    if escaped == '\n' {
+                        *line += 1;
+                    } else {
+                        value.push(escaped);
+                    }
+                }
+                i += 2;
+            }
+            c => {
+                if c == '\n' {
+                    *line += 1;
+                }
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+    (value, i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, path: &str, content: &str) {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_scan_follows_modules() {
+        let dir = tempfile::TempDir::new().unwrap();
+        write(dir.path(), "src/lib.rs", "mod parser;\n#[path = \"extra/other.rs\"]\nmod renamed;\n\n#[test]\nfn at_root() {}\n");
+        write(dir.path(), "src/parser.rs", "pub fn parse() {}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n    fn test_parse() {}\n}\n\nmod lexer;\n");
+        write(dir.path(), "src/parser/lexer.rs", "#[tokio::test(flavor = \"multi_thread\")]\nasync fn lexes() {}\n");
+        write(dir.path(), "src/extra/other.rs", "#[rstest]\n#[case(1)]\nfn cases(#[case] n: u32) {}\n");
+
+        let index = SourceIndex::scan(&dir.path().join("src/lib.rs"));
+        assert_eq!(index.tests.len(), 4);
+
+        let root = index.locate("at_root").unwrap();
+        assert_eq!((root.file.clone(), root.line), (dir.path().join("src/lib.rs"), 6));
+        let parse = index.locate("parser::tests::test_parse").unwrap();
+        assert_eq!((parse.file.clone(), parse.line), (dir.path().join("src/parser.rs"), 8));
+        assert_eq!(index.locate("parser::lexer::lexes").unwrap().file, dir.path().join("src/parser/lexer.rs"));
+        // rstest lists each case below the function
+        assert_eq!(index.locate("renamed::cases::case_1").unwrap().line, 3);
+        assert!(index.locate("parser::parse").is_none());
+    }
+
+    #[test]
+    fn test_tokenize_skips_comments_and_literals() {
+        let source = "// #[test] fn commented() {}\n/* #[test]\n fn block() {} */\nconst S: &str = \"#[test] fn quoted() {\";\nconst R: &str = r#\"}\"#;\nfn lifetime<'a>(c: char) -> bool { c == '{' }\n#[test]\nfn real() {}\n";
+        let dir = tempfile::TempDir::new().unwrap();
+        write(dir.path(), "tests/api.rs", source);
+
+        let index = SourceIndex::scan(&dir.path().join("tests/api.rs"));
+        assert_eq!(index.tests.len(), 1);
+        assert_eq!(index.locate("real").unwrap().line, 8);
+    }
+}
@@ -1,10 +1,427 @@
-//! Task result types for reporting
+//! Task pipeline
+//!
+//! Executes the `[tasks]` declared in `runx.toml`: builds the `depends_on`
+//! graph, rejects cycles, and runs tasks in dependency order with independent
+//! tasks executing in parallel.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Instant;
+
+use crate::config::TaskConfig;
+use crate::failure::TestFailure;
 
 /// Result of executing a task
 #[derive(Debug, Clone)]
 pub struct TaskResult {
     pub name: String,
     pub success: bool,
+    /// Status string as stored in `task_results.status` (e.g. "timed_out")
+    pub status: String,
     pub duration_ms: u128,
     pub category: Option<String>,
+    /// Reason given with `#[ignore = "..."]`
+    pub ignore_reason: Option<String>,
+    /// Panic of a failed test, parsed from its output
+    pub failure: Option<TestFailure>,
+    /// Failed at first, then passed when retried
+    pub passed_on_retry: bool,
+}
+
+impl TaskResult {
+    /// Convert a stored task result to the report model
+    pub fn from_db(result: &crate::db::TaskResult) -> Self {
+        Self {
+            name: result.task_name.clone(),
+            success: result.status == "passed",
+            status: result.status.clone(),
+            duration_ms: result.duration_ms as u128,
+            category: result.category.clone(),
+            ignore_reason: result.ignore_reason.clone(),
+            failure: match (result.status.as_str(), &result.output) {
+                ("failed", Some(output)) => {
+                    TestFailure::parse(&output.lines().map(String::from).collect::<Vec<_>>())
+                }
+                _ => None,
+            },
+            passed_on_retry: result.passed_on_retry(),
+        }
+    }
+}
+
+/// Outcome of a pipeline task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    Passed,
+    Failed,
+    /// Not executed because a dependency did not pass
+    Skipped,
+}
+
+impl TaskStatus {
+    /// Status string stored in `task_results.status`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Passed => "passed",
+            TaskStatus::Failed => "failed",
+            TaskStatus::Skipped => "skipped",
+        }
+    }
+}
+
+/// A task executed (or skipped) by the pipeline
+#[derive(Debug, Clone)]
+pub struct TaskRun {
+    pub name: String,
+    pub category: Option<String>,
+    pub status: TaskStatus,
+    pub duration_ms: u128,
+    pub started_at: DateTime<Utc>,
+    /// Combined stdout and stderr lines
+    pub output: Vec<String>,
+}
+
+impl TaskRun {
+    /// Convert to the report model
+    pub fn to_result(&self) -> TaskResult {
+        TaskResult {
+            name: self.name.clone(),
+            success: self.status == TaskStatus::Passed,
+            status: self.status.as_str().to_string(),
+            duration_ms: self.duration_ms,
+            category: self.category.clone(),
+            ignore_reason: None,
+            failure: None,
+            passed_on_retry: false,
+        }
+    }
+}
+
+/// Progress event emitted while the pipeline runs
+#[derive(Debug, Clone)]
+pub enum TaskEvent {
+    Started { name: String },
+    Finished(TaskRun),
+}
+
+/// Validated dependency graph of configured tasks
+#[derive(Debug, Clone)]
+pub struct TaskGraph {
+    tasks: BTreeMap<String, TaskConfig>,
+    /// All task names, dependencies before dependents
+    order: Vec<String>,
+}
+
+impl TaskGraph {
+    /// Build the graph, failing on unknown dependencies or cycles
+    pub fn new(tasks: BTreeMap<String, TaskConfig>) -> Result<Self> {
+        for (name, task) in &tasks {
+            for dep in &task.depends_on {
+                if !tasks.contains_key(dep) {
+                    anyhow::bail!("Task '{}' depends on unknown task '{}'", name, dep);
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(tasks.len());
+        let mut marks: HashMap<&str, Mark> = HashMap::new();
+        let mut stack: Vec<&str> = Vec::new();
+        for name in tasks.keys() {
+            visit(name, &tasks, &mut marks, &mut stack, &mut order)?;
+        }
+
+        Ok(Self { tasks, order })
+    }
+
+    /// Look up a task by name
+    pub fn get(&self, name: &str) -> Option<&TaskConfig> {
+        self.tasks.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Select the given tasks plus their transitive dependencies, in execution order.
+    /// An empty selection means every task.
+    pub fn select(&self, names: &[String]) -> Result<Vec<String>> {
+        if names.is_empty() {
+            return Ok(self.order.clone());
+        }
+
+        let mut wanted: Vec<&str> = Vec::new();
+        let mut stack: Vec<&str> = Vec::new();
+        for name in names {
+            if !self.tasks.contains_key(name) {
+                anyhow::bail!("Unknown task '{}'", name);
+            }
+            stack.push(name);
+        }
+
+        while let Some(name) = stack.pop() {
+            if wanted.contains(&name) {
+                continue;
+            }
+            wanted.push(name);
+            stack.extend(self.tasks[name].depends_on.iter().map(|d| d.as_str()));
+        }
+
+        Ok(self.order.iter()
+            .filter(|n| wanted.contains(&n.as_str()))
+            .cloned()
+            .collect())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+/// Depth-first topological sort with cycle reporting
+fn visit<'a>(
+    name: &'a str,
+    tasks: &'a BTreeMap<String, TaskConfig>,
+    marks: &mut HashMap<&'a str, Mark>,
+    stack: &mut Vec<&'a str>,
+    order: &mut Vec<String>,
+) -> Result<()> {
+    match marks.get(name) {
+        Some(Mark::Done) => return Ok(()),
+        Some(Mark::Visiting) => {
+            let start = stack.iter().position(|n| *n == name).unwrap_or(0);
+            let mut cycle: Vec<&str> = stack[start..].to_vec();
+            cycle.push(name);
+            anyhow::bail!("Task dependency cycle: {}", cycle.join(" -> "));
+        }
+        None => {}
+    }
+
+    marks.insert(name, Mark::Visiting);
+    stack.push(name);
+    for dep in &tasks[name].depends_on {
+        visit(dep, tasks, marks, stack, order)?;
+    }
+    stack.pop();
+    marks.insert(name, Mark::Done);
+    order.push(name.to_string());
+
+    Ok(())
+}
+
+/// Runs tasks from a [`TaskGraph`]
+pub struct TaskPipeline<'a> {
+    project_dir: PathBuf,
+    graph: &'a TaskGraph,
+}
+
+impl<'a> TaskPipeline<'a> {
+    pub fn new(project_dir: &Path, graph: &'a TaskGraph) -> Self {
+        Self {
+            project_dir: project_dir.to_path_buf(),
+            graph,
+        }
+    }
+
+    /// Run the selected tasks (which must be closed under dependencies and in
+    /// execution order, as returned by [`TaskGraph::select`]).
+    ///
+    /// A task starts as soon as all of its dependencies have passed; tasks whose
+    /// dependencies failed or were skipped are themselves skipped.
+    pub fn run(&self, selected: &[String], mut on_event: impl FnMut(&TaskEvent)) -> Result<Vec<TaskRun>> {
+        let (tx, rx) = channel::<TaskRun>();
+        let mut pending: Vec<String> = selected.to_vec();
+        let mut finished: HashMap<String, TaskStatus> = HashMap::new();
+        let mut runs = Vec::with_capacity(selected.len());
+        let mut running = 0usize;
+
+        loop {
+            // Launch or skip every pending task whose dependencies are settled.
+            // `pending` is in topological order, so one pass is enough.
+            let mut i = 0;
+            while i < pending.len() {
+                let config = self.graph.get(&pending[i])
+                    .with_context(|| format!("Unknown task '{}'", pending[i]))?;
+                let dep_statuses: Option<Vec<TaskStatus>> = config.depends_on.iter()
+                    .map(|d| finished.get(d).copied())
+                    .collect();
+
+                let Some(dep_statuses) = dep_statuses else {
+                    i += 1;
+                    continue;
+                };
+
+                let name = pending.remove(i);
+                if dep_statuses.iter().any(|s| *s != TaskStatus::Passed) {
+                    let run = TaskRun {
+                        name: name.clone(),
+                        category: config.category.clone(),
+                        status: TaskStatus::Skipped,
+                        duration_ms: 0,
+                        started_at: Utc::now(),
+                        output: Vec::new(),
+                    };
+                    finished.insert(name, TaskStatus::Skipped);
+                    on_event(&TaskEvent::Finished(run.clone()));
+                    runs.push(run);
+                } else {
+                    on_event(&TaskEvent::Started { name: name.clone() });
+                    let tx = tx.clone();
+                    let config = config.clone();
+                    let project_dir = self.project_dir.clone();
+                    thread::spawn(move || {
+                        let _ = tx.send(execute_task(&project_dir, &name, &config));
+                    });
+                    running += 1;
+                }
+            }
+
+            if running == 0 {
+                break;
+            }
+
+            let run = rx.recv().context("Task worker disconnected")?;
+            running -= 1;
+            finished.insert(run.name.clone(), run.status);
+            on_event(&TaskEvent::Finished(run.clone()));
+            runs.push(run);
+        }
+
+        Ok(runs)
+    }
+}
+
+/// Execute a single task command in the project directory
+fn execute_task(project_dir: &Path, name: &str, config: &TaskConfig) -> TaskRun {
+    let started_at = Utc::now();
+    let start = Instant::now();
+
+    let output = shell_command(&config.cmd)
+        .current_dir(project_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output();
+
+    let (status, lines) = match output {
+        Ok(out) => {
+            let mut lines: Vec<String> = String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .map(String::from)
+                .collect();
+            lines.extend(String::from_utf8_lossy(&out.stderr).lines().map(String::from));
+            let status = if out.status.success() { TaskStatus::Passed } else { TaskStatus::Failed };
+            (status, lines)
+        }
+        Err(e) => (TaskStatus::Failed, vec![format!("Failed to spawn '{}': {}", config.cmd, e)]),
+    };
+
+    TaskRun {
+        name: name.to_string(),
+        category: config.category.clone(),
+        status,
+        duration_ms: start.elapsed().as_millis(),
+        started_at,
+        output: lines,
+    }
+}
+
+/// Build a platform shell invocation for a command string
+fn shell_command(cmd: &str) -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", cmd]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", cmd]);
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(cmd: &str, deps: &[&str]) -> TaskConfig {
+        TaskConfig {
+            cmd: cmd.to_string(),
+            depends_on: deps.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn graph(entries: &[(&str, &str, &[&str])]) -> Result<TaskGraph> {
+        TaskGraph::new(entries.iter()
+            .map(|(name, cmd, deps)| (name.to_string(), task(cmd, deps)))
+            .collect())
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let g = graph(&[
+            ("test", "true", &["build"]),
+            ("build", "true", &[]),
+            ("lint", "true", &["build"]),
+        ]).unwrap();
+
+        let order = g.select(&[]).unwrap();
+        let pos = |n: &str| order.iter().position(|o| o == n).unwrap();
+        assert!(pos("build") < pos("test"));
+        assert!(pos("build") < pos("lint"));
+    }
+
+    #[test]
+    fn test_cycle_detected() {
+        let err = graph(&[
+            ("a", "true", &["b"]),
+            ("b", "true", &["c"]),
+            ("c", "true", &["a"]),
+        ]).unwrap_err();
+        assert!(err.to_string().contains("a -> b -> c -> a"));
+    }
+
+    #[test]
+    fn test_unknown_dependency() {
+        let err = graph(&[("a", "true", &["missing"])]).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn test_select_includes_dependencies() {
+        let g = graph(&[
+            ("build", "true", &[]),
+            ("test", "true", &["build"]),
+            ("lint", "true", &[]),
+        ]).unwrap();
+
+        assert_eq!(g.select(&["test".to_string()]).unwrap(), vec!["build", "test"]);
+        assert!(g.select(&["nope".to_string()]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_dependency_skips_dependents() {
+        let g = graph(&[
+            ("build", "exit 1", &[]),
+            ("test", "true", &["build"]),
+            ("lint", "echo linted", &[]),
+        ]).unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let runs = TaskPipeline::new(dir.path(), &g)
+            .run(&g.select(&[]).unwrap(), |_| {})
+            .unwrap();
+
+        let status = |n: &str| runs.iter().find(|r| r.name == n).unwrap().status;
+        assert_eq!(status("build"), TaskStatus::Failed);
+        assert_eq!(status("test"), TaskStatus::Skipped);
+        assert_eq!(status("lint"), TaskStatus::Passed);
+        assert_eq!(runs.iter().find(|r| r.name == "lint").unwrap().output, vec!["linted"]);
+    }
 }
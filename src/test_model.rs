@@ -23,6 +23,10 @@ pub enum TestStatus {
     Failed,
     /// Test is marked with #[ignore]
     Ignored,
+    /// Test was still running when the session was cancelled
+    Cancelled,
+    /// Test exceeded its time limit and was killed
+    TimedOut,
 }
 
 impl TestStatus {
@@ -33,6 +37,8 @@ impl TestStatus {
             TestStatus::Passed => "✓",
             TestStatus::Failed => "✗",
             TestStatus::Ignored => "⊘",
+            TestStatus::Cancelled => "⊗",
+            TestStatus::TimedOut => "⧗",
         }
     }
 
@@ -44,10 +50,203 @@ impl TestStatus {
             TestStatus::Passed => Color::Green,
             TestStatus::Failed => Color::Red,
             TestStatus::Ignored => Color::DarkGray,
+            TestStatus::Cancelled => Color::Magenta,
+            TestStatus::TimedOut => Color::LightRed,
         }
     }
+
+    /// Status string stored in `task_results.status`
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            TestStatus::Passed => "passed",
+            TestStatus::Failed => "failed",
+            TestStatus::Ignored => "skipped",
+            TestStatus::Cancelled => "cancelled",
+            TestStatus::TimedOut => "timed_out",
+            TestStatus::Pending | TestStatus::Running => "pending",
+        }
+    }
+
+    /// Whether this outcome counts as a failure
+    pub fn is_failure(&self) -> bool {
+        matches!(self, TestStatus::Failed | TestStatus::TimedOut)
+    }
+}
+
+/// Kind of cargo target a test is compiled into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    Lib,
+    Bin,
+    /// Integration test (`tests/*.rs`)
+    Test,
+    Bench,
+    Example,
+    /// Documentation tests of a library
+    Doc,
 }
 
+impl TargetKind {
+    /// Map cargo's target `kind` list (e.g. `["lib"]`, `["proc-macro"]`, `["test"]`)
+    pub fn from_cargo(kinds: &[String]) -> Self {
+        match kinds.first().map(|k| k.as_str()) {
+            Some("bin") => TargetKind::Bin,
+            Some("test") => TargetKind::Test,
+            Some("bench") => TargetKind::Bench,
+            Some("example") => TargetKind::Example,
+            _ => TargetKind::Lib,
+        }
+    }
+
+    /// Kind of a `label()`
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "lib" => Some(TargetKind::Lib),
+            "bin" => Some(TargetKind::Bin),
+            "integration" => Some(TargetKind::Test),
+            "bench" => Some(TargetKind::Bench),
+            "example" => Some(TargetKind::Example),
+            "doc" => Some(TargetKind::Doc),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TargetKind::Lib => "lib",
+            TargetKind::Bin => "bin",
+            TargetKind::Test => "integration",
+            TargetKind::Bench => "bench",
+            TargetKind::Example => "example",
+            TargetKind::Doc => "doc",
+        }
+    }
+}
+
+/// Cargo package target a test belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TestTarget {
+    /// Package name
+    pub package: String,
+    /// Target name (crate name for lib and doc targets)
+    pub name: String,
+    pub kind: TargetKind,
+}
+
+impl TestTarget {
+    /// Node label in the test tree, e.g. "lib", "bin runx", "integration api"
+    pub fn label(&self) -> String {
+        match self.kind {
+            TargetKind::Lib | TargetKind::Doc => self.kind.label().to_string(),
+            _ => format!("{} {}", self.kind.label(), self.name),
+        }
+    }
+
+    /// Label including the package, e.g. "core (integration api)"
+    pub fn qualified_label(&self) -> String {
+        format!("{} ({})", self.package, self.label())
+    }
+
+    /// Target of a `qualified_label()`
+    pub fn from_qualified_label(label: &str) -> Option<Self> {
+        let (package, label) = label.strip_suffix(')')?.split_once(" (")?;
+        let (kind, name) = match label.split_once(' ') {
+            Some((kind, name)) => (TargetKind::from_label(kind)?, name.to_string()),
+            None => (TargetKind::from_label(label)?, package.replace('-', "_")),
+        };
+        Some(Self { package: package.to_string(), name, kind })
+    }
+
+    /// Target of a nextest binary id: "core" (lib), "core::api" (integration
+    /// test), "core::bin/cli", "core::example/demo" or "core::bench/parse"
+    pub fn from_binary_id(id: &str) -> Self {
+        let (package, target) = match id.split_once("::") {
+            Some((package, target)) => (package, Some(target)),
+            None => (id, None),
+        };
+        let (kind, name) = match target {
+            None => (TargetKind::Lib, package.replace('-', "_")),
+            Some(target) => match target.split_once('/') {
+                Some(("bin", name)) => (TargetKind::Bin, name.to_string()),
+                Some(("example", name)) => (TargetKind::Example, name.to_string()),
+                Some(("bench", name)) => (TargetKind::Bench, name.to_string()),
+                _ => (TargetKind::Test, target.to_string()),
+            },
+        };
+        Self { package: package.to_string(), name, kind }
+    }
+}
+
+/// Packages and targets a test run is restricted to
+/// (`-p <package>`, `--test <target>`, ...)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestScope {
+    /// Package names; empty means the whole workspace
+    pub packages: Vec<String>,
+    /// Target kinds and names; empty means every target
+    pub targets: Vec<(TargetKind, String)>,
+}
+
+impl TestScope {
+    /// Scope covering a single target
+    pub fn target(target: &TestTarget) -> Self {
+        Self {
+            packages: vec![target.package.clone()],
+            targets: vec![(target.kind, target.name.clone())],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty() && self.targets.is_empty()
+    }
+
+    /// Whether a discovered test falls inside this scope
+    pub fn matches(&self, test: &Test) -> bool {
+        match test.target {
+            Some(ref target) => self.matches_target(target),
+            None => self.is_empty(),
+        }
+    }
+
+    /// Whether a package target falls inside this scope
+    pub fn matches_target(&self, target: &TestTarget) -> bool {
+        (self.packages.is_empty() || self.packages.contains(&target.package))
+            && (self.targets.is_empty() || self.targets.iter().any(|(kind, name)| {
+                *kind == target.kind && (matches!(kind, TargetKind::Lib | TargetKind::Doc) || *name == target.name)
+            }))
+    }
+
+    /// `cargo test` arguments selecting this scope
+    pub fn cargo_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.packages.is_empty() {
+            args.push("--workspace".to_string());
+        }
+        for package in &self.packages {
+            args.extend(["-p".to_string(), package.clone()]);
+        }
+        for (kind, name) in &self.targets {
+            match kind {
+                TargetKind::Lib => args.push("--lib".to_string()),
+                TargetKind::Doc => args.push("--doc".to_string()),
+                _ => args.extend([format!("--{}", kind_flag(*kind)), name.clone()]),
+            }
+        }
+        args
+    }
+}
+
+fn kind_flag(kind: TargetKind) -> &'static str {
+    match kind {
+        TargetKind::Lib => "lib",
+        TargetKind::Bin => "bin",
+        TargetKind::Test => "test",
+        TargetKind::Bench => "bench",
+        TargetKind::Example => "example",
+        TargetKind::Doc => "doc",
+    }
+}
 
 /// A discovered test
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +271,12 @@ pub struct Test {
     pub source_file: Option<String>,
     /// Line number in source file (if known)
     pub line_number: Option<u32>,
+    /// Package target containing the test (if known)
+    #[serde(default)]
+    pub target: Option<TestTarget>,
+    /// Reason given with `#[ignore = "..."]`
+    #[serde(default)]
+    pub ignore_reason: Option<String>,
 }
 
 impl Test {
@@ -99,7 +304,48 @@ impl Test {
             last_run: None,
             source_file: None,
             line_number: None,
+            target: None,
+            ignore_reason: None,
+        }
+    }
+
+    /// Attach the package target, which also makes the ID unique across targets
+    pub fn with_target(mut self, target: TestTarget) -> Self {
+        self.id = format!("{:x}", md5_hash(&format!("{}/{}/{}", target.package, target.label(), self.full_name)));
+        if target.kind == TargetKind::Doc {
+            // "src/lib.rs - module::Item (line 10)": group by file
+            if let Some((file, item)) = self.full_name.split_once(" - ") {
+                self.module_path = vec![file.to_string()];
+                self.short_name = item.to_string();
+                self.source_file = Some(file.to_string());
+                self.line_number = item.rsplit_once("(line ")
+                    .and_then(|(_, line)| line.trim_end_matches(')').parse().ok());
+            }
         }
+        self.target = Some(target);
+        self
+    }
+
+    /// Path of the test's parent node in the tree:
+    /// package → target → modules when the target is known
+    pub fn tree_path(&self) -> Vec<String> {
+        match self.target {
+            Some(ref target) => {
+                let mut path = vec![target.package.clone(), target.label()];
+                path.extend(self.module_path.iter().cloned());
+                path
+            }
+            None => self.module_path.clone(),
+        }
+    }
+
+    /// "file:line" where the test is defined, if known
+    pub fn location(&self) -> Option<String> {
+        let file = self.source_file.as_ref()?;
+        Some(match self.line_number {
+            Some(line) => format!("{}:{}", file, line),
+            None => file.clone(),
+        })
     }
 
     /// Add output line to the test
@@ -242,17 +488,12 @@ impl TestNode {
 
     /// Add a test to this tree
     pub fn add_test(&mut self, test: Test) {
-        if test.module_path.is_empty() {
-            // Add directly as a child
-            self.children.push(TestNode::new_test(test));
-        } else {
-            // Navigate/create path to the correct module
-            let mut current = self;
-            for module in &test.module_path {
-                current = current.get_or_create_child(module);
-            }
-            current.children.push(TestNode::new_test(test));
+        // Navigate/create path to the correct module
+        let mut current = self;
+        for module in test.tree_path() {
+            current = current.get_or_create_child(&module);
         }
+        current.children.push(TestNode::new_test(test));
     }
 
     /// Sort children alphabetically (modules first, then tests)
@@ -285,6 +526,30 @@ impl TestNode {
         None
     }
 
+    /// Find a test by its unique ID
+    pub fn find_test_by_id(&self, id: &str) -> Option<&Test> {
+        self.all_tests().into_iter().find(|t| t.id == id)
+    }
+
+    /// Find a test by its unique ID (mutable)
+    pub fn find_test_by_id_mut(&mut self, id: &str) -> Option<&mut Test> {
+        if let Some(ref mut test) = self.test {
+            if test.id == id {
+                return Some(test);
+            }
+        }
+        self.children.iter_mut().find_map(|c| c.find_test_by_id_mut(id))
+    }
+
+    /// Find a node by its path of node names
+    pub fn find_node(&self, path: &[String]) -> Option<&TestNode> {
+        let mut current = self;
+        for segment in path {
+            current = current.children.iter().find(|c| &c.name == segment)?;
+        }
+        Some(current)
+    }
+
     /// Find a test by its full name (mutable)
     pub fn find_test_mut(&mut self, full_name: &str) -> Option<&mut Test> {
         if let Some(ref mut test) = self.test {
@@ -300,6 +565,27 @@ impl TestNode {
         None
     }
 
+    /// Find every test with the given full name, across all targets
+    pub fn find_tests_mut(&mut self, full_name: &str) -> Vec<&mut Test> {
+        let mut tests = Vec::new();
+        self.for_each_test_mut(&mut |test| {
+            if test.full_name == full_name {
+                tests.push(test);
+            }
+        });
+        tests
+    }
+
+    /// Apply a function to every test in the tree
+    pub fn for_each_test_mut<'a>(&'a mut self, f: &mut impl FnMut(&'a mut Test)) {
+        if let Some(ref mut test) = self.test {
+            f(test);
+        }
+        for child in &mut self.children {
+            child.for_each_test_mut(f);
+        }
+    }
+
     /// Get all tests as a flat list
     pub fn all_tests(&self) -> Vec<&Test> {
         let mut tests = Vec::new();
@@ -338,9 +624,12 @@ impl TestNode {
             .collect()
     }
 
-    /// Get failed tests
+    /// Get failed tests (including timed out ones)
     pub fn failed_tests(&self) -> Vec<&Test> {
-        self.tests_by_status(TestStatus::Failed)
+        self.all_tests()
+            .into_iter()
+            .filter(|t| t.status.is_failure())
+            .collect()
     }
 }
 
@@ -370,7 +659,7 @@ impl FilterMode {
         match self {
             FilterMode::All => true,
             FilterMode::Passed => status == TestStatus::Passed,
-            FilterMode::Failed => status == TestStatus::Failed,
+            FilterMode::Failed => status.is_failure(),
             FilterMode::Pending => status == TestStatus::Pending,
             FilterMode::Ignored => status == TestStatus::Ignored,
         }
@@ -396,13 +685,17 @@ pub struct TestStats {
     pub pending: usize,
     pub running: usize,
     pub ignored: usize,
+    pub cancelled: usize,
+    pub timed_out: usize,
 }
 
 impl TestStats {
     pub fn from_tree(tree: &TestNode) -> Self {
         let tests = tree.all_tests();
-        let mut stats = Self::default();
-        stats.total = tests.len();
+        let mut stats = Self {
+            total: tests.len(),
+            ..Self::default()
+        };
 
         for test in tests {
             match test.status {
@@ -411,6 +704,8 @@ impl TestStats {
                 TestStatus::Pending => stats.pending += 1,
                 TestStatus::Running => stats.running += 1,
                 TestStatus::Ignored => stats.ignored += 1,
+                TestStatus::Cancelled => stats.cancelled += 1,
+                TestStatus::TimedOut => stats.timed_out += 1,
             }
         }
 
@@ -465,4 +760,59 @@ mod tests {
         assert_eq!(filtered.len(), 1);
         assert_eq!(filtered[0].short_name, "test_alpha");
     }
+
+    #[test]
+    fn test_tree_grouped_by_target() {
+        let lib = TestTarget { package: "core".to_string(), name: "core".to_string(), kind: TargetKind::Lib };
+        let api = TestTarget { package: "core".to_string(), name: "api".to_string(), kind: TargetKind::Test };
+        let doc = TestTarget { kind: TargetKind::Doc, ..lib.clone() };
+
+        let mut root = TestNode::new_module("root");
+        root.add_test(Test::from_name("parser::test_one").with_target(lib.clone()));
+        root.add_test(Test::from_name("parser::test_one").with_target(api.clone()));
+        root.add_test(Test::from_name("src/lib.rs - parser::parse (line 12)").with_target(doc));
+        root.sort_children();
+        root.update_counts();
+
+        assert_eq!(root.children.len(), 1);
+        let names: Vec<_> = root.children[0].children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["doc", "integration api", "lib"]);
+
+        let doc_test = root.find_node(&["core".to_string(), "doc".to_string(), "src/lib.rs".to_string()]).unwrap();
+        assert_eq!(doc_test.children[0].name, "parser::parse (line 12)");
+        assert_eq!(doc_test.children[0].test.as_ref().unwrap().location().as_deref(), Some("src/lib.rs:12"));
+
+        let ids: std::collections::HashSet<_> = root.all_tests().iter().map(|t| t.id.clone()).collect();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(root.find_tests_mut("parser::test_one").len(), 2);
+    }
+
+    #[test]
+    fn test_scope_matching() {
+        let lib = TestTarget { package: "core".to_string(), name: "core".to_string(), kind: TargetKind::Lib };
+        let api = TestTarget { package: "core".to_string(), name: "api".to_string(), kind: TargetKind::Test };
+        let unit = Test::from_name("a::b").with_target(lib);
+        let integration = Test::from_name("a::b").with_target(api);
+
+        let package = TestScope { packages: vec!["core".to_string()], targets: Vec::new() };
+        assert!(package.matches(&unit) && package.matches(&integration));
+        assert_eq!(package.cargo_args(), vec!["-p", "core"]);
+
+        let target = TestScope { packages: Vec::new(), targets: vec![(TargetKind::Test, "api".to_string())] };
+        assert!(!target.matches(&unit));
+        assert!(target.matches(&integration));
+        assert_eq!(target.cargo_args(), vec!["--workspace", "--test", "api"]);
+    }
+
+    #[test]
+    fn test_target_from_labels() {
+        let api = TestTarget { package: "core".to_string(), name: "api".to_string(), kind: TargetKind::Test };
+        assert_eq!(TestTarget::from_qualified_label(&api.qualified_label()), Some(api.clone()));
+        assert_eq!(TestTarget::from_binary_id("core::api"), api);
+
+        let lib = TestTarget::from_qualified_label("my-core (lib)").unwrap();
+        assert_eq!((lib.kind, lib.name.as_str()), (TargetKind::Lib, "my_core"));
+        assert_eq!(TestTarget::from_binary_id("cli::bin/cli").qualified_label(), "cli (bin cli)");
+        assert!(TestTarget::from_qualified_label("unit tests").is_none());
+    }
 }
@@ -5,14 +5,22 @@
 #![allow(dead_code)]
 
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::process::{Child, Command, Stdio};
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use crate::test_model::TestStatus;
+use crate::config::{RetryConfig, RunxConfig};
+use crate::db::Quarantine;
+use crate::discovery::{TestBinary, TestBuild};
+use crate::failure::TestFailure;
+use crate::test_model::{Test, TestScope, TestStatus, TestTarget};
 
 /// Event from the test runner
 #[derive(Debug, Clone)]
@@ -21,6 +29,8 @@ pub enum TestEvent {
     Started { test_name: String },
     /// Test output line
     Output { test_name: String, line: String },
+    /// Test is still running after the slow-test threshold
+    Slow { test_name: String, elapsed_ms: u64 },
     /// Test completed
     Completed {
         test_name: String,
@@ -37,17 +47,253 @@ pub enum TestEvent {
     Error { message: String },
 }
 
+/// Tool used to discover and execute tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TestBackend {
+    /// `cargo test`
+    #[default]
+    Cargo,
+    /// `cargo nextest` (process-per-test)
+    Nextest,
+}
+
+impl TestBackend {
+    /// Command shown in messages
+    pub fn command_name(&self) -> &'static str {
+        match self {
+            TestBackend::Cargo => "cargo test",
+            TestBackend::Nextest => "cargo nextest",
+        }
+    }
+
+    /// Backend selected by `[test] backend` in runx.toml
+    pub fn configured(project_dir: &Path) -> Self {
+        RunxConfig::load(project_dir)
+            .map(|config| config.test.backend)
+            .unwrap_or_default()
+    }
+}
+
+/// Output format requested from libtest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable output, parsed line by line
+    Text,
+    /// `--format json` event stream (requires nightly or `RUSTC_BOOTSTRAP`)
+    Json,
+}
+
+impl OutputFormat {
+    /// Use the JSON event stream when the project's toolchain allows unstable options
+    pub fn detect(project_dir: &Path) -> Self {
+        if std::env::var_os("RUSTC_BOOTSTRAP").is_some() || is_nightly_toolchain(project_dir) {
+            OutputFormat::Json
+        } else {
+            OutputFormat::Text
+        }
+    }
+
+    /// Extra libtest arguments for this format
+    fn libtest_args(&self) -> &'static [&'static str] {
+        match self {
+            OutputFormat::Text => &[],
+            OutputFormat::Json => &["-Z", "unstable-options", "--format", "json", "--report-time"],
+        }
+    }
+}
+
+/// Check whether `rustc` (as resolved from the project directory) is a nightly build
+fn is_nightly_toolchain(project_dir: &Path) -> bool {
+    Command::new("rustc")
+        .arg("--version")
+        .current_dir(project_dir)
+        .output()
+        .map(|out| {
+            let version = String::from_utf8_lossy(&out.stdout);
+            version.contains("-nightly") || version.contains("-dev")
+        })
+        .unwrap_or(false)
+}
+
+/// How `#[ignore]`d tests are treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IgnoredTests {
+    /// Report them as ignored without running them
+    #[default]
+    Skip,
+    /// Run them along with the other tests (`--include-ignored`)
+    Include,
+    /// Run only them (`--ignored`)
+    Only,
+}
+
+impl IgnoredTests {
+    /// Whether a discovered test is part of the run
+    fn selects(&self, test: &Test) -> bool {
+        *self != IgnoredTests::Only || test.status == TestStatus::Ignored
+    }
+
+    fn libtest_arg(&self) -> Option<&'static str> {
+        match self {
+            IgnoredTests::Skip => None,
+            IgnoredTests::Include => Some("--include-ignored"),
+            IgnoredTests::Only => Some("--ignored"),
+        }
+    }
+
+    fn nextest_arg(&self) -> Option<&'static str> {
+        match self {
+            IgnoredTests::Skip => None,
+            IgnoredTests::Include => Some("--run-ignored=all"),
+            IgnoredTests::Only => Some("--run-ignored=ignored-only"),
+        }
+    }
+}
+
+/// Time limits applied while tests run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Kill a single test running longer than this
+    pub test: Option<Duration>,
+    /// Warn about a test still running after this
+    pub slow: Duration,
+    /// Stop the whole session after this
+    pub run: Option<Duration>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            test: None,
+            // Same threshold as libtest's "has been running for over 60 seconds"
+            slow: Duration::from_secs(60),
+            run: None,
+        }
+    }
+}
+
+impl Timeouts {
+    /// Limits from the `[test]` section of runx.toml
+    pub fn from_config(config: &crate::config::TestConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            test: config.timeout.map(Duration::from_secs),
+            slow: config.slow_timeout.map(Duration::from_secs).unwrap_or(defaults.slow),
+            run: config.run_timeout.map(Duration::from_secs),
+        }
+    }
+}
+
+/// Handle used to cancel a running test session from another thread
+///
+/// Cancelling kills the process tree of every test process started with this
+/// handle; tests still in flight are then reported as `Cancelled`.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle {
+    inner: Arc<CancelState>,
+}
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    /// Process group leaders of the running test processes
+    pids: Mutex<Vec<u32>>,
+}
+
+impl CancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation and kill every registered process tree
+    pub fn cancel(&self) {
+        let pids = self.inner.pids.lock().unwrap_or_else(|e| e.into_inner());
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        for &pid in pids.iter() {
+            kill_process_tree(pid);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Spawn a command in its own process group and register it for
+    /// cancellation. Returns `None` if the session was already cancelled.
+    pub fn spawn(&self, cmd: &mut Command) -> std::io::Result<Option<Child>> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+
+        // Holding the lock across spawn means a concurrent cancel() either
+        // sees this pid or we see its flag
+        let mut pids = self.inner.pids.lock().unwrap_or_else(|e| e.into_inner());
+        if self.is_cancelled() {
+            return Ok(None);
+        }
+        let child = cmd.spawn()?;
+        pids.push(child.id());
+        Ok(Some(child))
+    }
+
+    /// Forget a process that has exited
+    pub fn release(&self, pid: u32) {
+        self.inner.pids.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|&p| p != pid);
+    }
+}
+
+/// Kill a process and all of its descendants
+fn kill_process_tree(pid: u32) {
+    #[cfg(unix)]
+    let mut cmd = {
+        // Test processes lead their own group, so signal the whole group
+        let mut cmd = Command::new("kill");
+        cmd.args(["-KILL", "--", &format!("-{}", pid)]);
+        cmd
+    };
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = Command::new("taskkill");
+        cmd.args(["/T", "/F", "/PID", &pid.to_string()]);
+        cmd
+    };
+
+    let _ = cmd.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
+
 /// Test runner for executing Rust tests
 pub struct TestRunner {
     project_dir: std::path::PathBuf,
     event_tx: Option<Sender<TestEvent>>,
+    format: OutputFormat,
+    backend: TestBackend,
+    /// Number of tests executed concurrently
+    jobs: usize,
+    cancel: CancelHandle,
+    timeouts: Timeouts,
+    /// Packages and targets to test
+    scope: TestScope,
+    ignored: IgnoredTests,
 }
 
 impl TestRunner {
     pub fn new(project_dir: &Path) -> Self {
+        let config = RunxConfig::load(project_dir).unwrap_or_default();
         Self {
             project_dir: project_dir.to_path_buf(),
             event_tx: None,
+            format: OutputFormat::detect(project_dir),
+            backend: config.test.backend,
+            jobs: config.test.jobs.unwrap_or(1).max(1),
+            cancel: CancelHandle::new(),
+            timeouts: Timeouts::from_config(&config.test),
+            scope: TestScope::default(),
+            ignored: IgnoredTests::Skip,
         }
     }
 
@@ -57,69 +303,197 @@ impl TestRunner {
         self
     }
 
+    /// Override the detected libtest output format
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Override the configured backend
+    pub fn with_backend(mut self, backend: TestBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Override the configured number of concurrent tests
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Override the configured time limits
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Override the per-test timeout
+    pub fn with_test_timeout(mut self, limit: Duration) -> Self {
+        self.timeouts.test = Some(limit);
+        self
+    }
+
+    /// Override the global run timeout
+    pub fn with_run_timeout(mut self, limit: Duration) -> Self {
+        self.timeouts.run = Some(limit);
+        self
+    }
+
+    /// Restrict the run to some packages and targets
+    pub fn with_scope(mut self, scope: TestScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Run `#[ignore]`d tests too, or only them
+    pub fn with_ignored(mut self, ignored: IgnoredTests) -> Self {
+        self.ignored = ignored;
+        self
+    }
+
+    /// Use an existing cancellation handle
+    pub fn with_cancel_handle(mut self, cancel: CancelHandle) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Handle that cancels this runner's test processes
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    /// A runner with the same limits and cancel handle for running again
+    /// tests that already ran: it includes ignored tests and sends no event
+    pub fn detached(&self) -> Self {
+        Self {
+            project_dir: self.project_dir.clone(),
+            event_tx: None,
+            format: self.format,
+            backend: self.backend,
+            jobs: 1,
+            cancel: self.cancel.clone(),
+            timeouts: self.timeouts,
+            scope: self.scope.clone(),
+            ignored: IgnoredTests::Include,
+        }
+    }
+
+    /// When a session starting now must stop, if it has a time limit
+    pub fn deadline(&self) -> Option<Instant> {
+        self.timeouts.run.map(|limit| Instant::now() + limit)
+    }
+
+    /// Run one test of a binary in its own process, with extra environment
+    /// variables, under the per-test timeout, `deadline` and cancellation
+    pub fn run_alone(
+        &self,
+        binary: &TestBinary,
+        test_name: &str,
+        envs: &[(&str, &Path)],
+        deadline: Option<Instant>,
+    ) -> Result<TestRunResult> {
+        let names = [test_name.to_string()];
+        let mut cmd = self.test_command(&TestUnit::Binary(binary.clone()), &Selection::Exact(&names), 1);
+        cmd.envs(envs.iter().copied());
+        let (mut result, _) = self.run_process(cmd, deadline)?;
+        for test in &mut result.test_results {
+            test.target = Some(binary.target.clone());
+        }
+        Ok(result)
+    }
+
+    /// Output format actually produced by the backend
+    fn effective_format(&self) -> OutputFormat {
+        match self.backend {
+            TestBackend::Cargo => self.format,
+            // nextest always emits libtest-json with its reporter
+            TestBackend::Nextest => OutputFormat::Json,
+        }
+    }
+
     /// Run all tests
     pub fn run_all(&self) -> Result<TestRunResult> {
-        self.run_tests_internal(None, false)
+        self.run_tests_internal(None)
     }
 
     /// Run a single test by name
     pub fn run_test(&self, test_name: &str) -> Result<TestRunResult> {
-        self.run_tests_internal(Some(test_name), false)
+        self.run_tests_internal(Some(test_name))
     }
 
     /// Run tests matching a filter
     pub fn run_filtered(&self, filter: &str) -> Result<TestRunResult> {
-        self.run_tests_internal(Some(filter), false)
+        self.run_tests_internal(Some(filter))
     }
 
     /// Run only failed tests (requires test names)
     pub fn run_tests(&self, test_names: &[String]) -> Result<TestRunResult> {
-        let mut total_result = TestRunResult::default();
-
-        for name in test_names {
-            let result = self.run_test(name)?;
-            total_result.passed += result.passed;
-            total_result.failed += result.failed;
-            total_result.ignored += result.ignored;
-            total_result.test_results.extend(result.test_results);
-        }
-
-        Ok(total_result)
+        self.run_specific(test_names)
     }
 
-    /// Run specific tests by name (alias for run_tests)
+    /// Run specific tests by exact name in a single libtest invocation
+    /// (`--exact name...`), reporting one aggregated result
     pub fn run_specific(&self, test_names: &[String]) -> Result<TestRunResult> {
-        self.run_tests(test_names)
-    }
-
-    fn run_tests_internal(&self, filter: Option<&str>, _include_ignored: bool) -> Result<TestRunResult> {
-        let start = Instant::now();
-
-        // Build cargo test command
-        let mut cmd = Command::new("cargo");
-        cmd.arg("test");
+        let mut names = test_names.to_vec();
+        names.sort();
+        names.dedup();
 
-        if let Some(f) = filter {
-            cmd.arg(f);
+        if names.is_empty() {
+            // An empty selection must not fall through to "run everything"
+            let result = TestRunResult {
+                success: true,
+                ..Default::default()
+            };
+            if let Some(ref tx) = self.event_tx {
+                let _ = tx.send(TestEvent::AllCompleted { passed: 0, failed: 0, ignored: 0 });
+            }
+            return Ok(result);
         }
 
-        // Use test-threads=1 for deterministic output parsing
-        cmd.args(["--", "--test-threads=1"]);
+        self.run_selection(&Selection::Exact(&names))
+    }
 
-        cmd.current_dir(&self.project_dir)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+    fn run_tests_internal(&self, filter: Option<&str>) -> Result<TestRunResult> {
+        let selection = match filter {
+            Some(f) => Selection::Filter(f),
+            None => Selection::All,
+        };
+
+        self.run_selection(&selection)
+    }
 
-        let mut child = cmd.spawn().context("Failed to spawn cargo test")?;
+    fn run_selection(&self, selection: &Selection) -> Result<TestRunResult> {
+        let start = Instant::now();
+        let deadline = self.deadline();
 
-        // Parse output in real-time
-        let result = self.parse_test_output(&mut child)?;
+        // Built once for the whole selection, shards included
+        let build = match self.load_build() {
+            Ok(build) => build,
+            Err(e) => {
+                if let Some(ref tx) = self.event_tx {
+                    let _ = tx.send(TestEvent::Error { message: format!("{:#}", e) });
+                }
+                return Err(e);
+            }
+        };
 
-        // Wait for process to complete
-        let status = child.wait()?;
+        // Human-readable output can only be attributed with one test thread per
+        // process, so concurrency comes from sharding tests across processes.
+        // Structured output carries the test name on every event instead.
+        let mut result = match build {
+            Some(ref build) if self.jobs > 1 && self.effective_format() == OutputFormat::Text => {
+                let names = match selection {
+                    Selection::Exact(names) => names.to_vec(),
+                    _ => self.list_test_names(&build.tests, None, selection),
+                };
+                self.run_sharded(build, &names, deadline)?
+            }
+            _ => {
+                let units = self.test_units(build.as_ref(), selection);
+                self.run_units(build.as_ref(), &units, selection, self.jobs, deadline)?
+            }
+        };
 
-        let mut result = result;
-        result.success = status.success() || result.failed == 0;
         result.duration_ms = start.elapsed().as_millis() as u64;
 
         // Send completion event
@@ -134,39 +508,431 @@ impl TestRunner {
         Ok(result)
     }
 
-    fn parse_test_output(&self, child: &mut Child) -> Result<TestRunResult> {
+    /// With cargo, the test binaries, built unless they are up to date with
+    /// the sources. nextest builds them itself.
+    fn load_build(&self) -> Result<Option<TestBuild>> {
+        if self.backend == TestBackend::Nextest {
+            return Ok(None);
+        }
+        crate::discovery::build_tests(&self.project_dir).map(Some)
+    }
+
+    /// Test processes needed for a selection
+    fn test_units(&self, build: Option<&TestBuild>, selection: &Selection) -> Vec<TestUnit> {
+        let Some(build) = build else {
+            return vec![TestUnit::Nextest];
+        };
+
+        let runs_any = |target: &TestTarget| {
+            self.scope.matches_target(target)
+                && build.tests_in(target).any(|t| self.ignored.selects(t) && selection.matches(&t.full_name))
+        };
+
+        let mut units: Vec<TestUnit> = build.binaries.iter()
+            .filter(|b| runs_any(&b.target))
+            .cloned()
+            .map(TestUnit::Binary)
+            .collect();
+        units.extend(
+            build.doc_targets.iter()
+                .filter(|t| runs_any(t))
+                .cloned()
+                .map(TestUnit::Doc),
+        );
+
+        units
+    }
+
+    /// Build the command running a selection of one test unit
+    fn test_command(&self, unit: &TestUnit, selection: &Selection, threads: usize) -> Command {
+        let mut cmd = match unit {
+            TestUnit::Binary(binary) => {
+                let mut cmd = Command::new(&binary.executable);
+                cmd.args(self.libtest_args(selection, threads))
+                    .current_dir(&binary.package_dir)
+                    .env("CARGO_MANIFEST_DIR", &binary.package_dir)
+                    .env("CARGO_PKG_NAME", &binary.target.package);
+                cmd
+            }
+            TestUnit::Doc(target) => {
+                let mut cmd = Command::new("cargo");
+                cmd.args(["test", "--doc", "-p", &target.package, "--"])
+                    .args(self.libtest_args(selection, threads))
+                    .current_dir(&self.project_dir);
+                cmd
+            }
+            TestUnit::Nextest => {
+                let mut cmd = Command::new("cargo");
+                cmd.args(["nextest", "run", "--no-fail-fast", "--message-format", "libtest-json"])
+                    .args(["--test-threads", &threads.to_string()])
+                    .env("NEXTEST_EXPERIMENTAL_LIBTEST_JSON", "1")
+                    .args(self.scope.cargo_args())
+                    .args(self.ignored.nextest_arg())
+                    .current_dir(&self.project_dir);
+
+                match selection {
+                    Selection::All => {}
+                    Selection::Filter(f) => {
+                        cmd.arg(f);
+                    }
+                    Selection::Exact(names) => {
+                        cmd.arg("--").arg("--exact").args(*names);
+                    }
+                }
+                cmd
+            }
+        };
+
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        cmd
+    }
+
+    /// Arguments passed to a libtest harness
+    fn libtest_args(&self, selection: &Selection, threads: usize) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Selection::Filter(f) = selection {
+            args.push(f.to_string());
+        }
+        args.push(format!("--test-threads={}", threads));
+        args.extend(self.ignored.libtest_arg().map(String::from));
+        args.extend(self.format.libtest_args().iter().map(|a| a.to_string()));
+        if let Selection::Exact(names) = selection {
+            args.push("--exact".to_string());
+            args.extend(names.iter().cloned());
+        }
+        args
+    }
+
+    /// Run a selection in each test unit, one after the other
+    fn run_units(
+        &self,
+        build: Option<&TestBuild>,
+        units: &[TestUnit],
+        selection: &Selection,
+        threads: usize,
+        deadline: Option<Instant>,
+    ) -> Result<TestRunResult> {
+        let mut total = TestRunResult {
+            success: true,
+            ..Default::default()
+        };
+        for unit in units {
+            let mut result = self.run_resumable(build, unit, selection, threads, deadline)?;
+            // nextest already named each test's binary
+            if let Some(target) = unit.target() {
+                for test in &mut result.test_results {
+                    test.target = Some(target.clone());
+                }
+            }
+            total.merge(result);
+        }
+        Ok(total)
+    }
+
+    /// Run a selection in one process. If a hung test had to be killed, the
+    /// tests that never got to run are started again in a fresh process.
+    fn run_resumable(
+        &self,
+        build: Option<&TestBuild>,
+        unit: &TestUnit,
+        selection: &Selection,
+        threads: usize,
+        deadline: Option<Instant>,
+    ) -> Result<TestRunResult> {
+        let (mut result, killed) = self.run_process(self.test_command(unit, selection, threads), deadline)?;
+        if !killed {
+            return Ok(result);
+        }
+
+        let expected = match build {
+            Some(build) => self.list_test_names(&build.tests, unit.target(), selection),
+            None => {
+                let tree = crate::discovery::discover_nextest_tests(&self.project_dir)?;
+                self.list_test_names(tree.all_tests(), None, selection)
+            }
+        };
+
+        loop {
+            let reported: HashSet<&str> = result.test_results.iter().map(|t| t.name.as_str()).collect();
+            let remaining: Vec<String> = expected.iter()
+                .filter(|n| !reported.contains(n.as_str()))
+                .cloned()
+                .collect();
+            if remaining.is_empty() {
+                break;
+            }
+
+            let before = result.test_results.len();
+            let (next, killed) = self.run_process(
+                self.test_command(unit, &Selection::Exact(&remaining), threads),
+                deadline,
+            )?;
+            result.merge(next);
+
+            // Stop unless another hung test was killed and we made progress
+            if !killed || result.test_results.len() == before {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Spawn one test process and parse its output, enforcing time limits.
+    ///
+    /// Also returns whether the process was killed because a single test
+    /// exceeded its timeout (as opposed to cancellation or the run deadline).
+    fn run_process(&self, mut cmd: Command, deadline: Option<Instant>) -> Result<(TestRunResult, bool)> {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            return Ok((TestRunResult {
+                timed_out: true,
+                ..Default::default()
+            }, false));
+        }
+
+        let Some(mut child) = self.cancel.spawn(&mut cmd)
+            .with_context(|| format!("Failed to spawn {}", self.backend.command_name()))?
+        else {
+            return Ok((TestRunResult {
+                cancelled: true,
+                ..Default::default()
+            }, false));
+        };
+
+        let in_flight = InFlight::default();
+        let (stop_tx, stop_rx) = channel::<()>();
+
+        let result = thread::scope(|scope| {
+            let pid = child.id();
+            let in_flight = &in_flight;
+            scope.spawn(move || self.watchdog(pid, in_flight, deadline, stop_rx));
+
+            // Parse output in real-time
+            let result = self.parse_test_output(&mut child, in_flight);
+            drop(stop_tx);
+            result
+        });
+
+        // Wait for process to complete
+        let status = child.wait();
+        self.cancel.release(child.id());
+        let mut result = result?;
+        let status = status?;
+
+        let state = in_flight.lock();
+        let killed = !state.timed_out.is_empty() && !state.run_expired && !self.cancel.is_cancelled();
+        if self.cancel.is_cancelled() {
+            result.cancelled = true;
+            result.success = false;
+        } else if state.run_expired {
+            result.timed_out = true;
+            result.success = false;
+        } else {
+            result.success = (status.success() || result.failed == 0) && !killed;
+        }
+
+        Ok((result, killed))
+    }
+
+    /// Watch the tests running in process `pid`: warn about slow tests and
+    /// kill the process when a test or the whole run exceeds its limit.
+    /// Returns when `stop` is dropped.
+    fn watchdog(&self, pid: u32, in_flight: &InFlight, deadline: Option<Instant>, stop: Receiver<()>) {
+        const TICK: Duration = Duration::from_millis(100);
+
+        while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(TICK) {
+            let now = Instant::now();
+            let mut state = in_flight.lock();
+
+            if deadline.is_some_and(|d| now >= d) {
+                state.run_expired = true;
+                drop(state);
+                kill_process_tree(pid);
+                return;
+            }
+
+            let mut kill = false;
+            let InFlightState { running, warned, timed_out, .. } = &mut *state;
+            for (key, start) in running.iter() {
+                let elapsed = now.duration_since(*start);
+                if elapsed >= self.timeouts.slow && warned.insert(key.clone()) {
+                    self.send_slow(&key.1, elapsed);
+                }
+                if self.timeouts.test.is_some_and(|limit| elapsed >= limit) {
+                    timed_out.insert(key.clone());
+                    kill = true;
+                }
+            }
+
+            if kill {
+                drop(state);
+                kill_process_tree(pid);
+                return;
+            }
+        }
+    }
+
+    fn send_slow(&self, test_name: &str, elapsed: Duration) {
+        if let Some(ref tx) = self.event_tx {
+            let _ = tx.send(TestEvent::Slow {
+                test_name: test_name.to_string(),
+                elapsed_ms: elapsed.as_millis() as u64,
+            });
+        }
+    }
+
+    /// Names of the tests a selection runs, optionally only those of one target
+    fn list_test_names<'a>(
+        &self,
+        tests: impl IntoIterator<Item = &'a Test>,
+        target: Option<&TestTarget>,
+        selection: &Selection,
+    ) -> Vec<String> {
+        let mut names: Vec<String> = tests.into_iter()
+            .filter(|t| self.scope.matches(t) && self.ignored.selects(t))
+            .filter(|t| target.is_none() || t.target.as_ref() == target)
+            .filter(|t| selection.matches(&t.full_name))
+            .map(|t| t.full_name.clone())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Split the given tests across `jobs` single-threaded workers and
+    /// merge their results
+    fn run_sharded(&self, build: &TestBuild, names: &[String], deadline: Option<Instant>) -> Result<TestRunResult> {
+        let shards = shard_names(names, self.jobs);
+
+        let results: Vec<Result<TestRunResult>> = thread::scope(|scope| {
+            let handles: Vec<_> = shards.iter()
+                .map(|shard| {
+                    scope.spawn(move || {
+                        // Only start the units that contain tests of this shard
+                        let selection = Selection::Exact(shard);
+                        let units = self.test_units(Some(build), &selection);
+                        self.run_units(Some(build), &units, &selection, 1, deadline)
+                    })
+                })
+                .collect();
+
+            handles.into_iter()
+                .map(|h| h.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Test worker panicked"))))
+                .collect()
+        });
+
+        let mut total = TestRunResult {
+            success: true,
+            ..Default::default()
+        };
+        for result in results {
+            total.merge(result?);
+        }
+
+        Ok(total)
+    }
+
+    fn parse_test_output(&self, child: &mut Child, in_flight: &InFlight) -> Result<TestRunResult> {
         let stdout = child.stdout.take().context("Failed to capture stdout")?;
         let stderr = child.stderr.take().context("Failed to capture stderr")?;
 
+        // Drain stderr (compilation errors, nextest progress) concurrently so a
+        // full pipe cannot stall the test process
+        let stderr_tx = self.event_tx.clone();
+        let stderr_thread = thread::spawn(move || {
+            let stderr_reader = BufReader::new(stderr);
+            for line in stderr_reader.lines().map_while(Result::ok) {
+                // Emit as error output
+                if let Some(ref tx) = stderr_tx {
+                    let _ = tx.send(TestEvent::Output {
+                        test_name: "compile".to_string(),
+                        line,
+                    });
+                }
+            }
+        });
+
+        let result = match self.effective_format() {
+            OutputFormat::Text => self.parse_text_stream(BufReader::new(stdout), in_flight)?,
+            OutputFormat::Json => self.parse_json_stream(BufReader::new(stdout), in_flight)?,
+        };
+
+        let _ = stderr_thread.join();
+
+        Ok(result)
+    }
+
+    /// Parse libtest's human-readable output
+    fn parse_text_stream<R: BufRead>(&self, reader: R, in_flight: &InFlight) -> Result<TestRunResult> {
         let mut result = TestRunResult::default();
 
-        // Read stdout for test output
-        let reader = BufReader::new(stdout);
         let mut current_test: Option<String> = None;
         let mut current_output: Vec<String> = Vec::new();
         let mut test_start: Option<Instant> = None;
+        // Test whose captured output is being printed after the results
+        let mut failure_section: Option<String> = None;
 
-        for line in reader.lines() {
+        for line in StreamLines::new(reader) {
             let line = line?;
 
+            if let Some(name) = failure_section_name(&line) {
+                failure_section = Some(name.to_string());
+                continue;
+            }
+            if let Some(ref name) = failure_section {
+                // The closing "failures:" list repeats the failed test names
+                if line == "failures:" {
+                    failure_section = None;
+                } else {
+                    self.add_failure_output(&mut result, name, line);
+                }
+                continue;
+            }
+
+            // libtest's own slow warning: "test name has been running for over 60 seconds"
+            if let Some(name) = line.strip_prefix("test ").and_then(|l| l.strip_suffix(LIBTEST_SLOW_SUFFIX)) {
+                if let Some(elapsed) = in_flight.warn(None, name) {
+                    self.send_slow(name, elapsed);
+                }
+                continue;
+            }
+
             // Parse test start: "test module::test_name ..."
             if line.starts_with("test ") && !line.starts_with("test result:") {
                 // Check for test status on same line
                 if let Some(test_info) = parse_test_line(&line) {
+                    // Completion of the test whose "test name ... " prefix was seen earlier
+                    let tracked = current_test.as_deref() == Some(test_info.name.as_str());
+
                     // Emit start event
                     if let Some(ref tx) = self.event_tx {
-                        let _ = tx.send(TestEvent::Started {
-                            test_name: test_info.name.clone(),
-                        });
+                        if !tracked {
+                            let _ = tx.send(TestEvent::Started {
+                                test_name: test_info.name.clone(),
+                            });
+                        }
                     }
 
                     // If status is immediate (not "...")
                     if test_info.status != TestStatus::Running {
+                        let duration_ms = if tracked {
+                            in_flight.finish(None, &test_info.name);
+                            current_test = None;
+                            test_start.take().map(|s| s.elapsed().as_millis() as u64)
+                        } else {
+                            Some(0)
+                        };
+
                         let test_result = SingleTestResult {
                             name: test_info.name.clone(),
                             status: test_info.status,
-                            duration_ms: Some(0),
-                            output: Vec::new(),
+                            duration_ms,
+                            output: std::mem::take(&mut current_output),
+                            ignore_reason: test_info.ignore_reason,
+                            failure: None,
+                            target: None,
+                            attempts: Vec::new(),
                         };
 
                         match test_info.status {
@@ -181,13 +947,14 @@ impl TestRunner {
                             let _ = tx.send(TestEvent::Completed {
                                 test_name: test_info.name,
                                 status: test_result.status,
-                                duration_ms: 0,
+                                duration_ms: duration_ms.unwrap_or(0),
                             });
                         }
 
                         result.test_results.push(test_result);
                     } else {
                         // Test is running, track it
+                        in_flight.start(None, &test_info.name);
                         current_test = Some(test_info.name);
                         current_output.clear();
                         test_start = Some(Instant::now());
@@ -207,12 +974,17 @@ impl TestRunner {
 
                     let duration_ms = test_start
                         .map(|s| s.elapsed().as_millis() as u64);
+                    in_flight.finish(None, test_name);
 
                     let test_result = SingleTestResult {
                         name: test_name.clone(),
                         status,
                         duration_ms,
                         output: current_output.clone(),
+                        ignore_reason: line.split_once(" ignored, ").map(|(_, reason)| reason.trim().to_string()),
+                        failure: None,
+                        target: None,
+                        attempts: Vec::new(),
                     };
 
                     match status {
@@ -253,28 +1025,391 @@ impl TestRunner {
             }
         }
 
-        // Also read stderr for compilation errors
-        let stderr_reader = BufReader::new(stderr);
-        for line in stderr_reader.lines() {
-            if let Ok(line) = line {
-                // Emit as error output
-                if let Some(ref tx) = self.event_tx {
+        if let Some(test_name) = current_test {
+            let duration_ms = test_start.map(|s| s.elapsed().as_millis() as u64);
+            self.report_unfinished(&mut result, in_flight, None, test_name, duration_ms, current_output);
+        }
+
+        attach_failures(&mut result);
+        Ok(result)
+    }
+
+    /// Parse libtest's `--format json` event stream
+    fn parse_json_stream<R: BufRead>(&self, reader: R, in_flight: &InFlight) -> Result<TestRunResult> {
+        let mut result = TestRunResult::default();
+        let mut started: HashMap<(Option<TestTarget>, String), Instant> = HashMap::new();
+
+        for line in reader.lines() {
+            let line = line?;
+
+            // Non-JSON lines (e.g. doc-test headers) carry no results
+            let Ok(record) = serde_json::from_str::<LibtestRecord>(&line) else {
+                continue;
+            };
+            if record.kind != "test" {
+                continue;
+            }
+            // nextest reports names as "<binary-id>$<test name>"
+            let Some((target, name)) = record.name.map(|n| match n.split_once('$') {
+                Some((binary_id, test)) => (Some(TestTarget::from_binary_id(binary_id)), test.to_string()),
+                None => (None, n),
+            }) else {
+                continue;
+            };
+
+            let status = match record.event.as_str() {
+                "started" => {
+                    in_flight.start(target.as_ref(), &name);
+                    started.insert((target, name.clone()), Instant::now());
+                    if let Some(ref tx) = self.event_tx {
+                        let _ = tx.send(TestEvent::Started { test_name: name });
+                    }
+                    continue;
+                }
+                "timeout" => {
+                    if let Some(elapsed) = in_flight.warn(target.as_ref(), &name) {
+                        self.send_slow(&name, elapsed);
+                    }
+                    continue;
+                }
+                "ok" => TestStatus::Passed,
+                "failed" => TestStatus::Failed,
+                "ignored" => TestStatus::Ignored,
+                _ => continue,
+            };
+
+            let started_at = started.remove(&(target.clone(), name.clone()));
+            in_flight.finish(target.as_ref(), &name);
+            let duration_ms = record.exec_time
+                .map(|secs| (secs * 1000.0).round() as u64)
+                .or_else(|| started_at.map(|s| s.elapsed().as_millis() as u64))
+                .unwrap_or(0);
+
+            let mut output: Vec<String> = record.stdout
+                .as_deref()
+                .map(|s| s.lines().map(String::from).collect())
+                .unwrap_or_default();
+            let mut ignore_reason = None;
+            if let Some(message) = record.message.filter(|m| !m.is_empty()) {
+                if status == TestStatus::Ignored {
+                    ignore_reason = Some(message);
+                } else {
+                    output.push(message);
+                }
+            }
+
+            if let Some(ref tx) = self.event_tx {
+                for line in &output {
                     let _ = tx.send(TestEvent::Output {
-                        test_name: "compile".to_string(),
-                        line,
+                        test_name: name.clone(),
+                        line: line.clone(),
                     });
                 }
+                let _ = tx.send(TestEvent::Completed {
+                    test_name: name.clone(),
+                    status,
+                    duration_ms,
+                });
+            }
+
+            match status {
+                TestStatus::Passed => result.passed += 1,
+                TestStatus::Failed => result.failed += 1,
+                TestStatus::Ignored => result.ignored += 1,
+                _ => {}
             }
+
+            result.test_results.push(SingleTestResult {
+                name,
+                status,
+                duration_ms: Some(duration_ms),
+                output,
+                ignore_reason,
+                failure: None,
+                target,
+                attempts: Vec::new(),
+            });
+        }
+
+        let mut unfinished: Vec<_> = started.into_iter().collect();
+        unfinished.sort_by_key(|(_, start)| *start);
+        for ((target, name), start) in unfinished {
+            let duration_ms = Some(start.elapsed().as_millis() as u64);
+            self.report_unfinished(&mut result, in_flight, target, name, duration_ms, Vec::new());
         }
 
+        attach_failures(&mut result);
         Ok(result)
     }
+
+    /// Record a test that was still running when its process ended: it timed
+    /// out, or was cut short by the run deadline or cancellation. Tests killed
+    /// alongside another test's timeout are left unreported so they run again.
+    fn report_unfinished(
+        &self,
+        result: &mut TestRunResult,
+        in_flight: &InFlight,
+        target: Option<TestTarget>,
+        name: String,
+        duration_ms: Option<u64>,
+        mut output: Vec<String>,
+    ) {
+        let key = (target, name);
+        let state = in_flight.lock();
+        let status = if state.timed_out.contains(&key) || state.run_expired {
+            TestStatus::TimedOut
+        } else if self.cancel.is_cancelled() {
+            TestStatus::Cancelled
+        } else {
+            return;
+        };
+        drop(state);
+        let (target, name) = key;
+
+        if status == TestStatus::TimedOut {
+            result.failed += 1;
+            output.push(format!(
+                "test timed out after {}ms and was killed",
+                duration_ms.unwrap_or(0)
+            ));
+        }
+
+        if let Some(ref tx) = self.event_tx {
+            if status == TestStatus::TimedOut {
+                let _ = tx.send(TestEvent::Output {
+                    test_name: name.clone(),
+                    line: output.last().cloned().unwrap_or_default(),
+                });
+            }
+            let _ = tx.send(TestEvent::Completed {
+                test_name: name.clone(),
+                status,
+                duration_ms: duration_ms.unwrap_or(0),
+            });
+        }
+
+        result.test_results.push(SingleTestResult {
+            name,
+            status,
+            duration_ms,
+            output,
+            ignore_reason: None,
+            failure: None,
+            target,
+            attempts: Vec::new(),
+        });
+    }
+
+    /// Append a line of a failed test's "---- name stdout ----" section to
+    /// its result
+    fn add_failure_output(&self, result: &mut TestRunResult, test_name: &str, line: String) {
+        let Some(test) = result.test_results.iter_mut().rev().find(|t| t.name == test_name) else {
+            return;
+        };
+        if test.output.is_empty() && line.trim().is_empty() {
+            return;
+        }
+        if let Some(ref tx) = self.event_tx {
+            let _ = tx.send(TestEvent::Output {
+                test_name: test_name.to_string(),
+                line: line.clone(),
+            });
+        }
+        test.output.push(line);
+    }
+}
+
+/// Name of the test whose captured output starts at this line
+/// ("---- name stdout ----")
+fn failure_section_name(line: &str) -> Option<&str> {
+    line.strip_prefix("---- ")?
+        .strip_suffix(" stdout ----")
+}
+
+/// Drop trailing blank lines and parse the panic of every failed test
+pub(crate) fn attach_failures(result: &mut TestRunResult) {
+    for test in &mut result.test_results {
+        while test.output.last().is_some_and(|l| l.trim().is_empty()) {
+            test.output.pop();
+        }
+        if test.status == TestStatus::Failed {
+            test.failure = TestFailure::parse(&test.output);
+        }
+    }
+}
+
+/// Line iterator over libtest's human-readable output.
+///
+/// With one test thread libtest prints `test name ... ` and only ends the line
+/// once the test finishes, so a trailing `" ... "` fragment is also yielded
+/// (once) to report the test as started.
+struct StreamLines<R> {
+    reader: R,
+    pending: Vec<u8>,
+    partial_sent: bool,
+}
+
+impl<R: BufRead> StreamLines<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: Vec::new(),
+            partial_sent: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for StreamLines<R> {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.pending.drain(..=pos).collect();
+                self.partial_sent = false;
+                let line = String::from_utf8_lossy(&line[..pos]);
+                return Some(Ok(line.trim_end_matches('\r').to_string()));
+            }
+
+            if !self.partial_sent && self.pending.ends_with(b" ... ") {
+                self.partial_sent = true;
+                return Some(Ok(String::from_utf8_lossy(&self.pending).into_owned()));
+            }
+
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            };
+            if buf.is_empty() {
+                if self.pending.is_empty() || self.partial_sent {
+                    return None;
+                }
+                let line = String::from_utf8_lossy(&self.pending).into_owned();
+                self.pending.clear();
+                return Some(Ok(line));
+            }
+
+            let len = buf.len();
+            self.pending.extend_from_slice(buf);
+            self.reader.consume(len);
+        }
+    }
+}
+
+/// Suffix of libtest's slow-test notice in human-readable output
+const LIBTEST_SLOW_SUFFIX: &str = " has been running for over 60 seconds";
+
+/// Tests running in one test process, shared with its watchdog thread
+#[derive(Debug, Default)]
+struct InFlight(Mutex<InFlightState>);
+
+#[derive(Debug, Default)]
+struct InFlightState {
+    /// Start times by target (when the output names it) and test name
+    running: HashMap<(Option<TestTarget>, String), Instant>,
+    /// Tests already reported as slow
+    warned: HashSet<(Option<TestTarget>, String)>,
+    /// Tests killed for exceeding the per-test timeout
+    timed_out: HashSet<(Option<TestTarget>, String)>,
+    /// The run deadline passed while this process was running
+    run_expired: bool,
+}
+
+impl InFlight {
+    fn lock(&self) -> std::sync::MutexGuard<'_, InFlightState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn start(&self, target: Option<&TestTarget>, name: &str) {
+        self.lock().running.insert((target.cloned(), name.to_string()), Instant::now());
+    }
+
+    fn finish(&self, target: Option<&TestTarget>, name: &str) {
+        self.lock().running.remove(&(target.cloned(), name.to_string()));
+    }
+
+    /// Mark a test as reported slow; returns its running time the first time
+    fn warn(&self, target: Option<&TestTarget>, name: &str) -> Option<Duration> {
+        let key = (target.cloned(), name.to_string());
+        let mut state = self.lock();
+        let elapsed = state.running.get(&key).map(|s| s.elapsed()).unwrap_or_default();
+        state.warned.insert(key).then_some(elapsed)
+    }
+}
+
+/// Which tests a single test process should run
+enum Selection<'a> {
+    All,
+    /// Substring filter passed to libtest
+    Filter(&'a str),
+    /// Exact test names (`--exact name...`)
+    Exact(&'a [String]),
+}
+
+impl Selection<'_> {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Selection::All => true,
+            Selection::Filter(f) => name.contains(f),
+            Selection::Exact(names) => names.iter().any(|n| n == name),
+        }
+    }
+}
+
+/// What a single test process runs
+#[derive(Debug, Clone)]
+enum TestUnit {
+    /// A compiled test binary, invoked directly
+    Binary(TestBinary),
+    /// Documentation tests of a library, run through `cargo test --doc`
+    Doc(TestTarget),
+    /// `cargo nextest run` over the whole scope
+    Nextest,
+}
+
+impl TestUnit {
+    fn target(&self) -> Option<&TestTarget> {
+        match self {
+            TestUnit::Binary(binary) => Some(&binary.target),
+            TestUnit::Doc(target) => Some(target),
+            TestUnit::Nextest => None,
+        }
+    }
+}
+
+/// Distribute test names round-robin into at most `jobs` non-empty shards
+fn shard_names(names: &[String], jobs: usize) -> Vec<Vec<String>> {
+    let count = jobs.max(1).min(names.len());
+    let mut shards: Vec<Vec<String>> = vec![Vec::new(); count];
+    for (i, name) in names.iter().enumerate() {
+        shards[i % count].push(name.clone());
+    }
+    shards
+}
+
+/// A record from libtest's JSON event stream, e.g.
+/// `{ "type": "test", "name": "a::b", "event": "ok", "exec_time": 0.001 }`
+#[derive(Debug, Deserialize)]
+pub(crate) struct LibtestRecord {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub event: String,
+    pub name: Option<String>,
+    /// Seconds, present with `--report-time`
+    pub exec_time: Option<f64>,
+    /// Captured output (failed tests, or all with `--show-output`)
+    pub stdout: Option<String>,
+    /// Failure or ignore message
+    pub message: Option<String>,
 }
 
 /// Information parsed from a test line
 struct TestLineInfo {
     name: String,
     status: TestStatus,
+    /// Reason given with `#[ignore = "..."]`
+    ignore_reason: Option<String>,
 }
 
 /// Parse a line like "test module::test_name ... ok"
@@ -288,9 +1423,9 @@ fn parse_test_line(line: &str) -> Option<TestLineInfo> {
     // Find the test name (ends at " ... " or " ..." at end of line)
     let (name, status_part) = if let Some(idx) = rest.find(" ... ") {
         (&rest[..idx], &rest[idx + 5..])
-    } else if rest.ends_with(" ...") {
+    } else if let Some(name) = rest.strip_suffix(" ...") {
         // Running test - ends with " ..."
-        (&rest[..rest.len() - 4], "")
+        (name, "")
     } else if let Some(idx) = rest.find(" - ") {
         // Doc test format: "test module::func - ... "
         (&rest[..idx], &rest[idx + 3..])
@@ -298,14 +1433,16 @@ fn parse_test_line(line: &str) -> Option<TestLineInfo> {
         (rest.trim(), "")
     };
 
+    // "ignored, <reason>" may itself contain "ok" or "FAILED"
+    let ignore_reason = status_part.trim().strip_prefix("ignored, ").map(String::from);
     let status = if status_part.is_empty() || status_part.trim() == "..." {
         TestStatus::Running
+    } else if status_part.trim_start().starts_with("ignored") {
+        TestStatus::Ignored
     } else if status_part.contains("ok") {
         TestStatus::Passed
     } else if status_part.contains("FAILED") {
         TestStatus::Failed
-    } else if status_part.contains("ignored") {
-        TestStatus::Ignored
     } else {
         TestStatus::Running
     };
@@ -313,6 +1450,7 @@ fn parse_test_line(line: &str) -> Option<TestLineInfo> {
     Some(TestLineInfo {
         name: name.to_string(),
         status,
+        ignore_reason,
     })
 }
 
@@ -323,6 +1461,36 @@ pub struct SingleTestResult {
     pub status: TestStatus,
     pub duration_ms: Option<u64>,
     pub output: Vec<String>,
+    /// Reason given with `#[ignore = "..."]`
+    pub ignore_reason: Option<String>,
+    /// Panic parsed from the output of a failed test
+    pub failure: Option<TestFailure>,
+    /// Target the test was compiled into, when run from a known target
+    pub target: Option<TestTarget>,
+    /// Earlier attempts, oldest first, when the test was retried
+    pub attempts: Vec<TestAttempt>,
+}
+
+impl SingleTestResult {
+    /// Failed at first, then passed when retried
+    pub fn passed_on_retry(&self) -> bool {
+        self.status == TestStatus::Passed && !self.attempts.is_empty()
+    }
+
+    /// Whether the test is quarantined in its target
+    pub fn is_quarantined(&self, quarantined: &Quarantine) -> bool {
+        let target = self.target.as_ref().map(|t| t.qualified_label());
+        quarantined.contains(target.as_deref(), &self.name)
+    }
+}
+
+/// An earlier, failed attempt of a retried test
+#[derive(Debug, Clone)]
+pub struct TestAttempt {
+    pub status: TestStatus,
+    pub duration_ms: Option<u64>,
+    pub output: Vec<String>,
+    pub failure: Option<TestFailure>,
 }
 
 /// Result of running tests
@@ -334,12 +1502,145 @@ pub struct TestRunResult {
     pub ignored: usize,
     pub duration_ms: u64,
     pub test_results: Vec<SingleTestResult>,
+    /// The session was cancelled before all tests finished
+    pub cancelled: bool,
+    /// The global run timeout expired before all tests finished
+    pub timed_out: bool,
 }
 
 impl TestRunResult {
     pub fn total(&self) -> usize {
         self.passed + self.failed + self.ignored
     }
+
+    /// Failed tests that are in quarantine: they still run, but their
+    /// failures do not fail the run
+    pub fn quarantined_failures(&self, quarantined: &Quarantine) -> Vec<&SingleTestResult> {
+        self.test_results.iter()
+            .filter(|t| t.status.is_failure() && t.is_quarantined(quarantined))
+            .collect()
+    }
+
+    /// Failures that count against the run
+    pub fn blocking_failures(&self, quarantined: &Quarantine) -> usize {
+        self.failed.saturating_sub(self.quarantined_failures(quarantined).len())
+    }
+
+    /// Tests that only passed after being retried
+    pub fn passed_on_retry(&self) -> Vec<&SingleTestResult> {
+        self.test_results.iter().filter(|t| t.passed_on_retry()).collect()
+    }
+
+    /// Replace the failed tests run again in `retry` with their new
+    /// outcome, keeping the previous one as an earlier attempt
+    pub fn merge_retry(&mut self, retry: TestRunResult) {
+        self.cancelled |= retry.cancelled;
+        self.timed_out |= retry.timed_out;
+        for test in retry.test_results {
+            if test.status != TestStatus::Passed && !test.status.is_failure() {
+                continue;
+            }
+            let Some(orig) = self.test_results.iter_mut().find(|t| {
+                t.name == test.name
+                    && t.status.is_failure()
+                    && (t.target.is_none() || test.target.is_none() || t.target == test.target)
+            }) else {
+                continue;
+            };
+
+            let mut attempts = std::mem::take(&mut orig.attempts);
+            attempts.push(TestAttempt {
+                status: orig.status,
+                duration_ms: orig.duration_ms,
+                output: std::mem::take(&mut orig.output),
+                failure: orig.failure.take(),
+            });
+            if test.status == TestStatus::Passed {
+                self.passed += 1;
+                self.failed -= 1;
+            }
+            let target = orig.target.take();
+            *orig = SingleTestResult {
+                target: test.target.or(target),
+                attempts,
+                ..test
+            };
+        }
+        self.success = self.failed == 0 && !self.cancelled && !self.timed_out;
+    }
+
+    /// Fold another partial result into this one
+    pub fn merge(&mut self, other: TestRunResult) {
+        self.success &= other.success;
+        self.passed += other.passed;
+        self.failed += other.failed;
+        self.ignored += other.ignored;
+        self.cancelled |= other.cancelled;
+        self.timed_out |= other.timed_out;
+        self.test_results.extend(other.test_results);
+    }
+}
+
+/// Which failed tests are run again, and how many times
+#[derive(Debug, Clone, Default)]
+pub struct RetryPolicy {
+    /// Retries of a failed test
+    pub count: u32,
+    /// Only retry failures whose output contains one of these (lowercase)
+    pub on: Vec<String>,
+    /// Retries of the tests whose name contains the key, overriding `count`
+    pub tests: BTreeMap<String, u32>,
+}
+
+impl RetryPolicy {
+    /// Policy from the `[retry]` section, `count` (from `--retry`) taking
+    /// precedence over the configured count
+    pub fn from_config(config: &RetryConfig, count: Option<u32>) -> Self {
+        Self {
+            count: count.or(config.count).unwrap_or(0),
+            on: config.on.iter().map(|p| p.to_lowercase()).collect(),
+            tests: config.tests.clone(),
+        }
+    }
+
+    /// Most retries any test can get
+    pub fn max_retries(&self) -> u32 {
+        self.tests.values().copied().fold(self.count, u32::max)
+    }
+
+    /// Retries allowed for a test: the longest `tests` key found in its
+    /// name, or `count`
+    pub fn budget(&self, test_name: &str) -> u32 {
+        self.tests.iter()
+            .filter(|(pattern, _)| test_name.contains(pattern.as_str()))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map_or(self.count, |(_, &retries)| retries)
+    }
+
+    /// Whether a failure is worth retrying. A test killed by its timeout
+    /// matches "timed out".
+    pub fn matches(&self, test: &SingleTestResult) -> bool {
+        if self.on.is_empty() {
+            return true;
+        }
+        let mut text = test.output.join("\n").to_lowercase();
+        if test.status == TestStatus::TimedOut {
+            text.push_str("\ntimed out");
+        }
+        self.on.iter().any(|pattern| text.contains(pattern))
+    }
+
+    /// Failed tests that still have retries left
+    pub fn to_retry(&self, result: &TestRunResult) -> Vec<String> {
+        result.test_results.iter()
+            .filter(|t| {
+                t.status.is_failure()
+                    && (t.attempts.len() as u32) < self.budget(&t.name)
+                    && self.matches(t)
+            })
+            .map(|t| t.name.clone())
+            .collect()
+    }
 }
 
 /// Create a channel for receiving test events
@@ -347,28 +1648,73 @@ pub fn create_event_channel() -> (Sender<TestEvent>, Receiver<TestEvent>) {
     channel()
 }
 
+/// A test session running on a background thread
+pub struct RunHandle {
+    pub thread: thread::JoinHandle<Result<TestRunResult>>,
+    pub cancel: CancelHandle,
+}
+
 /// Async-friendly test runner that spawns tests in a separate thread
 pub fn run_tests_async(
     project_dir: &Path,
     filter: Option<String>,
     event_tx: Sender<TestEvent>,
-) -> thread::JoinHandle<Result<TestRunResult>> {
+) -> RunHandle {
+    run_scoped_async(project_dir, TestScope::default(), filter, event_tx)
+}
+
+/// Run the tests of some packages and targets in a separate thread
+pub fn run_scoped_async(
+    project_dir: &Path,
+    scope: TestScope,
+    filter: Option<String>,
+    event_tx: Sender<TestEvent>,
+) -> RunHandle {
     let project_dir = project_dir.to_path_buf();
+    let cancel = CancelHandle::new();
+    let runner_cancel = cancel.clone();
 
-    thread::spawn(move || {
-        let runner = TestRunner::new(&project_dir).with_event_sender(event_tx);
+    let thread = thread::spawn(move || {
+        let runner = TestRunner::new(&project_dir)
+            .with_event_sender(event_tx)
+            .with_cancel_handle(runner_cancel)
+            .with_scope(scope);
 
         if let Some(f) = filter {
             runner.run_filtered(&f)
         } else {
             runner.run_all()
         }
-    })
+    });
+
+    RunHandle { thread, cancel }
+}
+
+/// Run an explicit set of tests by exact name in a separate thread
+pub fn run_specific_async(
+    project_dir: &Path,
+    test_names: Vec<String>,
+    event_tx: Sender<TestEvent>,
+) -> RunHandle {
+    let project_dir = project_dir.to_path_buf();
+    let cancel = CancelHandle::new();
+    let runner_cancel = cancel.clone();
+
+    let thread = thread::spawn(move || {
+        let runner = TestRunner::new(&project_dir)
+            .with_event_sender(event_tx)
+            .with_cancel_handle(runner_cancel);
+        runner.run_specific(&test_names)
+    });
+
+    RunHandle { thread, cancel }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_model::TargetKind;
+    use std::path::PathBuf;
 
     #[test]
     fn test_parse_test_line() {
@@ -383,6 +1729,368 @@ mod tests {
         let info = parse_test_line("test ignored_test ... ignored").unwrap();
         assert_eq!(info.name, "ignored_test");
         assert_eq!(info.status, TestStatus::Ignored);
+
+        // A reason mentioning "ok" must not turn the test into a pass
+        let info = parse_test_line("test slow_test ... ignored, looks ok offline").unwrap();
+        assert_eq!(info.status, TestStatus::Ignored);
+        assert_eq!(info.ignore_reason.as_deref(), Some("looks ok offline"));
+    }
+
+    #[test]
+    fn test_parse_json_stream() {
+        let stream = r#"{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "a::passes" }
+{ "type": "test", "name": "a::passes", "event": "ok", "exec_time": 0.012 }
+{ "type": "test", "event": "started", "name": "a::fails" }
+{ "type": "test", "name": "a::fails", "event": "failed", "exec_time": 0.5, "stdout": "thread 'a::fails' panicked\ntest ok FAILED\n" }
+{ "type": "test", "event": "started", "name": "a::skipped" }
+{ "type": "test", "name": "a::skipped", "event": "ignored", "message": "slow" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1, "measured": 0, "filtered_out": 0, "exec_time": 0.6 }
+"#;
+
+        let (tx, rx) = create_event_channel();
+        let runner = TestRunner::new(Path::new("."))
+            .with_format(OutputFormat::Json)
+            .with_event_sender(tx);
+        let result = runner.parse_json_stream(stream.as_bytes(), &InFlight::default()).unwrap();
+
+        assert_eq!((result.passed, result.failed, result.ignored), (1, 1, 1));
+        assert_eq!(result.test_results[0].duration_ms, Some(12));
+        assert_eq!(result.test_results[1].status, TestStatus::Failed);
+        // Output containing " ok" does not change the outcome
+        assert_eq!(result.test_results[1].output, vec!["thread 'a::fails' panicked", "test ok FAILED"]);
+        assert!(result.test_results[2].output.is_empty());
+        assert_eq!(result.test_results[2].ignore_reason.as_deref(), Some("slow"));
+
+        drop(runner);
+        let completed: Vec<String> = rx.iter()
+            .filter_map(|e| match e {
+                TestEvent::Completed { test_name, .. } => Some(test_name),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(completed, vec!["a::passes", "a::fails", "a::skipped"]);
+    }
+
+    #[test]
+    fn test_parse_text_failures() {
+        let stream = "
+running 2 tests
+test a::passes ... ok
+test a::fails ... FAILED
+
+failures:
+
+---- a::fails stdout ----
+
+thread 'a::fails' panicked at src/lib.rs:9:5:
+assertion `left == right` failed
+  left: 4
+ right: 5
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    a::fails
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+
+        let runner = TestRunner::new(Path::new("."));
+        let result = runner.parse_text_stream(stream.as_bytes(), &InFlight::default()).unwrap();
+
+        assert_eq!((result.passed, result.failed), (1, 1));
+        assert!(result.test_results[0].failure.is_none());
+        let failed = &result.test_results[1];
+        assert_eq!(failed.output.first().map(String::as_str), Some("thread 'a::fails' panicked at src/lib.rs:9:5:"));
+        assert_eq!(failed.output.last().map(String::as_str), Some("note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"));
+
+        let failure = failed.failure.as_ref().unwrap();
+        assert_eq!(failure.kind, crate::failure::FailureKind::AssertEq);
+        assert_eq!((failure.left.as_deref(), failure.right.as_deref()), (Some("4"), Some("5")));
+    }
+
+    #[test]
+    fn test_parse_nextest_names() {
+        let stream = r#"{"type":"suite","event":"started","test_count":1,"nextest":{"crate":"demo","test_binary":"demo","kind":"lib"}}
+{"type":"test","event":"started","name":"demo$tests::one"}
+{"type":"test","event":"started","name":"demo::api$tests::one"}
+{"type":"test","event":"ok","name":"demo$tests::one","exec_time":0.002}
+"#;
+
+        let runner = TestRunner::new(Path::new(".")).with_backend(TestBackend::Nextest);
+        let in_flight = InFlight::default();
+        let result = runner.parse_json_stream(stream.as_bytes(), &in_flight).unwrap();
+        assert_eq!(result.passed, 1);
+        assert_eq!(result.test_results[0].name, "tests::one");
+        assert_eq!(result.test_results[0].target, Some(TestTarget::from_binary_id("demo")));
+        // The namesake in another binary is still running
+        let running: Vec<_> = in_flight.lock().running.keys().cloned().collect();
+        assert_eq!(running, vec![(Some(TestTarget::from_binary_id("demo::api")), "tests::one".to_string())]);
+    }
+
+    #[test]
+    fn test_shard_names() {
+        let names: Vec<String> = (0..5).map(|i| format!("t{}", i)).collect();
+        let shards = shard_names(&names, 2);
+        assert_eq!(shards, vec![vec!["t0", "t2", "t4"], vec!["t1", "t3"]]);
+
+        // Never more shards than tests
+        assert_eq!(shard_names(&names[..1], 8).len(), 1);
+        assert!(shard_names(&[], 4).is_empty());
+    }
+
+    #[test]
+    fn test_exact_command_args() {
+        let runner = TestRunner::new(Path::new("."))
+            .with_backend(TestBackend::Cargo)
+            .with_format(OutputFormat::Text);
+        let target = TestTarget { package: "core".to_string(), name: "api".to_string(), kind: TargetKind::Test };
+        let unit = TestUnit::Binary(TestBinary {
+            target: target.clone(),
+            executable: PathBuf::from("/ws/target/debug/deps/api-1"),
+            package_dir: PathBuf::from("/ws/core"),
+        });
+        let names = vec!["a::one".to_string(), "b::two".to_string()];
+
+        let cmd = runner.test_command(&unit, &Selection::Exact(&names), 4);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert_eq!(cmd.get_program(), "/ws/target/debug/deps/api-1");
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/ws/core")));
+        assert_eq!(args, vec!["--test-threads=4", "--exact", "a::one", "b::two"]);
+
+        let cmd = runner.test_command(&TestUnit::Doc(target), &Selection::Filter("parse"), 1);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert_eq!(args, vec!["test", "--doc", "-p", "core", "--", "parse", "--test-threads=1"]);
+    }
+
+    #[test]
+    fn test_scoped_command_args() {
+        let runner = TestRunner::new(Path::new("."))
+            .with_backend(TestBackend::Nextest)
+            .with_scope(TestScope {
+                packages: vec!["core".to_string()],
+                targets: vec![(TargetKind::Lib, String::new()), (TargetKind::Test, "api".to_string())],
+            });
+        let cmd = runner.test_command(&TestUnit::Nextest, &Selection::Filter("parser"), 1);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert_eq!(args, vec![
+            "nextest", "run", "--no-fail-fast", "--message-format", "libtest-json", "--test-threads", "1",
+            "-p", "core", "--lib", "--test", "api", "parser",
+        ]);
+    }
+
+    #[test]
+    fn test_run_specific_empty_runs_nothing() {
+        let (tx, rx) = create_event_channel();
+        let dir = tempfile::TempDir::new().unwrap();
+        let runner = TestRunner::new(dir.path()).with_event_sender(tx);
+
+        let result = runner.run_specific(&[]).unwrap();
+        assert_eq!(result.total(), 0);
+        assert!(result.success);
+        assert!(matches!(rx.try_recv(), Ok(TestEvent::AllCompleted { passed: 0, .. })));
+    }
+
+    #[test]
+    fn test_cancel_marks_in_flight_tests() {
+        let stream = r#"{ "type": "test", "event": "started", "name": "a::done" }
+{ "type": "test", "name": "a::done", "event": "ok", "exec_time": 0.001 }
+{ "type": "test", "event": "started", "name": "a::slow" }
+"#;
+
+        let (tx, rx) = create_event_channel();
+        let runner = TestRunner::new(Path::new("."))
+            .with_format(OutputFormat::Json)
+            .with_event_sender(tx);
+        runner.cancel_handle().cancel();
+        let result = runner.parse_json_stream(stream.as_bytes(), &InFlight::default()).unwrap();
+
+        assert_eq!(result.passed, 1);
+        assert_eq!(result.test_results[1].name, "a::slow");
+        assert_eq!(result.test_results[1].status, TestStatus::Cancelled);
+
+        drop(runner);
+        assert!(rx.iter().any(|e| matches!(e,
+            TestEvent::Completed { ref test_name, status: TestStatus::Cancelled, .. } if test_name == "a::slow")));
+    }
+
+    #[test]
+    fn test_cancelled_runner_spawns_nothing() {
+        let cancel = CancelHandle::new();
+        cancel.cancel();
+        assert!(cancel.is_cancelled());
+
+        let runner = TestRunner::new(Path::new(".")).with_cancel_handle(cancel);
+        let (result, killed) = runner.run_process(Command::new("definitely-not-a-command"), None).unwrap();
+        assert!(result.cancelled);
+        assert!(!killed);
+        assert!(!result.success);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hung_test_is_killed() {
+        let (tx, rx) = create_event_channel();
+        let runner = TestRunner::new(Path::new("."))
+            .with_backend(TestBackend::Cargo)
+            .with_format(OutputFormat::Text)
+            .with_timeouts(Timeouts {
+                test: Some(Duration::from_millis(500)),
+                slow: Duration::from_millis(100),
+                run: None,
+            })
+            .with_event_sender(tx);
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo 'test a::quick ... ok'; printf 'test a::hang ... '; sleep 30"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let start = Instant::now();
+        let (result, killed) = runner.run_process(cmd, None).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(killed);
+        assert!(!result.success);
+        assert_eq!((result.passed, result.failed), (1, 1));
+        assert_eq!(result.test_results[1].name, "a::hang");
+        assert_eq!(result.test_results[1].status, TestStatus::TimedOut);
+
+        drop(runner);
+        assert!(rx.iter().any(|e| matches!(e, TestEvent::Slow { ref test_name, .. } if test_name == "a::hang")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_alone_is_killed_when_hung() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A "test binary" that hangs
+        let dir = tempfile::TempDir::new().unwrap();
+        let executable = dir.path().join("hangs");
+        std::fs::write(&executable, "#!/bin/sh\nprintf \"test a::hang ... \"\nsleep 30\n").unwrap();
+        std::fs::set_permissions(&executable, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let binary = TestBinary {
+            target: TestTarget { package: "core".to_string(), name: "core".to_string(), kind: TargetKind::Lib },
+            executable,
+            package_dir: dir.path().to_path_buf(),
+        };
+
+        let runner = TestRunner::new(Path::new("."))
+            .with_backend(TestBackend::Cargo)
+            .with_format(OutputFormat::Text)
+            .with_test_timeout(Duration::from_millis(300))
+            .detached();
+        let start = Instant::now();
+        let result = runner.run_alone(&binary, "a::hang", &[("LLVM_PROFILE_FILE", dir.path())], runner.deadline()).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(result.test_results[0].status, TestStatus::TimedOut);
+        assert_eq!(result.test_results[0].target, Some(binary.target));
+    }
+
+    #[test]
+    fn test_run_deadline_times_out_in_flight() {
+        let in_flight = InFlight::default();
+        in_flight.start(None, "a::slow");
+        in_flight.lock().run_expired = true;
+
+        let runner = TestRunner::new(Path::new(".")).with_format(OutputFormat::Text);
+        let result = runner.parse_text_stream("test a::slow ...\n".as_bytes(), &in_flight).unwrap();
+        assert_eq!(result.failed, 1);
+        assert_eq!(result.test_results[0].status, TestStatus::TimedOut);
+
+        // Nothing is spawned once the deadline has passed
+        let (result, _) = runner.run_process(Command::new("definitely-not-a-command"), Some(Instant::now())).unwrap();
+        assert!(result.timed_out);
+    }
+
+    #[test]
+    fn test_stream_lines_yields_started_fragment() {
+        let lines: Vec<String> = StreamLines::new("running 1 test\ntest a::slow ... ".as_bytes())
+            .map(|l| l.unwrap())
+            .collect();
+        assert_eq!(lines, vec!["running 1 test", "test a::slow ... "]);
+
+        let runner = TestRunner::new(Path::new(".")).with_format(OutputFormat::Text);
+        let result = runner
+            .parse_text_stream("test a::slow ... ok\n".as_bytes(), &InFlight::default())
+            .unwrap();
+        assert_eq!(result.passed, 1);
+    }
+
+    fn failed(name: &str, output: &str) -> SingleTestResult {
+        SingleTestResult {
+            name: name.to_string(),
+            status: TestStatus::Failed,
+            duration_ms: Some(10),
+            output: vec![output.to_string()],
+            ignore_reason: None,
+            failure: None,
+            target: None,
+            attempts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let config = RetryConfig {
+            count: Some(1),
+            on: vec!["Connection refused".to_string()],
+            tests: BTreeMap::from([("net::".to_string(), 2), ("net::fetch".to_string(), 4)]),
+        };
+        let policy = RetryPolicy::from_config(&config, None);
+        assert_eq!(policy.budget("db::query"), 1);
+        assert_eq!(policy.budget("net::send"), 2);
+        assert_eq!(policy.budget("net::fetch_all"), 4);
+        assert_eq!(policy.max_retries(), 4);
+        assert_eq!(RetryPolicy::from_config(&config, Some(0)).budget("db::query"), 0);
+
+        let mut result = TestRunResult::default();
+        result.test_results.push(failed("db::query", "error: connection refused (os error 111)"));
+        result.test_results.push(failed("db::assert", "assertion `left == right` failed"));
+        assert_eq!(policy.to_retry(&result), vec!["db::query"]);
+        result.test_results[0].attempts.push(TestAttempt {
+            status: TestStatus::Failed,
+            duration_ms: None,
+            output: Vec::new(),
+            failure: None,
+        });
+        assert!(policy.to_retry(&result).is_empty());
+    }
+
+    #[test]
+    fn test_merge_retry() {
+        let mut result = TestRunResult { failed: 2, ..Default::default() };
+        result.test_results.push(failed("a::flaky", "first"));
+        result.test_results.push(failed("a::broken", "first"));
+
+        // Every failure passing on retry makes the run a success
+        let mut all_pass = result.clone();
+        let mut retry = TestRunResult { passed: 2, ..Default::default() };
+        for name in ["a::flaky", "a::broken"] {
+            retry.test_results.push(SingleTestResult { status: TestStatus::Passed, output: Vec::new(), ..failed(name, "") });
+        }
+        let mut cancelled = all_pass.clone();
+        all_pass.merge_retry(retry.clone());
+        assert!(all_pass.success);
+        // Unless the retry was cut short
+        cancelled.merge_retry(TestRunResult { cancelled: true, ..retry });
+        assert!(!cancelled.success);
+
+        let mut retry = TestRunResult { passed: 1, failed: 1, ..Default::default() };
+        retry.test_results.push(SingleTestResult { status: TestStatus::Passed, output: Vec::new(), ..failed("a::flaky", "") });
+        retry.test_results.push(failed("a::broken", "second"));
+        result.merge_retry(retry);
+
+        assert_eq!((result.passed, result.failed), (1, 1));
+        assert!(!result.success);
+        let flaky = &result.test_results[0];
+        assert!(flaky.passed_on_retry());
+        assert_eq!(flaky.attempts[0].output, vec!["first"]);
+        let broken = &result.test_results[1];
+        assert!(!broken.passed_on_retry());
+        assert_eq!((broken.attempts.len(), broken.output[0].as_str()), (1, "second"));
+        assert_eq!(result.passed_on_retry().len(), 1);
     }
 
     #[test]
@@ -0,0 +1,46 @@
+//! Text sparklines
+//!
+//! Compact one-line views of a test's history for the CLI.
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One bar per value, scaled between the smallest and largest value
+pub fn sparkline(values: &[u64]) -> String {
+    let (Some(&min), Some(&max)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    let range = (max - min).max(1) as f64;
+    values.iter()
+        .map(|&v| BARS[((v - min) as f64 / range * (BARS.len() - 1) as f64).round() as usize])
+        .collect()
+}
+
+/// One mark per outcome: `✓` passed, `✗` failed, `↻` passed on retry and
+/// `·` anything else
+pub fn outcome_strip<'a>(statuses: impl IntoIterator<Item = &'a str>) -> String {
+    statuses.into_iter()
+        .map(|status| match status {
+            "passed" => '✓',
+            "failed" | "timed_out" => '✗',
+            crate::db::PASSED_ON_RETRY => '↻',
+            _ => '·',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[5, 5]), "▁▁");
+        assert_eq!(sparkline(&[0, 70, 35, 10]), "▁█▅▂");
+    }
+
+    #[test]
+    fn test_outcome_strip() {
+        assert_eq!(outcome_strip(["passed", "failed", "passed_on_retry", "skipped"]), "✓✗↻·");
+    }
+}
@@ -2,13 +2,17 @@
 
 #![allow(dead_code)]
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::mpsc::{Receiver, Sender};
 
+use crate::config::RunxConfig;
 use crate::db::Database;
 use crate::discovery::{discover_all_tests, get_project_name};
-use crate::test_model::{FilterMode, Test, TestNode, TestStats, TestStatus};
-use crate::test_runner::{create_event_channel, run_tests_async, TestEvent};
+use crate::editor::{configured_editor, editor_command, Location};
+use crate::test_model::{FilterMode, Test, TestNode, TestScope, TestStats, TestStatus};
+use crate::test_runner::{create_event_channel, run_scoped_async, run_specific_async, CancelHandle, TestEvent};
 use crate::tui::widgets::{TestTreeState, toggle_node_expansion, expand_all, collapse_all};
 
 /// Focus area in the UI
@@ -42,6 +46,8 @@ pub struct App {
     pub filter_input_mode: bool,
     /// Output scroll position
     pub output_scroll: usize,
+    /// Whether a failed test's raw log is expanded below its failure
+    pub show_raw_log: bool,
     /// Currently selected test (for output display)
     pub selected_test: Option<String>,
     /// Test statistics
@@ -52,6 +58,11 @@ pub struct App {
     pub event_rx: Option<Receiver<TestEvent>>,
     /// Event sender (kept to pass to runner)
     event_tx: Option<Sender<TestEvent>>,
+    /// Cancels the current run
+    cancel: Option<CancelHandle>,
+    /// Id of the test each name last started as in the current run, so that
+    /// output printed after a test finished reaches the right target
+    started: HashMap<String, String>,
     /// Status message
     pub status_message: Option<String>,
     /// Discovery in progress
@@ -76,11 +87,14 @@ impl App {
             focus: Focus::TestList,
             filter_input_mode: false,
             output_scroll: 0,
+            show_raw_log: false,
             selected_test: None,
             stats: TestStats::default(),
             running: false,
             event_rx: None,
             event_tx: None,
+            cancel: None,
+            started: HashMap::new(),
             status_message: Some("Press 'd' to discover tests".to_string()),
             discovering: false,
         }
@@ -116,8 +130,8 @@ impl App {
     /// Get the currently selected test
     pub fn selected_test(&self) -> Option<&Test> {
         if let Some(item) = self.tree_state.selected_item() {
-            if let Some(ref full_name) = item.full_name {
-                return self.test_tree.find_test(full_name);
+            if let Some(ref id) = item.test_id {
+                return self.test_tree.find_test_by_id(id);
             }
         }
         None
@@ -125,15 +139,39 @@ impl App {
 
     /// Get the currently selected test (mutable)
     pub fn selected_test_mut(&mut self) -> Option<&mut Test> {
-        let full_name = self.tree_state.selected_item()
-            .and_then(|item| item.full_name.clone());
+        let id = self.tree_state.selected_item()
+            .and_then(|item| item.test_id.clone());
 
-        if let Some(name) = full_name {
-            return self.test_tree.find_test_mut(&name);
+        if let Some(id) = id {
+            return self.test_tree.find_test_by_id_mut(&id);
         }
         None
     }
 
+    /// Command opening the selected test in the user's editor, at its panic
+    /// location if it failed; sets a status message when there is none
+    pub fn editor_command(&mut self) -> Option<Command> {
+        let location = match self.selected_test() {
+            Some(test) => Location::of_test(test, &self.project_dir),
+            None => {
+                self.status_message = Some("Select a test to open it".to_string());
+                return None;
+            }
+        };
+        let Some(location) = location else {
+            self.status_message = Some("Source location unknown for this test".to_string());
+            return None;
+        };
+
+        let config = RunxConfig::load(&self.project_dir).unwrap_or_default();
+        let command = configured_editor(&config.editor)
+            .and_then(|editor| editor_command(&editor, &location));
+        if command.is_none() {
+            self.status_message = Some("Set $VISUAL or $EDITOR to open tests".to_string());
+        }
+        command
+    }
+
     /// Move selection up
     pub fn select_prev(&mut self) {
         self.tree_state.up();
@@ -201,6 +239,11 @@ impl App {
     }
 
     /// Cycle filter mode
+    pub fn toggle_raw_log(&mut self) {
+        self.show_raw_log = !self.show_raw_log;
+        self.output_scroll = 0;
+    }
+
     pub fn cycle_filter_mode(&mut self) {
         self.filter_mode = self.filter_mode.cycle_next();
     }
@@ -246,23 +289,24 @@ impl App {
         }
 
         if let Some(item) = self.tree_state.selected_item().cloned() {
-            if let Some(full_name) = item.full_name {
-                self.run_test(&full_name);
+            if let Some(test) = item.test_id.and_then(|id| self.test_tree.find_test_by_id(&id)) {
+                let test = test.clone();
+                self.run_test(&test);
             } else if item.is_module {
-                // Run all tests in module
-                self.run_filtered(&item.path.join("::"));
+                // Run all tests in the package, target or module
+                self.run_node(&item.path);
             }
         }
     }
 
     /// Run a single test
-    pub fn run_test(&mut self, test_name: &str) {
+    pub fn run_test(&mut self, test: &Test) {
         if self.running {
             return;
         }
 
         // Reset test status
-        if let Some(test) = self.test_tree.find_test_mut(test_name) {
+        if let Some(test) = self.test_tree.find_test_by_id_mut(&test.id) {
             test.status = TestStatus::Running;
             test.output.clear();
         }
@@ -275,9 +319,53 @@ impl App {
 
         // Start test runner in background
         self.running = true;
-        self.status_message = Some(format!("Running: {}", test_name));
+        self.status_message = Some(format!("Running: {}", test.full_name));
 
-        let _handle = run_tests_async(&self.project_dir, Some(test_name.to_string()), tx);
+        let scope = test.target.as_ref().map(TestScope::target).unwrap_or_default();
+        let handle = run_scoped_async(&self.project_dir, scope, Some(test.full_name.clone()), tx);
+        self.cancel = Some(handle.cancel);
+    }
+
+    /// Run every test under a tree node: a package, a target or a module
+    pub fn run_node(&mut self, path: &[String]) {
+        if self.running {
+            return;
+        }
+
+        let Some(node) = self.test_tree.find_node(path) else {
+            return;
+        };
+        let tests: Vec<Test> = node.all_tests().into_iter().cloned().collect();
+        let Some(target) = tests.first().and_then(|t| t.target.clone()) else {
+            // Tree without package targets: modules only
+            self.run_filtered(&path.join("::"));
+            return;
+        };
+
+        let (scope, filter) = match path {
+            [_] => (TestScope { packages: vec![target.package], targets: Vec::new() }, None),
+            [_, _] => (TestScope::target(&target), None),
+            [_, _, modules @ ..] => (TestScope::target(&target), Some(modules.join("::"))),
+            [] => (TestScope::default(), None),
+        };
+
+        for test in &tests {
+            if let Some(t) = self.test_tree.find_test_by_id_mut(&test.id) {
+                t.status = TestStatus::Running;
+                t.output.clear();
+            }
+        }
+        self.test_tree.update_counts();
+
+        let (tx, rx) = create_event_channel();
+        self.event_tx = Some(tx.clone());
+        self.event_rx = Some(rx);
+
+        self.running = true;
+        self.status_message = Some(format!("Running tests in: {}", path.join(" › ")));
+
+        let handle = run_scoped_async(&self.project_dir, scope, filter, tx);
+        self.cancel = Some(handle.cancel);
     }
 
     /// Run tests matching filter
@@ -288,18 +376,12 @@ impl App {
 
         // Mark matching tests as running
         let filter_lower = filter.to_lowercase();
-        let matching_names: Vec<String> = self.test_tree.all_tests()
-            .iter()
-            .filter(|t| t.full_name.to_lowercase().contains(&filter_lower))
-            .map(|t| t.full_name.clone())
-            .collect();
-
-        for name in matching_names {
-            if let Some(t) = self.test_tree.find_test_mut(&name) {
+        self.test_tree.for_each_test_mut(&mut |t| {
+            if t.full_name.to_lowercase().contains(&filter_lower) {
                 t.status = TestStatus::Running;
                 t.output.clear();
             }
-        }
+        });
         self.test_tree.update_counts();
 
         // Create event channel
@@ -311,7 +393,8 @@ impl App {
         self.running = true;
         self.status_message = Some(format!("Running tests matching: {}", filter));
 
-        let _handle = run_tests_async(&self.project_dir, Some(filter.to_string()), tx);
+        let handle = run_scoped_async(&self.project_dir, TestScope::default(), Some(filter.to_string()), tx);
+        self.cancel = Some(handle.cancel);
     }
 
     /// Run all tests
@@ -321,12 +404,10 @@ impl App {
         }
 
         // Mark all tests as running
-        for name in self.test_tree.all_test_names() {
-            if let Some(test) = self.test_tree.find_test_mut(&name) {
-                test.status = TestStatus::Running;
-                test.output.clear();
-            }
-        }
+        self.test_tree.for_each_test_mut(&mut |test| {
+            test.status = TestStatus::Running;
+            test.output.clear();
+        });
         self.test_tree.update_counts();
 
         // Create event channel
@@ -338,7 +419,8 @@ impl App {
         self.running = true;
         self.status_message = Some("Running all tests...".to_string());
 
-        let _handle = run_tests_async(&self.project_dir, None, tx);
+        let handle = run_scoped_async(&self.project_dir, TestScope::default(), None, tx);
+        self.cancel = Some(handle.cancel);
     }
 
     /// Run failed tests
@@ -347,7 +429,7 @@ impl App {
             return;
         }
 
-        let failed_names: Vec<String> = self.test_tree
+        let mut failed_names: Vec<String> = self.test_tree
             .failed_tests()
             .iter()
             .map(|t| t.full_name.clone())
@@ -359,12 +441,14 @@ impl App {
         }
 
         // Mark failed tests as running
-        for name in &failed_names {
-            if let Some(test) = self.test_tree.find_test_mut(name) {
+        self.test_tree.for_each_test_mut(&mut |test| {
+            if test.status.is_failure() {
                 test.status = TestStatus::Running;
                 test.output.clear();
             }
-        }
+        });
+        failed_names.sort();
+        failed_names.dedup();
         self.test_tree.update_counts();
 
         // Create event channel
@@ -375,10 +459,19 @@ impl App {
         self.running = true;
         self.status_message = Some(format!("Running {} failed tests...", failed_names.len()));
 
-        // Run each failed test
-        for name in &failed_names {
-            // This is a simplification - ideally we'd batch these
-            let _handle = run_tests_async(&self.project_dir, Some(name.clone()), tx.clone());
+        // Run all failed tests in one batched invocation
+        let handle = run_specific_async(&self.project_dir, failed_names, tx);
+        self.cancel = Some(handle.cancel);
+    }
+
+    /// Cancel the current run, killing its test processes
+    pub fn cancel_run(&mut self) {
+        if !self.running {
+            return;
+        }
+        if let Some(ref cancel) = self.cancel {
+            cancel.cancel();
+            self.status_message = Some("Cancelling...".to_string());
         }
     }
 
@@ -389,18 +482,26 @@ impl App {
             while let Ok(event) = rx.try_recv() {
                 match event {
                     TestEvent::Started { test_name } => {
-                        if let Some(test) = self.test_tree.find_test_mut(&test_name) {
+                        if let Some(test) = event_test_mut(&mut self.test_tree, &test_name) {
                             test.status = TestStatus::Running;
+                            self.started.insert(test_name.clone(), test.id.clone());
                         }
                         self.status_message = Some(format!("Running: {}", test_name));
                     }
                     TestEvent::Output { test_name, line } => {
-                        if let Some(test) = self.test_tree.find_test_mut(&test_name) {
+                        if let Some(test) = started_test_mut(&mut self.test_tree, &self.started, &test_name) {
                             test.add_output(line);
                         }
                     }
+                    TestEvent::Slow { test_name, elapsed_ms } => {
+                        self.status_message = Some(format!(
+                            "Slow: {} still running after {}s",
+                            test_name,
+                            elapsed_ms / 1000
+                        ));
+                    }
                     TestEvent::Completed { test_name, status, duration_ms } => {
-                        if let Some(test) = self.test_tree.find_test_mut(&test_name) {
+                        if let Some(test) = started_test_mut(&mut self.test_tree, &self.started, &test_name) {
                             test.status = status;
                             test.duration_ms = Some(duration_ms);
                             test.last_run = Some(chrono::Utc::now());
@@ -408,10 +509,24 @@ impl App {
                     }
                     TestEvent::AllCompleted { passed, failed, ignored } => {
                         self.running = false;
-                        self.status_message = Some(format!(
-                            "Completed: {} passed, {} failed, {} ignored",
-                            passed, failed, ignored
-                        ));
+                        self.started.clear();
+                        if self.cancel.take().is_some_and(|c| c.is_cancelled()) {
+                            // Tests that never reported a result were cut short
+                            self.test_tree.for_each_test_mut(&mut |test| {
+                                if test.status == TestStatus::Running {
+                                    test.status = TestStatus::Cancelled;
+                                }
+                            });
+                            self.status_message = Some(format!(
+                                "Cancelled: {} passed, {} failed, {} ignored",
+                                passed, failed, ignored
+                            ));
+                        } else {
+                            self.status_message = Some(format!(
+                                "Completed: {} passed, {} failed, {} ignored",
+                                passed, failed, ignored
+                            ));
+                        }
                     }
                     TestEvent::Error { message } => {
                         self.running = false;
@@ -434,3 +549,25 @@ impl App {
     }
 }
 
+/// Test an event refers to. The same name can exist in several targets,
+/// so tests that are part of the current run are preferred.
+fn event_test_mut<'a>(tree: &'a mut TestNode, name: &str) -> Option<&'a mut Test> {
+    let mut tests = tree.find_tests_mut(name);
+    let index = tests.iter()
+        .position(|t| t.status == TestStatus::Running)
+        .unwrap_or(0);
+    (index < tests.len()).then(|| tests.swap_remove(index))
+}
+
+/// Test an event refers to, preferring the one that started under this name:
+/// libtest prints failure output once the test has already finished
+fn started_test_mut<'a>(
+    tree: &'a mut TestNode,
+    started: &HashMap<String, String>,
+    name: &str,
+) -> Option<&'a mut Test> {
+    match started.get(name) {
+        Some(id) if tree.find_test_by_id(id).is_some() => tree.find_test_by_id_mut(id),
+        _ => event_test_mut(tree, name),
+    }
+}
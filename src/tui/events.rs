@@ -1,6 +1,7 @@
 //! TUI event handling for Test Explorer
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use std::process::Command;
 use std::time::Duration;
 
 use super::App;
@@ -10,6 +11,8 @@ use crate::test_model::FilterMode;
 pub enum EventResult {
     Continue,
     Quit,
+    /// Suspend the UI while the editor runs
+    OpenEditor(Command),
 }
 
 /// Poll for an event with timeout
@@ -118,6 +121,18 @@ fn handle_key_event(key: KeyEvent, app: &mut App) -> EventResult {
             let _ = app.discover_tests();
             EventResult::Continue
         }
+        KeyCode::Char('x') => {
+            app.cancel_run();
+            EventResult::Continue
+        }
+        KeyCode::Char('r') => {
+            app.toggle_raw_log();
+            EventResult::Continue
+        }
+        KeyCode::Char('o') => match app.editor_command() {
+            Some(command) => EventResult::OpenEditor(command),
+            None => EventResult::Continue,
+        },
 
         // Filter
         KeyCode::Char('/') => {
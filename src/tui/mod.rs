@@ -28,10 +28,8 @@ use crate::db::Database;
 /// Run the TUI application
 pub fn run_tui(project_dir: &Path, db: Option<Database>) -> Result<()> {
     // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    enter_terminal()?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
@@ -43,18 +41,28 @@ pub fn run_tui(project_dir: &Path, db: Option<Database>) -> Result<()> {
     // Run main loop
     let result = run_app(&mut terminal, &mut app);
 
+    // Don't leave test processes running behind the closed UI
+    app.cancel_run();
+
     // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    leave_terminal()?;
     terminal.show_cursor()?;
 
     result
 }
 
+/// Switch the terminal to raw mode on the alternate screen
+fn enter_terminal() -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
+}
+
+/// Give the terminal back to the shell (or to a child process)
+fn leave_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)
+}
+
 /// Main application loop
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
     loop {
@@ -64,6 +72,18 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
             match events::handle_event(event, app) {
                 EventResult::Continue => {}
                 EventResult::Quit => break,
+                EventResult::OpenEditor(mut command) => {
+                    // The editor owns the terminal until it exits
+                    leave_terminal()?;
+                    let status = command.status();
+                    enter_terminal()?;
+                    terminal.clear()?;
+                    app.status_message = Some(match status {
+                        Ok(status) if status.success() => "Editor closed".to_string(),
+                        Ok(status) => format!("Editor exited with {}", status),
+                        Err(e) => format!("Failed to start editor: {}", e),
+                    });
+                }
             }
         }
 
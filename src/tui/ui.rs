@@ -2,11 +2,13 @@
 
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Clear, Gauge, Paragraph, Wrap},
+    widgets::{block::{Position, Title}, Block, Borders, Clear, Gauge, Paragraph, Wrap},
 };
 
 use super::app::{App, Focus};
 use super::widgets::TestTree;
+use crate::diff::{DiffRow, Segment};
+use crate::failure::TestFailure;
 use crate::test_model::FilterMode;
 
 /// Draw the entire UI
@@ -167,47 +169,60 @@ fn draw_test_tree(frame: &mut Frame, app: &mut App, area: Rect) {
 }
 
 fn draw_output_viewer(frame: &mut Frame, app: &App, area: Rect) {
-    let (title, content) = if let Some(test) = app.selected_test() {
+    let (title, location, content) = if let Some(test) = app.selected_test() {
         let status_symbol = test.status.symbol();
         let duration = test.duration_ms
             .map(|d| format!(" ({}ms)", d))
             .unwrap_or_default();
 
         let title = format!(" {} {} {} ", status_symbol, test.short_name, duration);
+        let location = test.location().map(|l| format!(" {} ", l)).unwrap_or_default();
 
-        let content = if test.output.is_empty() {
-            match test.status {
+        let failure = match test.status {
+            crate::test_model::TestStatus::Failed => TestFailure::parse(&test.output),
+            _ => None,
+        };
+
+        let content = if let Some(failure) = failure {
+            failure_text(&failure, &test.output, app, area.width.saturating_sub(2) as usize)
+        } else if test.output.is_empty() {
+            Text::from(match test.status {
                 crate::test_model::TestStatus::Pending => "Test not yet run.\n\nPress Enter to run.".to_string(),
                 crate::test_model::TestStatus::Running => "Running...".to_string(),
                 crate::test_model::TestStatus::Passed => "Test passed (no output)".to_string(),
                 crate::test_model::TestStatus::Failed => "Test failed (no output captured)".to_string(),
-                crate::test_model::TestStatus::Ignored => "Test is ignored (#[ignore])".to_string(),
-            }
+                crate::test_model::TestStatus::Ignored => match test.ignore_reason {
+                    Some(ref reason) => format!("Test is ignored: {}", reason),
+                    None => "Test is ignored (#[ignore])".to_string(),
+                },
+                crate::test_model::TestStatus::Cancelled => "Test was cancelled before it finished".to_string(),
+                crate::test_model::TestStatus::TimedOut => "Test timed out and was killed".to_string(),
+            })
         } else {
             let output: Vec<&str> = test.output.iter()
                 .skip(app.output_scroll)
                 .map(|s| s.as_str())
                 .collect();
-            output.join("\n")
+            Text::from(output.join("\n"))
         };
 
-        (title, content)
+        (title, location, content)
     } else if let Some(item) = app.tree_state.selected_item() {
         if item.is_module {
             let title = format!(" {} ", item.name);
             let content = format!(
                 "Module: {}\n\nTests: {}\nPassed: {}\nFailed: {}\n\nPress Enter to run all tests in this module.",
-                item.path.join("::"),
+                item.path.join(" › "),
                 item.test_count,
                 item.passed_count,
                 item.failed_count
             );
-            (title, content)
+            (title, String::new(), Text::from(content))
         } else {
-            (" Output ".to_string(), "Select a test to view output".to_string())
+            (" Output ".to_string(), String::new(), Text::from("Select a test to view output"))
         }
     } else {
-        (" Output ".to_string(), "No test selected".to_string())
+        (" Output ".to_string(), String::new(), Text::from("No test selected"))
     };
 
     let border_style = if app.focus == Focus::Output {
@@ -220,13 +235,105 @@ fn draw_output_viewer(frame: &mut Frame, app: &App, area: Rect) {
         .block(Block::default()
             .borders(Borders::ALL)
             .border_style(border_style)
-            .title(title))
+            .title(title)
+            .title(Title::from(location.dark_gray()).position(Position::Bottom).alignment(Alignment::Right)))
         .wrap(Wrap { trim: false })
         .style(Style::default().fg(Color::Gray));
 
     frame.render_widget(paragraph, area);
 }
 
+/// Failure headline (message, location, assertion operands) followed by the
+/// raw log, collapsed unless toggled with `r`
+fn failure_text(failure: &TestFailure, output: &[String], app: &App, width: usize) -> Text<'static> {
+    let mut lines = vec![Line::from(format!("✗ {}", failure.headline()).red().bold())];
+    if let Some(ref location) = failure.location {
+        lines.push(Line::from(format!(
+            "  at {}:{}:{}",
+            location.file.display(),
+            location.line,
+            location.column.unwrap_or(1)
+        ).dark_gray()));
+    }
+
+    match (failure.diff(), &failure.left, &failure.right) {
+        (Some(rows), _, _) if width >= MIN_DIFF_WIDTH => {
+            lines.push(Line::from(""));
+            lines.extend(diff_lines(&rows, width));
+        }
+        (_, Some(left), Some(right)) => {
+            for (label, value, color) in [("left: ", left, Color::Red), ("right:", right, Color::Green)] {
+                for (i, line) in value.lines().enumerate() {
+                    let label = if i == 0 { label } else { "      " };
+                    lines.push(Line::from(vec![
+                        Span::raw(format!("  {} ", label)).dark_gray(),
+                        Span::styled(line.to_string(), Style::default().fg(color)),
+                    ]));
+                }
+            }
+        }
+        _ => {
+            for line in failure.message.lines().skip(1) {
+                lines.push(Line::from(format!("  {}", line)));
+            }
+        }
+    }
+
+    lines.push(Line::from(""));
+    if app.show_raw_log {
+        lines.push(Line::from("▾ Raw log ([r] to collapse)".dark_gray()));
+        lines.extend(output.iter().skip(app.output_scroll).map(|l| Line::from(l.clone())));
+    } else {
+        lines.push(Line::from(format!("▸ Raw log: {} lines ([r] to expand)", output.len()).dark_gray()));
+    }
+
+    Text::from(lines)
+}
+
+/// Narrowest output pane that still fits a side-by-side diff
+const MIN_DIFF_WIDTH: usize = 30;
+
+/// `left │ right` columns of an `assert_eq!` diff, changed characters
+/// highlighted
+fn diff_lines(rows: &[DiffRow], width: usize) -> Vec<Line<'static>> {
+    let column = (width - 3) / 2;
+    let separator = || Span::raw(" │ ").dark_gray();
+
+    let mut lines = vec![Line::from(vec![
+        Span::raw(format!("{:<column$}", "left")).red().bold(),
+        separator(),
+        Span::raw("right").green().bold(),
+    ])];
+    for row in rows {
+        let equal = row.is_equal();
+        let mut spans = diff_cell(row.left.as_deref(), column, Color::Red, equal);
+        spans.push(separator());
+        spans.extend(diff_cell(row.right.as_deref(), column, Color::Green, equal));
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+/// One side of a diff row, truncated and padded to `width` characters
+fn diff_cell(segments: Option<&[Segment]>, width: usize, color: Color, equal: bool) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut used = 0;
+    for segment in segments.unwrap_or_default() {
+        let text: String = segment.text.chars().take(width - used).collect();
+        used += text.chars().count();
+        let style = if equal {
+            Style::default().fg(Color::DarkGray)
+        } else if segment.changed {
+            Style::default().fg(Color::Black).bg(color)
+        } else {
+            Style::default().fg(color)
+        };
+        spans.push(Span::styled(text, style));
+    }
+    spans.push(Span::raw(" ".repeat(width - used)));
+    spans
+}
+
 fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -240,9 +347,9 @@ fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
     let help = if app.filter_input_mode {
         " [Enter] apply [Esc] cancel "
     } else if app.running {
-        " [j/k] nav [Tab] focus [q] quit "
+        " [j/k] nav [x] cancel [Tab] focus [q] quit "
     } else {
-        " [j/k] nav [Enter] run [a] all [f] failed [d] discover [/] filter [q] quit "
+        " [j/k] nav [Enter] run [a] all [f] failed [o] open [r] log [d] discover [/] filter [q] quit "
     };
 
     let help_text = Paragraph::new(help)
@@ -57,8 +57,8 @@ impl TestTreeState {
 pub struct TreeItem {
     /// Display name
     pub name: String,
-    /// Full test name (if this is a test)
-    pub full_name: Option<String>,
+    /// Unique test ID (if this is a test)
+    pub test_id: Option<String>,
     /// Indentation level
     pub depth: usize,
     /// Whether this is a module (can be expanded)
@@ -113,7 +113,10 @@ impl<'a> TestTree<'a> {
     /// Flatten the tree into visible items
     fn flatten_tree(&self) -> Vec<TreeItem> {
         let mut items = Vec::new();
-        self.flatten_node(self.tree, 0, &mut items, Vec::new());
+        // Skip root node, start with its children
+        for child in &self.tree.children {
+            self.flatten_node(child, 0, &mut items, vec![child.name.clone()]);
+        }
         items
     }
 
@@ -124,16 +127,6 @@ impl<'a> TestTree<'a> {
         items: &mut Vec<TreeItem>,
         path: Vec<String>,
     ) {
-        // Skip root node, start with its children
-        if depth == 0 {
-            for child in &node.children {
-                let mut child_path = path.clone();
-                child_path.push(child.name.clone());
-                self.flatten_node(child, 0, items, child_path);
-            }
-            return;
-        }
-
         // Check if this node matches the filter
         let matches_filter = if self.filter.is_empty() {
             true
@@ -161,7 +154,7 @@ impl<'a> TestTree<'a> {
 
         let item = TreeItem {
             name: node.name.clone(),
-            full_name: node.test.as_ref().map(|t| t.full_name.clone()),
+            test_id: node.test.as_ref().map(|t| t.id.clone()),
             depth,
             is_module: node.is_module(),
             expanded: node.expanded,
@@ -8,12 +8,14 @@ use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
 use std::path::Path;
 use std::sync::mpsc::{self, Receiver};
 use std::time::{Duration, Instant};
+use uuid::Uuid;
 
 use crate::affected::find_affected_from_files;
-use crate::db::Database;
+use crate::config::RunxConfig;
+use crate::db::{Database, QuarantinePolicy};
 use crate::discovery::discover_all_tests;
-use crate::test_model::TestNode;
-use crate::test_runner::TestRunner;
+use crate::test_model::{TestNode, TestStatus};
+use crate::test_runner::{TestRunner, TestRunResult};
 
 const DEBOUNCE_MS: u128 = 300;
 const EXCLUDED_DIRS: &[&str] = &["target", "node_modules", "dist", "out", ".git"];
@@ -22,8 +24,8 @@ const EXCLUDED_DIRS: &[&str] = &["target", "node_modules", "dist", "out", ".git"
 pub struct TestWatcher<'a> {
     project_dir: &'a Path,
     test_filter: Option<String>,
-    #[allow(dead_code)]
-    db: Option<Database>, // Reserved for future: saving watch results to DB
+    /// Where re-runs are recorded, if available
+    db: Option<Database>,
     test_tree: Option<TestNode>,
 }
 
@@ -122,12 +124,14 @@ impl<'a> TestWatcher<'a> {
 
             last_run = Instant::now();
 
-            // Re-discover tests if Cargo.toml changed
             if changed_files.iter().any(|f| f.ends_with("Cargo.toml")) {
                 println!("\n{} Cargo.toml changed, re-discovering tests...", "↻".yellow());
-                if let Ok(tree) = discover_all_tests(self.project_dir) {
-                    self.test_tree = Some(tree);
-                }
+            }
+
+            // Rebuilding the test binaries is needed to run them anyway, and the
+            // runner reuses that build, so new tests are picked up for free
+            if let Ok(tree) = discover_all_tests(self.project_dir) {
+                self.test_tree = Some(tree);
             }
 
             self.run_affected_tests(&changed_files)?;
@@ -144,9 +148,12 @@ impl<'a> TestWatcher<'a> {
             changed_files.join(", ").dimmed()
         );
 
-        // Find affected tests
+        // Find affected tests, precisely for those with a coverage map
         let affected = if let Some(ref tree) = self.test_tree {
-            find_affected_from_files(changed_files, tree, self.project_dir)
+            let coverage = self.db.as_ref()
+                .and_then(|db| db.get_test_coverage().ok())
+                .unwrap_or_default();
+            find_affected_from_files(changed_files, tree, self.project_dir, &coverage)
         } else {
             Vec::new()
         };
@@ -172,34 +179,30 @@ impl<'a> TestWatcher<'a> {
             tests_to_run.len()
         );
 
-        // Run tests
+        // Run exactly the affected tests, in a single invocation per binary
         let runner = TestRunner::new(self.project_dir);
-
-        // For efficiency, we run all affected tests at once using filter
-        // This is faster than running them individually
-        let filter = if tests_to_run.len() == 1 {
-            tests_to_run[0].clone()
-        } else {
-            // Cargo test supports multiple filters separated by spaces
-            // But for exact matching, we use the first common prefix
-            find_common_prefix(&tests_to_run)
-        };
-
-        let result = runner.run_filtered(&filter)?;
+        let result = runner.run_specific(&tests_to_run)?;
+        if let Err(e) = self.save_results(&result) {
+            eprintln!("{} Failed to save results: {}", "⚠".yellow(), e);
+        }
 
         // Print summary
+        let quarantined = self.db.as_ref()
+            .and_then(|db| db.quarantined_tests().ok())
+            .unwrap_or_default();
+        let blocking = result.blocking_failures(&quarantined);
         println!();
-        if result.failed > 0 {
+        if blocking > 0 {
             println!(
                 "{} {} passed, {} failed",
                 "✗".red(),
                 result.passed.to_string().green(),
-                result.failed.to_string().red()
+                blocking.to_string().red()
             );
 
             // Show failed test names
             for test_result in &result.test_results {
-                if test_result.status == crate::test_model::TestStatus::Failed {
+                if test_result.status == TestStatus::Failed && !test_result.is_quarantined(&quarantined) {
                     println!("   {} {}", "✗".red(), test_result.name.red());
                 }
             }
@@ -211,61 +214,34 @@ impl<'a> TestWatcher<'a> {
             );
         }
 
+        for test_result in result.quarantined_failures(&quarantined) {
+            println!("   {} {} {}", "◌".yellow(), test_result.name, "(quarantined)".dimmed());
+        }
+
         println!("\n{}", "Watching for changes...".dimmed());
 
         Ok(())
     }
-}
-
-/// Find common prefix among test names for efficient filtering
-fn find_common_prefix(names: &[String]) -> String {
-    if names.is_empty() {
-        return String::new();
-    }
-
-    if names.len() == 1 {
-        return names[0].clone();
-    }
 
-    // Find shortest common prefix
-    let first = &names[0];
-    let mut prefix_len = first.len();
-
-    for name in &names[1..] {
-        let common = first.chars()
-            .zip(name.chars())
-            .take_while(|(a, b)| a == b)
-            .count();
-        prefix_len = prefix_len.min(common);
-    }
-
-    if prefix_len == 0 {
-        // No common prefix, run all tests
-        return String::new();
-    }
-
-    first[..prefix_len].to_string()
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Record a re-run as a run, with its outcomes in the test history
+    fn save_results(&self, result: &TestRunResult) -> Result<()> {
+        let Some(ref db) = self.db else {
+            return Ok(());
+        };
 
-    #[test]
-    fn test_find_common_prefix() {
-        assert_eq!(
-            find_common_prefix(&["module::test_one".to_string(), "module::test_two".to_string()]),
-            "module::test_"
-        );
+        let run_id = Uuid::new_v4().to_string();
+        db.create_run(&run_id, result.test_results.len() as i32)?;
 
-        assert_eq!(
-            find_common_prefix(&["foo::bar".to_string(), "foo::baz".to_string()]),
-            "foo::ba"
-        );
+        db.save_test_results(&run_id, result)?;
 
-        assert_eq!(
-            find_common_prefix(&["abc".to_string(), "xyz".to_string()]),
-            ""
-        );
+        let config = RunxConfig::load(self.project_dir).unwrap_or_default();
+        if let Some(policy) = QuarantinePolicy::from_config(&config.quarantine) {
+            for test in db.apply_quarantine_policy(&policy)? {
+                println!("{} {} is flaky and was quarantined", "◌".yellow(), test.label());
+            }
+        }
+        let quarantined = db.quarantined_tests()?;
+        let quarantined_failed = result.quarantined_failures(&quarantined).len() as i32;
+        db.finish_run_quarantined(&run_id, result.passed as i32, result.failed as i32, quarantined_failed)
     }
 }